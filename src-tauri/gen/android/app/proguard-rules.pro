@@ -18,4 +18,9 @@
 
 # If you keep the line number information, uncomment this to
 # hide the original source file name.
-#-renamesourcefileattribute SourceFile
\ No newline at end of file
+#-renamesourcefileattribute SourceFile
+
+# Called from the web view by name.
+-keepclassmembers class io.github.sak.read.it.later.AudioTracker {
+    @android.webkit.JavascriptInterface <methods>;
+}
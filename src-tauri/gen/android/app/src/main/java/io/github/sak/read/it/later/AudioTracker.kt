@@ -0,0 +1,78 @@
+package io.github.sak.read.it.later
+
+import android.content.BroadcastReceiver
+import android.content.Context
+import android.content.Intent
+import android.content.IntentFilter
+import android.media.AudioAttributes
+import android.media.AudioFocusRequest
+import android.media.AudioManager
+import android.webkit.JavascriptInterface
+import android.webkit.WebView
+import androidx.core.content.ContextCompat
+
+// Tracks the audio focus and headphone unplugs while reading, which the tts
+// plugin does not report, and passes them to the web view as
+// `read-later:audio` events.
+class AudioTracker(private val context: Context, private val webView: WebView) {
+  private val audioManager = context.getSystemService(AudioManager::class.java)
+  private val focusRequest = AudioFocusRequest.Builder(AudioManager.AUDIOFOCUS_GAIN)
+    .setAudioAttributes(
+      AudioAttributes.Builder()
+        .setUsage(AudioAttributes.USAGE_MEDIA)
+        .setContentType(AudioAttributes.CONTENT_TYPE_SPEECH)
+        .build()
+    )
+    .setWillPauseWhenDucked(true)
+    .setOnAudioFocusChangeListener { change ->
+      when (change) {
+        AudioManager.AUDIOFOCUS_GAIN -> dispatch("focus-gained")
+        AudioManager.AUDIOFOCUS_LOSS,
+        AudioManager.AUDIOFOCUS_LOSS_TRANSIENT,
+        AudioManager.AUDIOFOCUS_LOSS_TRANSIENT_CAN_DUCK -> dispatch("focus-lost")
+      }
+    }
+    .build()
+  private val noisyReceiver = object : BroadcastReceiver() {
+    override fun onReceive(context: Context, intent: Intent) {
+      if (intent.action == AudioManager.ACTION_AUDIO_BECOMING_NOISY) {
+        dispatch("output-lost")
+      }
+    }
+  }
+  private var holding = false
+
+  @JavascriptInterface
+  fun acquire() {
+    synchronized(this) {
+      if (holding) return
+      holding = true
+      audioManager.requestAudioFocus(focusRequest)
+      ContextCompat.registerReceiver(
+        context,
+        noisyReceiver,
+        IntentFilter(AudioManager.ACTION_AUDIO_BECOMING_NOISY),
+        ContextCompat.RECEIVER_NOT_EXPORTED
+      )
+    }
+  }
+
+  @JavascriptInterface
+  fun release() {
+    synchronized(this) {
+      if (!holding) return
+      holding = false
+      audioManager.abandonAudioFocusRequest(focusRequest)
+      context.unregisterReceiver(noisyReceiver)
+    }
+  }
+
+  private fun dispatch(change: String) {
+    webView.post {
+      webView.evaluateJavascript(
+        "window.dispatchEvent(new CustomEvent('read-later:audio', { detail: '$change' }))",
+        null
+      )
+    }
+  }
+}
@@ -1,11 +1,26 @@
 package io.github.sak.read.it.later
 
 import android.os.Bundle
+import android.webkit.WebView
 import androidx.activity.enableEdgeToEdge
 
 class MainActivity : TauriActivity() {
+  private var audioTracker: AudioTracker? = null
+
   override fun onCreate(savedInstanceState: Bundle?) {
     enableEdgeToEdge()
     super.onCreate(savedInstanceState)
   }
+
+  override fun onWebViewCreate(webView: WebView) {
+    super.onWebViewCreate(webView)
+    val tracker = AudioTracker(this, webView)
+    webView.addJavascriptInterface(tracker, "AndroidAudio")
+    audioTracker = tracker
+  }
+
+  override fun onDestroy() {
+    audioTracker?.release()
+    super.onDestroy()
+  }
 }
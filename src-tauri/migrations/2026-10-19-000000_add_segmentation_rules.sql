@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS segmentation_rules (
+    language TEXT NOT NULL DEFAULT '',
+    pattern TEXT NOT NULL,
+    is_regex INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (language, pattern)
+);
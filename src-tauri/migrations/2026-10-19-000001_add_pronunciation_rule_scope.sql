@@ -0,0 +1,11 @@
+-- priority orders rules; ties keep the old order (plain before regex, then pattern)
+ALTER TABLE pronunciation_rules ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE pronunciation_rules ADD COLUMN enabled INTEGER NOT NULL DEFAULT 1;
+
+-- empty scope columns mean the rule applies everywhere
+ALTER TABLE pronunciation_rules ADD COLUMN language TEXT NOT NULL DEFAULT '';
+ALTER TABLE pronunciation_rules ADD COLUMN domain TEXT NOT NULL DEFAULT '';
+ALTER TABLE pronunciation_rules ADD COLUMN scope TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE pronunciation_rules ADD COLUMN whole_word INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE pronunciation_rules ADD COLUMN case_insensitive INTEGER NOT NULL DEFAULT 0;
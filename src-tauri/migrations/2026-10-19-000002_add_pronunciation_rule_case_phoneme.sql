@@ -0,0 +1,4 @@
+ALTER TABLE pronunciation_rules ADD COLUMN preserve_case INTEGER NOT NULL DEFAULT 0;
+
+-- IPA pronunciation, only used when SSML phoneme hints are enabled
+ALTER TABLE pronunciation_rules ADD COLUMN phoneme TEXT NOT NULL DEFAULT '';
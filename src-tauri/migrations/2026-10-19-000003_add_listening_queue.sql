@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS listening_queue (
+    article_id INTEGER PRIMARY KEY REFERENCES articles(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL
+);
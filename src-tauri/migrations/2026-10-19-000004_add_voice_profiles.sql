@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS voice_profiles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    voice_id TEXT,
+    rate REAL NOT NULL DEFAULT 1.0,
+    pitch REAL NOT NULL DEFAULT 1.0,
+    volume REAL NOT NULL DEFAULT 1.0,
+    language TEXT NOT NULL DEFAULT ''
+);
+
+CREATE TABLE IF NOT EXISTS article_voice_profiles (
+    article_id INTEGER PRIMARY KEY REFERENCES articles(id) ON DELETE CASCADE,
+    profile_id INTEGER NOT NULL REFERENCES voice_profiles(id) ON DELETE CASCADE
+);
@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS reading_sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    article_id INTEGER REFERENCES articles(id) ON DELETE SET NULL,
+    url TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    ended_at TEXT NOT NULL,
+    start_para INTEGER NOT NULL,
+    end_para INTEGER NOT NULL,
+    duration_secs REAL NOT NULL,
+    rate REAL,
+    finished INTEGER NOT NULL DEFAULT 0,
+    article_created_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS reading_sessions_started_at ON reading_sessions(started_at);
+
+CREATE TABLE IF NOT EXISTS backlog_history (
+    day TEXT PRIMARY KEY,
+    size INTEGER NOT NULL
+);
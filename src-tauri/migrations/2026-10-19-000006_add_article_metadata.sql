@@ -0,0 +1,6 @@
+-- Site name and og:image of articles, shown by the media session.
+ALTER TABLE articles
+ADD COLUMN site_name TEXT DEFAULT '';
+
+ALTER TABLE articles
+ADD COLUMN image_url TEXT DEFAULT '';
@@ -0,0 +1,10 @@
+-- Per-record timestamps of synced settings and pronunciation rules.
+ALTER TABLE settings
+ADD COLUMN updated_at TEXT NOT NULL DEFAULT '1970-01-01 00:00:10';
+
+ALTER TABLE pronunciation_rules
+ADD COLUMN updated_at TEXT NOT NULL DEFAULT '1970-01-01 00:00:10';
+
+-- Deleted rules are kept until synced, like articles.
+ALTER TABLE pronunciation_rules
+ADD COLUMN is_deleted INTEGER NOT NULL DEFAULT 0;
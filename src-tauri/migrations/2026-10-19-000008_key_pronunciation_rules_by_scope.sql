@@ -0,0 +1,35 @@
+-- The same pattern may carry different rules per language, domain and
+-- scope, so those columns join the key. SQLite cannot change a primary key
+-- in place, so the table is rebuilt.
+CREATE TABLE pronunciation_rules_new (
+    match_pattern TEXT NOT NULL,
+    replacement TEXT NOT NULL DEFAULT '',
+    is_regex INTEGER NOT NULL DEFAULT 0,
+    priority INTEGER NOT NULL DEFAULT 0,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    language TEXT NOT NULL DEFAULT '',
+    domain TEXT NOT NULL DEFAULT '',
+    scope TEXT NOT NULL DEFAULT '',
+    whole_word INTEGER NOT NULL DEFAULT 0,
+    case_insensitive INTEGER NOT NULL DEFAULT 0,
+    preserve_case INTEGER NOT NULL DEFAULT 0,
+    phoneme TEXT NOT NULL DEFAULT '',
+    updated_at TEXT NOT NULL DEFAULT '1970-01-01 00:00:10',
+    is_deleted INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (match_pattern, language, domain, scope)
+);
+
+INSERT INTO pronunciation_rules_new (
+    match_pattern, replacement, is_regex, priority, enabled, language, domain,
+    scope, whole_word, case_insensitive, preserve_case, phoneme, updated_at,
+    is_deleted
+)
+SELECT
+    match_pattern, replacement, is_regex, priority, enabled, language, domain,
+    scope, whole_word, case_insensitive, preserve_case, phoneme, updated_at,
+    is_deleted
+FROM pronunciation_rules;
+
+DROP TABLE pronunciation_rules;
+
+ALTER TABLE pronunciation_rules_new RENAME TO pronunciation_rules;
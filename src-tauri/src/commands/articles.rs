@@ -1,7 +1,11 @@
 use crate::fetcher::{FetcherMode, fetch_parse_update_article, new_fetcher};
-use crate::models::{ArticleEntry, DB_URL, ArticleEntryRow, Article};
-use crate::parse::{build_snippet, process_html};
-use sqlx::{query, query_as, query_scalar};
+use crate::models::{
+    ArticleEntry, DB_URL, ArticleEntryRow, Article, BlockKind, ProcessedArticle, TtsParagraph,
+};
+use crate::commands::load_segment_rules;
+use crate::commands::settings::get_setting_value;
+use crate::parse::{build_snippet, detect_language, primary_language, process_article};
+use sqlx::{SqlitePool, query, query_as, query_scalar};
 use tauri::{Manager, State};
 use tauri_plugin_sql::DbInstances;
 
@@ -52,80 +56,148 @@ pub async fn get_articles(
     }
 }
 
-#[tauri::command]
-pub async fn get_article(
+/// Detects the language of the article from its prose.
+pub(crate) fn article_language(paragraphs: &[TtsParagraph]) -> Option<&'static str> {
+    let prose: Vec<&str> = paragraphs
+        .iter()
+        .filter(|p| p.kind == BlockKind::Prose)
+        .map(|p| p.text.as_str())
+        .collect();
+    detect_language(&prose.join(" "))
+}
+
+/// Tags the article body for reading and extracts its paragraphs, using the
+/// segmentation rules for the language of the article. Returns that
+/// language too: the detected one, or the configured one.
+pub(crate) async fn process_for_reading(
+    pool: &SqlitePool,
+    article: &Article,
+) -> (ProcessedArticle, String) {
+    let locale = get_setting_value(pool, "locale").await.unwrap_or_default();
+    let rules = load_segment_rules(pool, &locale).await;
+    let processed = process_article(&article.body, &article.url, &rules);
+    // The prose reads the same however it is split into sentences.
+    match article_language(&processed.paragraphs) {
+        Some(language) if primary_language(language) != primary_language(&locale) => {
+            let rules = load_segment_rules(pool, language).await;
+            let processed = process_article(&article.body, &article.url, &rules);
+            (processed, language.to_string())
+        }
+        Some(language) => (processed, language.to_string()),
+        None => (processed, locale),
+    }
+}
+
+/// Loads a readable (already fetched) article.
+pub(crate) async fn load_article(pool: &SqlitePool, id: i32) -> Result<Article, String> {
+    query_as::<_, Article>(
+        r"
+        SELECT id, title, body, url, site_name, image_url
+        FROM articles
+        WHERE is_deleted == 0 AND id = ?
+        ",
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Downloads an article that was saved without content and stores it.
+pub(crate) async fn download_article(
+    app: &tauri::AppHandle,
     id: i32,
-    db_instances: State<'_, DbInstances>,
-    app: tauri::AppHandle,
-) -> Result<Option<Article>, String> {
-    let instances = db_instances.0.read().await;
+    url: &str,
+) -> Result<(), String> {
+    let mode = {
+        let instances = app.state::<DbInstances>();
+        let instances = instances.0.read().await;
+        let db = instances.get(DB_URL).ok_or("db not loaded")?;
+        match db {
+            tauri_plugin_sql::DbPool::Sqlite(pool) => get_setting_value(pool, "fetcher_mode")
+                .await
+                .and_then(|mode| mode.parse::<FetcherMode>().ok())
+                .unwrap_or_default(),
+        }
+    };
+    let mut fetcher = new_fetcher(app, url, mode)?;
+    let fetched = fetch_parse_update_article(url, &mut *fetcher).await?;
+
+    let instances = app.state::<DbInstances>();
+    let instances = instances.0.write().await;
     let db = instances.get(DB_URL).ok_or("db not loaded")?;
     match db {
         tauri_plugin_sql::DbPool::Sqlite(pool) => {
-            let mut article = query_as::<_, Article>(
+            query(
                 r"
-                SELECT id, title, body, url
-                FROM articles
-                WHERE is_deleted == 0 AND id = ?
+                UPDATE articles
+                SET title = $2, body = $3, text_content = $4, site_name = $5, image_url = $6,
+                    updated_at = datetime('now')
+                WHERE id = $1
                 ",
             )
             .bind(id)
-            .fetch_one(pool)
+            .bind(fetched.title)
+            .bind(fetched.body)
+            .bind(fetched.text_content)
+            .bind(fetched.site_name)
+            .bind(fetched.image_url)
+            .execute(pool)
             .await
             .map_err(|e| e.to_string())?;
-            if article.title.is_empty() {
-                let mode = sqlx::query_as::<_, (String,)>(
-                    "SELECT value FROM settings WHERE name = 'fetcher_mode'",
-                )
-                .fetch_one(pool)
-                .await
-                .ok()
-                .and_then(|r| r.0.parse::<FetcherMode>().ok())
-                .unwrap_or_default();
+            Ok(())
+        }
+    }
+}
 
-                let mut fetcher = new_fetcher(&app, &article.url, mode)?;
+/// Marks an article deleted and clears its content.
+pub(crate) async fn soft_delete_article(pool: &SqlitePool, id: i32) -> Result<u64, String> {
+    let result = query(
+        r"
+        UPDATE articles
+        SET is_deleted = 1, title = '', body = '', text_content = '', updated_at = datetime('now')
+        WHERE id = ? AND is_deleted = 0
+        ",
+    )
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
+
+#[tauri::command]
+pub async fn get_article(
+    id: i32,
+    db_instances: State<'_, DbInstances>,
+    app: tauri::AppHandle,
+) -> Result<Option<Article>, String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            let mut article = load_article(pool, id).await?;
+            if article.title.is_empty() {
                 tauri::async_runtime::spawn(async move {
-                    let db_instances = app.state::<tauri_plugin_sql::DbInstances>();
-                    let instances = db_instances.0.write().await;
-                    if let Ok(tauri_plugin_sql::DbPool::Sqlite(pool)) =
-                        instances.get(DB_URL).ok_or("db not loaded")
-                    {
-                        match fetch_parse_update_article(&article.url, &mut *fetcher).await {
-                            Ok((title, body, text_content)) => {
-                                if let Err(e) = query_as::<_, Article>(
-                                    r"
-                                    UPDATE articles
-                                    SET title = $2, body = $3, url = $4, text_content = $5
-                                    WHERE id = $1
-                                    RETURNING id, title, body, created_at, url
-                                    ",
-                                )
-                                .bind(article.id)
-                                .bind(title)
-                                .bind(body)
-                                .bind(&article.url)
-                                .bind(text_content)
-                                .fetch_one(pool)
-                                .await
-                                {
-                                    eprintln!("{e}");
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("{e}");
-                                let _ = query(
-                            "UPDATE articles SET is_deleted = 1, title = '', body = '', text_content = '' WHERE id = ?",
-                        )
-                        .bind(id)
-                        .execute(pool)
-                        .await;
-                            }
+                    if let Err(e) = download_article(&app, article.id, &article.url).await {
+                        eprintln!("{e}");
+                        let db_instances = app.state::<DbInstances>();
+                        let instances = db_instances.0.write().await;
+                        if let Some(tauri_plugin_sql::DbPool::Sqlite(pool)) = instances.get(DB_URL) {
+                            let _ = query(
+                                "UPDATE articles SET is_deleted = 1, title = '', body = '', text_content = '' WHERE id = ?",
+                            )
+                            .bind(id)
+                            .execute(pool)
+                            .await;
                         }
                     }
                 });
                 Ok(None)
             } else {
-                article.body = process_html(&article.body, &article.url);
+                let (processed, _) = process_for_reading(pool, &article).await;
+                article.body = processed.html;
+                article.toc = processed.toc;
                 Ok(Some(article))
             }
         }
@@ -205,19 +277,7 @@ pub async fn delete_article(id: i32, db_instances: State<'_, DbInstances>) -> Re
     let instances = db_instances.0.write().await;
     let db = instances.get(DB_URL).ok_or("db not loaded")?;
     match db {
-        tauri_plugin_sql::DbPool::Sqlite(pool) => {
-            let result = query(r"
-                UPDATE articles
-                SET is_deleted = 1, title = '', body = '', text_content = '', updated_at = datetime('now')
-                WHERE id = ? AND is_deleted = 0
-            ")
-                .bind(id)
-                .execute(pool)
-                .await
-                .map_err(|e| e.to_string())?;
-
-            Ok(result.rows_affected())
-        }
+        tauri_plugin_sql::DbPool::Sqlite(pool) => soft_delete_article(pool, id).await,
     }
 }
 
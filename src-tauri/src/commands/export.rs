@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, ipc::Channel};
+
+use super::settings::get_setting_value;
+use crate::export::{AudioFormat, BACKEND_SETTING, Voice, backend, encode, render};
+use crate::models::DB_URL;
+use crate::reading::pause_after;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExportProgress {
+    pub count_processed: usize,
+    pub total_count: usize,
+}
+
+/// Renders an article to an audio file with the speak bar settings and
+/// asks where to save it. Progress is reported per unit; the last step is
+/// the encoding.
+#[tauri::command]
+pub async fn export_audio(
+    app: AppHandle,
+    article_id: i32,
+    format: AudioFormat,
+    progress_channel: Channel<ExportProgress>,
+) -> Result<(), String> {
+    let planned = super::speakbar::plan_article(&app, article_id).await?;
+    let (voice, speech, backend_name) = {
+        let instances = app.state::<tauri_plugin_sql::DbInstances>();
+        let instances = instances.0.read().await;
+        let db = instances.get(DB_URL).ok_or("db not loaded")?;
+        match db {
+            tauri_plugin_sql::DbPool::Sqlite(pool) => {
+                let speech = super::speakbar::speech_options(pool).await?;
+                let profile = super::voice_profiles::resolve_voice_profile(
+                    pool,
+                    article_id,
+                    &planned.language,
+                )
+                .await?;
+                let rate = match &profile {
+                    Some(profile) => profile.rate,
+                    None => get_setting_value(pool, "rate")
+                        .await
+                        .and_then(|rate| rate.parse::<f32>().ok())
+                        .unwrap_or(1.0),
+                };
+                let voice = Voice {
+                    language: planned.language.clone(),
+                    rate,
+                    pitch: profile.as_ref().map_or(speech.pitch, |p| p.pitch),
+                    volume: profile.as_ref().map_or(speech.volume, |p| p.volume),
+                };
+                let backend_name = get_setting_value(pool, BACKEND_SETTING)
+                    .await
+                    .unwrap_or_default();
+                (voice, speech, backend_name)
+            }
+        }
+    };
+
+    let total = planned.spoken.len() + 1;
+    let title = planned.title.clone();
+    let channel = progress_channel.clone();
+    let bytes = tauri::async_runtime::spawn_blocking(move || {
+        let backend = backend(&backend_name)?;
+        let pauses: Vec<_> = (0..planned.outline.len())
+            .map(|pos| pause_after(&planned.outline, pos, &speech))
+            .collect();
+        let rendered = render(
+            &planned.spoken,
+            &pauses,
+            &planned.toc,
+            backend.as_ref(),
+            &voice,
+            |done, _| {
+                let _ = channel.send(ExportProgress {
+                    count_processed: done,
+                    total_count: total,
+                });
+            },
+        )?;
+        encode(&rendered, &planned.title, format)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let _ = progress_channel.send(ExportProgress {
+        count_processed: total,
+        total_count: total,
+    });
+
+    let filename = format!("{}.{}", file_stem(&title), format.extension());
+    crate::file_helpers::pick_and_write_bytes(
+        &app,
+        &bytes,
+        &filename,
+        format.mime(),
+        format.extension(),
+    )
+}
+
+/// File name for an article title, without characters file systems reject.
+fn file_stem(title: &str) -> String {
+    let stem: String = title
+        .chars()
+        .map(|c| {
+            if c.is_control() || "/\\:*?\"<>|".contains(c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .take(80)
+        .collect();
+    match stem.trim() {
+        "" => "article".to_string(),
+        stem => stem.to_string(),
+    }
+}
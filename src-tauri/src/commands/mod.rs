@@ -1,12 +1,22 @@
 mod articles;
+mod export;
 mod pronunciation;
+mod queue;
+mod segmentation;
 mod settings;
+mod stats;
 pub mod speakbar;
 pub mod sync;
+mod voice_profiles;
 
 pub use articles::*;
+pub use export::*;
 pub use pronunciation::*;
+pub use queue::*;
+pub use segmentation::*;
 pub use settings::*;
+pub use stats::*;
 pub use speakbar::SpeakBarState;
 pub use speakbar::*;
 pub use sync::*;
+pub use voice_profiles::*;
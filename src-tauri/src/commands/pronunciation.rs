@@ -1,42 +1,79 @@
-use crate::models::{PronunciationRule, DB_URL};
-use regex::Regex;
-use sqlx::{query, query_as, SqlitePool};
+use super::settings::get_setting_value;
+use crate::models::{BlockKind, DB_URL, PronunciationRule, RulePreview, TtsParagraph};
+use crate::normalize::{NormalizeOptions, Normalizer};
+use crate::parse::primary_language;
+use crate::pronunciation::{PronunciationEngine, RuleContext, compile_rule, domain_of};
+use sqlx::{SqlitePool, query, query_as};
 use tauri::{AppHandle, Manager, State};
 use tauri_plugin_sql::DbInstances;
 
 async fn get_all_rules(pool: &SqlitePool) -> Result<Vec<PronunciationRule>, String> {
     query_as::<_, PronunciationRule>(
-        "SELECT match_pattern, replacement, is_regex FROM pronunciation_rules ORDER BY match_pattern",
+        r"
+        SELECT match_pattern, replacement, is_regex, priority, enabled,
+               language, domain, scope, whole_word, case_insensitive,
+               preserve_case, phoneme
+        FROM pronunciation_rules
+        WHERE is_deleted = 0
+        ORDER BY priority, is_regex, match_pattern
+        ",
     )
     .fetch_all(pool)
     .await
     .map_err(|e| e.to_string())
 }
 
-async fn save_rule(
+/// Stores `rule`, stamped with `updated_at` when it comes from another
+/// device and with the current time otherwise.
+pub(crate) async fn save_rule(
     pool: &SqlitePool,
-    match_pattern: &str,
-    replacement: &str,
-    is_regex: bool,
+    rule: &PronunciationRule,
+    updated_at: Option<&str>,
 ) -> Result<(), String> {
-    if is_regex {
-        Regex::new(match_pattern).map_err(|e| {
-            eprintln!("regex failure: pattern={match_pattern} error={e}");
-            format!("Invalid regex: {e}")
-        })?;
+    compile_rule(rule).map_err(|e| {
+        eprintln!("regex failure: pattern={} error={e}", rule.match_pattern);
+        format!("Invalid regex: {e}")
+    })?;
+    if !matches!(rule.scope.as_str(), "" | "prose" | "code") {
+        return Err(format!("Invalid scope: {}", rule.scope));
     }
     query(
         r"
-        INSERT INTO pronunciation_rules (match_pattern, replacement, is_regex)
-        VALUES ($1, $2, $3)
-        ON CONFLICT(match_pattern) DO UPDATE SET
+        INSERT INTO pronunciation_rules (
+            match_pattern, replacement, is_regex, priority, enabled,
+            language, domain, scope, whole_word, case_insensitive,
+            preserve_case, phoneme, updated_at, is_deleted
+        )
+        VALUES (
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12,
+            COALESCE($13, datetime('now')), 0
+        )
+        ON CONFLICT(match_pattern, language, domain, scope) DO UPDATE SET
             replacement = $2,
-            is_regex = $3
+            is_regex = $3,
+            priority = $4,
+            enabled = $5,
+            whole_word = $9,
+            case_insensitive = $10,
+            preserve_case = $11,
+            phoneme = $12,
+            updated_at = COALESCE($13, datetime('now')),
+            is_deleted = 0
         ",
     )
-    .bind(match_pattern)
-    .bind(replacement)
-    .bind(is_regex)
+    .bind(&rule.match_pattern)
+    .bind(&rule.replacement)
+    .bind(rule.is_regex)
+    .bind(rule.priority)
+    .bind(rule.enabled)
+    .bind(primary_language(&rule.language))
+    .bind(rule.domain.trim().to_lowercase())
+    .bind(&rule.scope)
+    .bind(rule.whole_word)
+    .bind(rule.case_insensitive)
+    .bind(rule.preserve_case)
+    .bind(rule.phoneme.trim())
+    .bind(updated_at)
     .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
@@ -56,34 +93,40 @@ pub async fn get_pronunciation_rules(
 
 #[tauri::command]
 pub async fn save_pronunciation_rule(
-    match_pattern: String,
-    replacement: String,
-    is_regex: bool,
+    rule: PronunciationRule,
     db_instances: State<'_, DbInstances>,
 ) -> Result<(), String> {
     let instances = db_instances.0.read().await;
     let db = instances.get(DB_URL).ok_or("db not loaded")?;
     match db {
-        tauri_plugin_sql::DbPool::Sqlite(pool) => {
-            save_rule(pool, &match_pattern, &replacement, is_regex).await
-        }
+        tauri_plugin_sql::DbPool::Sqlite(pool) => save_rule(pool, &rule, None).await,
     }
 }
 
+/// Deletes the rule with the pattern, language, domain and scope of `rule`.
 #[tauri::command]
 pub async fn delete_pronunciation_rule(
-    match_pattern: String,
+    rule: PronunciationRule,
     db_instances: State<'_, DbInstances>,
 ) -> Result<(), String> {
     let instances = db_instances.0.read().await;
     let db = instances.get(DB_URL).ok_or("db not loaded")?;
     match db {
         tauri_plugin_sql::DbPool::Sqlite(pool) => {
-            query("DELETE FROM pronunciation_rules WHERE match_pattern = $1")
-                .bind(&match_pattern)
-                .execute(pool)
-                .await
-                .map_err(|e| e.to_string())?;
+            query(
+                r"
+                UPDATE pronunciation_rules
+                SET is_deleted = 1, updated_at = datetime('now')
+                WHERE match_pattern = $1 AND language = $2 AND domain = $3 AND scope = $4
+                ",
+            )
+            .bind(&rule.match_pattern)
+            .bind(primary_language(&rule.language))
+            .bind(rule.domain.trim().to_lowercase())
+            .bind(&rule.scope)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
             Ok(())
         }
     }
@@ -101,10 +144,7 @@ pub async fn pick_pronunciation_import_file(
         tauri_plugin_sql::DbPool::Sqlite(pool) => {
             let mut failures = 0;
             for rule in &rules {
-                if save_rule(pool, &rule.match_pattern, &rule.replacement, rule.is_regex)
-                    .await
-                    .is_err()
-                {
+                if save_rule(pool, rule, None).await.is_err() {
                     failures += 1;
                 }
             }
@@ -130,46 +170,149 @@ pub async fn pick_pronunciation_export_file(
     crate::file_helpers::pick_and_write_json(&app, &rules, "read_later_rules.json")
 }
 
+/// Runs the normalizer and the rules over sample text or over one paragraph
+/// of an article, and reports which rules fired. `rule` is an unsaved draft
+/// that is evaluated in place of any saved rule with the same pattern.
+#[tauri::command]
+pub async fn preview_pronunciation_rules(
+    text: Option<String>,
+    url: Option<String>,
+    kind: Option<BlockKind>,
+    article_id: Option<i32>,
+    paragraph: Option<usize>,
+    rule: Option<PronunciationRule>,
+    db_instances: State<'_, DbInstances>,
+) -> Result<RulePreview, String> {
+    if let Some(rule) = &rule {
+        compile_rule(rule).map_err(|e| format!("Invalid regex: {e}"))?;
+    }
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            let (input, url, kind, heading, language) = if let Some(article_id) = article_id {
+                let article = super::articles::load_article(pool, article_id).await?;
+                let (processed, language) =
+                    super::articles::process_for_reading(pool, &article).await;
+                let index = paragraph.unwrap_or_default();
+                let paragraph = processed
+                    .paragraphs
+                    .into_iter()
+                    .nth(index)
+                    .ok_or_else(|| format!("Paragraph {index} not found"))?;
+                (
+                    paragraph.text,
+                    article.url,
+                    paragraph.kind,
+                    paragraph.heading.is_some(),
+                    language,
+                )
+            } else {
+                (
+                    text.ok_or("Missing text or article")?,
+                    url.unwrap_or_default(),
+                    kind.unwrap_or_default(),
+                    false,
+                    get_setting_value(pool, "locale").await.unwrap_or_default(),
+                )
+            };
+
+            let mut rules = get_all_rules(pool).await?;
+            if let Some(mut draft) = rule {
+                // Stored the way `save_rule` would store it.
+                draft.language = primary_language(&draft.language);
+                draft.domain = draft.domain.trim().to_lowercase();
+                rules.retain(|r| {
+                    (&r.match_pattern, &r.language, &r.domain, &r.scope)
+                        != (
+                            &draft.match_pattern,
+                            &draft.language,
+                            &draft.domain,
+                            &draft.scope,
+                        )
+                });
+                rules.push(draft);
+            }
+            let normalizer = Normalizer::new(&language, get_normalize_options(pool).await?);
+            let engine = PronunciationEngine::new(&rules).with_ssml(ssml_enabled(pool).await);
+            let domain = domain_of(&url);
+            let ctx = RuleContext {
+                language: &language,
+                domain: &domain,
+                kind,
+            };
+
+            let normalized = normalizer.normalize_unit(&input, kind, heading);
+            let (output, trace) = engine.trace(&normalized, &ctx);
+            Ok(RulePreview {
+                input,
+                normalized,
+                output,
+                trace,
+            })
+        }
+    }
+}
+
+/// Normalizes the units of an article in `language` and applies the rules.
 pub async fn apply_pronunciation_rules(
     app: &AppHandle,
-    paragraphs: Vec<String>,
+    paragraphs: &[TtsParagraph],
+    url: &str,
+    language: &str,
 ) -> Result<Vec<String>, String> {
-    let rules = {
+    let (rules, options, ssml) = {
         let instances = app.state::<tauri_plugin_sql::DbInstances>();
         let instances = instances.0.read().await;
         let db = instances.get(DB_URL).ok_or("db not loaded")?;
         match db {
-            tauri_plugin_sql::DbPool::Sqlite(pool) => get_all_rules(pool).await?,
+            tauri_plugin_sql::DbPool::Sqlite(pool) => (
+                get_all_rules(pool).await?,
+                get_normalize_options(pool).await?,
+                ssml_enabled(pool).await,
+            ),
         }
     };
 
-    if rules.is_empty() {
-        return Ok(paragraphs);
-    }
-
-    let compiled_regex: Vec<(Regex, &String)> = rules
-        .iter()
-        .filter(|r| r.is_regex)
-        .filter_map(|r| Regex::new(&r.match_pattern).ok().map(|re| (re, &r.replacement)))
-        .collect();
-
-    let plain: Vec<(&str, &str)> = rules
-        .iter()
-        .filter(|r| !r.is_regex)
-        .map(|r| (r.match_pattern.as_str(), r.replacement.as_str()))
-        .collect();
+    let normalizer = Normalizer::new(language, options);
+    let engine = PronunciationEngine::new(&rules).with_ssml(ssml);
+    let domain = domain_of(url);
 
     Ok(paragraphs
-        .into_iter()
-        .map(|text| {
-            let mut result = text;
-            for (pattern, replacement) in &plain {
-                result = result.replace(pattern, replacement);
-            }
-            for (re, replacement) in &compiled_regex {
-                result = re.replace_all(&result, replacement.as_str()).to_string();
-            }
-            result
+        .iter()
+        .map(|paragraph| {
+            let ctx = RuleContext {
+                language,
+                domain: &domain,
+                kind: paragraph.kind,
+            };
+            let normalized = normalizer.normalize_unit(
+                &paragraph.text,
+                paragraph.kind,
+                paragraph.heading.is_some(),
+            );
+            engine.apply(&normalized, &ctx)
         })
         .collect())
 }
+
+async fn ssml_enabled(pool: &SqlitePool) -> bool {
+    get_setting_value(pool, "ssmlPhonemes")
+        .await
+        .is_some_and(|value| value == "true")
+}
+
+async fn get_normalize_options(pool: &SqlitePool) -> Result<NormalizeOptions, String> {
+    let settings = query_as::<_, (String, String)>(
+        "SELECT name, value FROM settings WHERE name LIKE 'normalize_%'",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut options = NormalizeOptions::default();
+    for (name, value) in settings {
+        options.apply_setting(&name, &value);
+    }
+    Ok(options)
+}
@@ -0,0 +1,140 @@
+use crate::models::{DB_URL, QueueEntry};
+use sqlx::{SqlitePool, query, query_as, query_scalar};
+use tauri::State;
+use tauri_plugin_sql::DbInstances;
+
+/// Setting that deletes queued articles once they were listened to the end.
+pub const QUEUE_AUTO_DELETE: &str = "queueAutoDelete";
+
+async fn get_entries(pool: &SqlitePool) -> Result<Vec<QueueEntry>, String> {
+    query_as::<_, QueueEntry>(
+        r"
+        SELECT q.article_id, a.title, a.url
+        FROM listening_queue q
+        JOIN articles a ON a.id = q.article_id
+        WHERE a.is_deleted = 0
+        ORDER BY q.position
+        ",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Article ids in queue order.
+pub(crate) async fn queue_ids(pool: &SqlitePool) -> Result<Vec<i32>, String> {
+    Ok(get_entries(pool)
+        .await?
+        .into_iter()
+        .map(|entry| entry.article_id)
+        .collect())
+}
+
+async fn write_order(pool: &SqlitePool, ids: &[i32]) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    query("DELETE FROM listening_queue")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    for (position, id) in ids.iter().enumerate() {
+        query("INSERT INTO listening_queue (article_id, position) VALUES ($1, $2)")
+            .bind(id)
+            .bind(i64::try_from(position).map_err(|e| e.to_string())?)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+pub(crate) async fn remove_queued(pool: &SqlitePool, article_id: i32) -> Result<(), String> {
+    query("DELETE FROM listening_queue WHERE article_id = ?")
+        .bind(article_id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_queue(db_instances: State<'_, DbInstances>) -> Result<Vec<QueueEntry>, String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => get_entries(pool).await,
+    }
+}
+
+#[tauri::command]
+pub async fn add_to_queue(
+    article_id: i32,
+    db_instances: State<'_, DbInstances>,
+) -> Result<(), String> {
+    let instances = db_instances.0.write().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            let next: i64 =
+                query_scalar("SELECT COALESCE(MAX(position) + 1, 0) FROM listening_queue")
+                    .fetch_one(pool)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            query(
+                r"
+                INSERT INTO listening_queue (article_id, position)
+                VALUES ($1, $2)
+                ON CONFLICT(article_id) DO NOTHING
+                ",
+            )
+            .bind(article_id)
+            .bind(next)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn remove_from_queue(
+    article_id: i32,
+    db_instances: State<'_, DbInstances>,
+) -> Result<(), String> {
+    let instances = db_instances.0.write().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => remove_queued(pool, article_id).await,
+    }
+}
+
+/// Moves a queued article to `index`, clamped to the end of the queue.
+#[tauri::command]
+pub async fn move_in_queue(
+    article_id: i32,
+    index: usize,
+    db_instances: State<'_, DbInstances>,
+) -> Result<(), String> {
+    let instances = db_instances.0.write().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            let mut ids = queue_ids(pool).await?;
+            let Some(from) = ids.iter().position(|&id| id == article_id) else {
+                return Err("Article is not queued".into());
+            };
+            let id = ids.remove(from);
+            ids.insert(index.min(ids.len()), id);
+            write_order(pool, &ids).await
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn clear_queue(db_instances: State<'_, DbInstances>) -> Result<(), String> {
+    let instances = db_instances.0.write().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => write_order(pool, &[]).await,
+    }
+}
@@ -0,0 +1,149 @@
+use crate::models::{DB_URL, SegmentationRule};
+use crate::parse::{SegmentRules, primary_language, rule_pattern};
+use regex::Regex;
+use sqlx::{SqlitePool, query, query_as};
+use tauri::{AppHandle, State};
+use tauri_plugin_sql::DbInstances;
+
+async fn get_all_rules(pool: &SqlitePool) -> Result<Vec<SegmentationRule>, String> {
+    query_as::<_, SegmentationRule>(
+        "SELECT language, pattern, is_regex FROM segmentation_rules ORDER BY language, pattern",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+async fn save_rule(
+    pool: &SqlitePool,
+    language: &str,
+    pattern: &str,
+    is_regex: bool,
+) -> Result<(), String> {
+    if is_regex {
+        Regex::new(pattern).map_err(|e| {
+            eprintln!("regex failure: pattern={pattern} error={e}");
+            format!("Invalid regex: {e}")
+        })?;
+    }
+    let pattern = rule_pattern(pattern, is_regex);
+    if pattern.is_empty() {
+        return Err("Empty pattern".into());
+    }
+    query(
+        r"
+        INSERT INTO segmentation_rules (language, pattern, is_regex)
+        VALUES ($1, $2, $3)
+        ON CONFLICT(language, pattern) DO UPDATE SET
+            is_regex = $3
+        ",
+    )
+    .bind(primary_language(language))
+    .bind(pattern)
+    .bind(is_regex)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Builds the segmentation dictionary for `language`: the built-in entries
+/// plus the user defined rules for that language and for all languages.
+pub async fn load_segment_rules(pool: &SqlitePool, language: &str) -> SegmentRules {
+    let mut rules = SegmentRules::for_language(language);
+    match get_all_rules(pool).await {
+        Ok(user_rules) => rules.extend(language, &user_rules),
+        Err(e) => eprintln!("failed to load segmentation rules: {e}"),
+    }
+    rules
+}
+
+#[tauri::command]
+pub async fn get_segmentation_rules(
+    db_instances: State<'_, DbInstances>,
+) -> Result<Vec<SegmentationRule>, String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => get_all_rules(pool).await,
+    }
+}
+
+#[tauri::command]
+pub async fn save_segmentation_rule(
+    language: String,
+    pattern: String,
+    is_regex: bool,
+    db_instances: State<'_, DbInstances>,
+) -> Result<(), String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            save_rule(pool, &language, &pattern, is_regex).await
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn delete_segmentation_rule(
+    language: String,
+    pattern: String,
+    is_regex: bool,
+    db_instances: State<'_, DbInstances>,
+) -> Result<(), String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            query("DELETE FROM segmentation_rules WHERE language = $1 AND pattern = $2")
+                .bind(primary_language(&language))
+                .bind(rule_pattern(&pattern, is_regex))
+                .execute(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn pick_segmentation_import_file(
+    app: AppHandle,
+    db_instances: State<'_, DbInstances>,
+) -> Result<(), String> {
+    let rules: Vec<SegmentationRule> = crate::file_helpers::pick_and_read_json(&app)?;
+    let instances = db_instances.0.write().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            let mut failures = 0;
+            for rule in &rules {
+                if save_rule(pool, &rule.language, &rule.pattern, rule.is_regex)
+                    .await
+                    .is_err()
+                {
+                    failures += 1;
+                }
+            }
+            if failures == 0 {
+                Ok(())
+            } else {
+                Err(format!("{failures} rules failed to save"))
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn pick_segmentation_export_file(
+    app: AppHandle,
+    db_instances: State<'_, DbInstances>,
+) -> Result<(), String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    let rules = match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => get_all_rules(pool).await?,
+    };
+    crate::file_helpers::pick_and_write_json(&app, &rules, "read_later_segmentation.json")
+}
@@ -1,8 +1,18 @@
 use crate::models::DB_URL;
-use sqlx::{query, query_as};
+use sqlx::{SqlitePool, query, query_as, query_scalar};
 use tauri::State;
 use tauri_plugin_sql::DbInstances;
 
+/// Reads a setting straight from the pool, `None` when it is not set.
+pub(crate) async fn get_setting_value(pool: &SqlitePool, name: &str) -> Option<String> {
+    query_scalar::<_, String>("SELECT value FROM settings WHERE name = ?")
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+}
+
 #[tauri::command]
 pub async fn get_setting(
     name: String,
@@ -35,11 +45,12 @@ pub async fn set_setting(
     match db {
         tauri_plugin_sql::DbPool::Sqlite(pool) => {
             query(r"
-                INSERT INTO settings (name, value, default_value)
-                VALUES ($1, $2, '')
+                INSERT INTO settings (name, value, default_value, updated_at)
+                VALUES ($1, $2, '', datetime('now'))
                 ON CONFLICT(name) DO UPDATE SET
                     value = $2,
-                    default_value = ''
+                    default_value = '',
+                    updated_at = datetime('now')
             ")
                 .bind(name)
                 .bind(value)
@@ -1,25 +1,49 @@
 use serde::{Deserialize, Serialize};
-use std::sync::RwLock;
+use std::sync::Arc;
+use std::time::Instant;
 use tauri::{AppHandle, Emitter, Listener, Manager, State};
 use tauri_plugin_tts::TtsExt;
 
+use super::settings::get_setting_value;
+use crate::models::{DB_URL, TocEntry, TtsParagraph, VoiceProfile};
+use crate::pronunciation::domain_of;
+use crate::reading::{
+    ActiveSleepTimer, BlockPolicies, CAPTION_POLICY, CODE_POLICY, Effect, FOOTNOTE_POLICY,
+    IMAGE_POLICY, LINK_POLICY, PauseReason, Playback, QueueAdvance, SkipUnit, SleepTimer,
+    SleepTimerInfo, Speaker, SpeakerState, SpeechBackend, SpeechEvent, SpeechHandler,
+    SpeechOptions, SpeechVoice, TABLE_POLICY, Utterance, WordRange, apply_block_policies,
+    queue_neighbour, seek_target, skip_target,
+};
+use crate::stats::{Session, SessionKind};
+
 #[cfg(any(target_os = "android", target_os = "ios"))]
 use tauri_plugin_media_session::{MediaSessionExt, MediaState};
 
 #[derive(Debug, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
 pub enum MediaAction {
     #[default]
     Stop,
     Play,
     Pause,
+    Next,
+    Previous,
+    Seek,
 }
 
 #[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct MediaActionEvent {
     #[serde(default)]
     pub action: Option<MediaAction>,
+    /// Seconds into the article, for `Seek`.
+    #[serde(default)]
+    pub seek_position: Option<f32>,
 }
 
+/// The `mediaSkipUnit` setting: what media next and previous skip.
+const MEDIA_SKIP_UNIT: &str = "mediaSkipUnit";
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum Mode {
     #[serde(rename = "view")]
@@ -39,326 +63,847 @@ impl Mode {
 pub struct StateChanged {
     pub position: Option<usize>,
     pub mode: Mode,
+    pub sleep_timer: Option<SleepTimerInfo>,
+    pub pause_reason: Option<PauseReason>,
+}
+
+/// Emitted for each word boundary reported by the engine.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WordChanged {
+    pub position: usize,
+    pub word: WordRange,
 }
 
-pub struct SpeakBarState {
-    pub paragraphs: RwLock<Vec<String>>,
-    pub title: RwLock<String>,
-    pub current_position: RwLock<usize>,
-    pub rate: RwLock<f32>,
-    pub voice_id: RwLock<Option<String>>,
-    pub is_playing: RwLock<bool>,
-    pub tts_listener_ids: RwLock<Vec<u32>>,
-}
-
-impl Default for SpeakBarState {
-    fn default() -> Self {
-        Self {
-            paragraphs: RwLock::new(Vec::new()),
-            title: RwLock::new(String::new()),
-            current_position: RwLock::new(0),
-            rate: RwLock::new(1.0),
-            voice_id: RwLock::new(None),
-            is_playing: RwLock::new(false),
-            tts_listener_ids: RwLock::new(Vec::new()),
+/// Payload of `tts://speech:boundary`. Engines name the offset differently.
+#[derive(Debug, Deserialize)]
+struct SpeechBoundary {
+    #[serde(alias = "charIndex", alias = "start")]
+    char_index: usize,
+}
+
+/// What the speak bar knows about the article being read, next to the
+/// player behind the single lock of `SpeakBarState`.
+#[derive(Default)]
+pub struct LoadedArticle {
+    pub id: Option<i32>,
+    pub title: String,
+    /// Site name, or the domain when the page has none.
+    pub site: String,
+    pub image_url: String,
+    pub toc: Vec<TocEntry>,
+    /// Listening session running since playback last started.
+    pub session: Option<ListeningSession>,
+}
+
+pub type SpeakBar = SpeakerState<LoadedArticle>;
+
+pub type SpeakBarState = Speaker<PluginTts, LoadedArticle>;
+
+impl SpeakBar {
+    fn state_changed(&self) -> StateChanged {
+        let is_playing = self.player.is_playing();
+        StateChanged {
+            position: is_playing.then_some(self.player.position()),
+            mode: Mode::from_is_playing(is_playing),
+            sleep_timer: self.player.sleep_timer_info(),
+            pause_reason: self.player.pause_reason(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct ListeningSession {
+    pub article_id: i32,
+    pub start_para: usize,
+    pub started: Instant,
+}
+
+/// Emitted when the speak bar moves to another article. `article_id` is
+/// `None` when the finished article was deleted and nothing follows.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArticleChanged {
+    pub article_id: Option<i32>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ReadState {
     pub mode: Mode,
+    pub playback: Playback,
+    /// Why playback paused by itself, if it did.
+    pub pause_reason: Option<PauseReason>,
     pub position: usize,
+    pub sleep_timer: Option<SleepTimerInfo>,
+    pub voice_profile: Option<VoiceProfile>,
+    pub word: Option<WordRange>,
 }
 
-#[tauri::command]
-pub async fn init_reading(
-    app: AppHandle,
-    rate: f32,
-    title: String,
-    paragraphs: Vec<String>,
-    state: State<'_, SpeakBarState>,
+/// Loads an article into the speak bar, downloading it first when it was
+/// saved without content.
+async fn load_article_for_reading(
+    app: &AppHandle,
+    state: &SpeakBarState,
+    article_id: i32,
 ) -> Result<(), String> {
-    let processed = super::pronunciation::apply_pronunciation_rules(&app, paragraphs).await?;
-    *state.paragraphs.write().map_err(|e| e.to_string())? = processed;
-    *state.title.write().map_err(|e| e.to_string())? = title;
-    *state.rate.write().map_err(|e| e.to_string())? = rate;
-    *state.current_position.write().map_err(|e| e.to_string())? = 0;
-
-    let listener_finish = {
-        let app_clone = app.clone();
-        app_clone.clone().listen("tts://speech:finish", {
-            move |_event: tauri::Event| {
-                let app = app_clone.clone();
-                tauri::async_runtime::spawn(async move {
-                    if let Some(state) = app.try_state::<SpeakBarState>() {
-                        let pos = *state
-                            .current_position
-                            .read()
-                            .map_err(|e| e.to_string())
-                            .unwrap();
-                        *state
-                            .current_position
-                            .write()
-                            .map_err(|e| e.to_string())
-                            .unwrap() = pos + 1;
-                        let app = app.clone();
-                        let _ = start_reading(app, None, state).await;
-                    }
-                });
+    let pending = {
+        let instances = app.state::<tauri_plugin_sql::DbInstances>();
+        let instances = instances.0.read().await;
+        let db = instances.get(DB_URL).ok_or("db not loaded")?;
+        match db {
+            tauri_plugin_sql::DbPool::Sqlite(pool) => {
+                let article = super::articles::load_article(pool, article_id).await?;
+                article.title.is_empty().then_some(article.url)
             }
-        })
+        }
     };
+    if let Some(url) = pending {
+        super::articles::download_article(app, article_id, &url).await?;
+    }
 
-    let listener_error = {
-        let app_clone = app.clone();
-        app_clone.clone().listen("tts://speech:error", {
-            move |_event: tauri::Event| {
-                let app = app_clone.clone();
-                tauri::async_runtime::spawn(async move {
-                    if let Some(state) = app.try_state::<SpeakBarState>() {
-                        let app = app.clone();
-                        let _ = stop_reading(app, state).await;
-                    }
-                });
-            }
-        })
+    let planned = plan_article(app, article_id).await?;
+    let effects = {
+        let mut bar = state.lock();
+        end_listening(app, &mut bar);
+        bar.article.id = Some(article_id);
+        bar.article.title = planned.title;
+        bar.article.site = planned.site;
+        bar.article.image_url = planned.image_url;
+        bar.article.toc = planned.toc;
+        bar.player.load(planned.spoken, planned.outline)
     };
+    state.run(effects)?;
+    apply_voice_profile(app, state).await?;
+    load_speech_options(app, state).await
+}
 
-    let listener_interrupted = {
-        let app_clone = app.clone();
-        app_clone.clone().listen("tts://speech:interrupted", {
-            move |_event: tauri::Event| {
-                let app = app_clone.clone();
-                tauri::async_runtime::spawn(async move {
-                    if let Some(state) = app.try_state::<SpeakBarState>() {
-                        let app = app.clone();
-                        let _ = stop_reading(app, state).await;
+/// An article prepared for listening.
+pub(crate) struct PlannedArticle {
+    pub title: String,
+    /// Site name, or the domain when the page has none.
+    pub site: String,
+    pub image_url: String,
+    /// Units as displayed, in `tts_para` order.
+    pub outline: Vec<TtsParagraph>,
+    /// Units as spoken, after block policies and pronunciation rules. Empty
+    /// for units that are not read.
+    pub spoken: Vec<String>,
+    pub toc: Vec<TocEntry>,
+    /// Detected language, or the locale.
+    pub language: String,
+}
+
+/// Prepares a downloaded article for listening.
+pub(crate) async fn plan_article(
+    app: &AppHandle,
+    article_id: i32,
+) -> Result<PlannedArticle, String> {
+    let (article, paragraphs, toc, policies, language) = {
+        let instances = app.state::<tauri_plugin_sql::DbInstances>();
+        let instances = instances.0.read().await;
+        let db = instances.get(DB_URL).ok_or("db not loaded")?;
+        match db {
+            tauri_plugin_sql::DbPool::Sqlite(pool) => {
+                let article = super::articles::load_article(pool, article_id).await?;
+                let (processed, language) =
+                    super::articles::process_for_reading(pool, &article).await;
+                let mut policies = BlockPolicies::default();
+                for name in [
+                    CODE_POLICY,
+                    TABLE_POLICY,
+                    IMAGE_POLICY,
+                    CAPTION_POLICY,
+                    LINK_POLICY,
+                    FOOTNOTE_POLICY,
+                ] {
+                    if let Some(value) = get_setting_value(pool, name).await {
+                        policies.apply_setting(name, &value);
                     }
-                });
+                }
+                (
+                    article,
+                    processed.paragraphs,
+                    processed.toc,
+                    policies,
+                    language,
+                )
             }
-        })
+        }
     };
+    let planned = apply_block_policies(&paragraphs, &policies, &language);
+    let spoken =
+        super::pronunciation::apply_pronunciation_rules(app, &planned, &article.url, &language)
+            .await?;
+    let site = if article.site_name.is_empty() {
+        domain_of(&article.url)
+    } else {
+        article.site_name
+    };
+    Ok(PlannedArticle {
+        title: article.title,
+        site,
+        image_url: article.image_url,
+        outline: paragraphs,
+        spoken,
+        toc,
+        language,
+    })
+}
 
-    state
-        .tts_listener_ids
-        .write()
-        .map_err(|e| e.to_string())?
-        .clear();
-    state
-        .tts_listener_ids
-        .write()
-        .map_err(|e| e.to_string())?
-        .push(listener_finish);
-    state
-        .tts_listener_ids
-        .write()
-        .map_err(|e| e.to_string())?
-        .push(listener_error);
-    state
-        .tts_listener_ids
-        .write()
-        .map_err(|e| e.to_string())?
-        .push(listener_interrupted);
+/// Reads the `speech_*` settings.
+pub(crate) async fn speech_options(pool: &sqlx::SqlitePool) -> Result<SpeechOptions, String> {
+    let settings = sqlx::query_as::<_, (String, String)>(
+        "SELECT name, value FROM settings WHERE name LIKE 'speech_%'",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let mut options = SpeechOptions::default();
+    for (name, value) in settings {
+        options.apply_setting(&name, &value);
+    }
+    Ok(options)
+}
 
+/// Reads the `speech_*` settings into the speak bar.
+async fn load_speech_options(app: &AppHandle, state: &SpeakBarState) -> Result<(), String> {
+    let options = {
+        let instances = app.state::<tauri_plugin_sql::DbInstances>();
+        let instances = instances.0.read().await;
+        let db = instances.get(DB_URL).ok_or("db not loaded")?;
+        match db {
+            tauri_plugin_sql::DbPool::Sqlite(pool) => speech_options(pool).await?,
+        }
+    };
+    state.lock().player.set_speech(options);
     Ok(())
 }
 
+/// Applies changed `speech_*` settings to the following utterances.
 #[tauri::command]
-pub async fn start_reading(
+pub async fn reload_speech_settings(
     app: AppHandle,
-    start_para: Option<usize>,
     state: State<'_, SpeakBarState>,
 ) -> Result<(), String> {
-    let len = {
-        let paragraphs = state.paragraphs.read().map_err(|e| e.to_string())?;
-        paragraphs.len()
+    load_speech_options(&app, &state).await
+}
+
+/// Applies the voice profile of the loaded article, chosen for the article
+/// or for its detected language. Without a profile the rate falls back to
+/// the `rate` setting. The sentence being spoken restarts with the profile.
+pub(crate) async fn apply_voice_profile(
+    app: &AppHandle,
+    state: &SpeakBarState,
+) -> Result<Option<VoiceProfile>, String> {
+    let (article_id, detected) = {
+        let bar = state.lock();
+        let Some(article_id) = bar.article.id else {
+            return Ok(None);
+        };
+        (
+            article_id,
+            super::articles::article_language(bar.player.outline()),
+        )
     };
+    let (profile, default_rate) = {
+        let instances = app.state::<tauri_plugin_sql::DbInstances>();
+        let instances = instances.0.read().await;
+        let db = instances.get(DB_URL).ok_or("db not loaded")?;
+        match db {
+            tauri_plugin_sql::DbPool::Sqlite(pool) => {
+                let language = match detected {
+                    Some(language) => language.to_string(),
+                    None => get_setting_value(pool, "locale").await.unwrap_or_default(),
+                };
+                (
+                    super::voice_profiles::resolve_voice_profile(pool, article_id, &language)
+                        .await?,
+                    get_setting_value(pool, "rate")
+                        .await
+                        .and_then(|rate| rate.parse::<f32>().ok()),
+                )
+            }
+        }
+    };
+    let effects = {
+        let mut bar = state.lock();
+        if let Some(rate) = profile.as_ref().map(|p| p.rate).or(default_rate) {
+            bar.player.set_rate(rate);
+        }
+        bar.player.set_profile(profile.clone());
+        bar.player.restart()
+    };
+    state.run(effects)?;
+    Ok(profile)
+}
+
+fn speak_request(utterance: &Utterance) -> tauri_plugin_tts::SpeakRequest {
+    tauri_plugin_tts::SpeakRequest {
+        text: utterance.text.clone(),
+        rate: utterance.rate,
+        voice_id: utterance.voice_id.clone(),
+        pitch: utterance.pitch,
+        volume: utterance.volume,
+        language: None,
+        queue_mode: tauri_plugin_tts::QueueMode::Flush,
+    }
+}
+
+/// The `tauri_plugin_tts` engine, reporting through `tts://speech:*` events.
+/// The plugin does not track the audio focus or output; the Android app
+/// does, see `audio_changed`.
+///
+/// The pinned plugin (0.1.11) only emits `finish`, `error` and
+/// `interrupted`. `tts://speech:boundary` is listened to for plugin
+/// versions that add it; until then words are not highlighted.
+pub struct PluginTts {
+    app: AppHandle,
+}
 
-    let pos = start_para.unwrap_or_else(|| {
-        *state
-            .current_position
-            .read()
+impl SpeechBackend for PluginTts {
+    fn speak(&self, utterance: &Utterance) -> Result<(), String> {
+        self.app
+            .tts()
+            .speak(speak_request(utterance))
+            .map(|_| ())
             .map_err(|e| e.to_string())
-            .unwrap()
-    });
+    }
 
-    if pos >= len {
-        *state.is_playing.write().map_err(|e| e.to_string())? = false;
-        return stop_reading(app, state).await;
+    fn stop(&self) -> Result<(), String> {
+        self.app.tts().stop().map(|_| ()).map_err(|e| e.to_string())
     }
 
-    *state.current_position.write().map_err(|e| e.to_string())? = pos;
-    *state.is_playing.write().map_err(|e| e.to_string())? = true;
+    fn voices(&self) -> Result<Vec<SpeechVoice>, String> {
+        let response = self
+            .app
+            .tts()
+            .get_voices(tauri_plugin_tts::GetVoicesRequest { language: None })
+            .map_err(|e| e.to_string())?;
+        Ok(response
+            .voices
+            .into_iter()
+            .map(|voice| SpeechVoice {
+                id: voice.id,
+                name: voice.name,
+                language: voice.language,
+            })
+            .collect())
+    }
 
-    #[cfg(any(target_os = "android", target_os = "ios"))]
-    let _ = update_media_session(&app).await;
+    fn listen(&self, handler: SpeechHandler) -> Vec<u32> {
+        let mut ids: Vec<u32> = [
+            ("tts://speech:finish", SpeechEvent::Finished),
+            ("tts://speech:error", SpeechEvent::Error),
+            ("tts://speech:interrupted", SpeechEvent::Interrupted),
+        ]
+        .into_iter()
+        .map(|(name, event)| {
+            let handler = Arc::clone(&handler);
+            self.app
+                .listen(name, move |_event: tauri::Event| handler(event))
+        })
+        .collect();
+        ids.push(
+            self.app
+                .listen("tts://speech:boundary", move |event: tauri::Event| {
+                    if let Ok(boundary) = serde_json::from_str::<SpeechBoundary>(event.payload()) {
+                        handler(SpeechEvent::Boundary {
+                            char_index: boundary.char_index,
+                        });
+                    }
+                }),
+        );
+        ids
+    }
 
-    read_next_para(&app, &state)?;
+    fn unlisten(&self, id: u32) {
+        self.app.unlisten(id);
+    }
+}
 
-    Ok(())
+/// A change of the audio focus or output, as reported by `AudioTracker` of
+/// the Android app through the view.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum AudioChange {
+    FocusLost,
+    FocusGained,
+    OutputLost,
 }
 
-fn read_next_para(app: &AppHandle, state: &State<'_, SpeakBarState>) -> Result<(), String> {
-    let should_stop = {
-        let is_playing = *state.is_playing.read().map_err(|e| e.to_string())?;
-        let positions = state.paragraphs.read().map_err(|e| e.to_string())?;
-        let pos = *state.current_position.read().map_err(|e| e.to_string())?;
-        is_playing && pos < positions.len()
-    };
+/// Pauses when another app takes the audio focus or the headphones are
+/// unplugged, and resumes when the focus comes back if auto resume is on.
+#[tauri::command]
+pub async fn audio_changed(
+    change: AudioChange,
+    state: State<'_, SpeakBarState>,
+) -> Result<(), String> {
+    state.handle(match change {
+        AudioChange::FocusLost => SpeechEvent::FocusLost,
+        AudioChange::FocusGained => SpeechEvent::FocusGained,
+        AudioChange::OutputLost => SpeechEvent::OutputLost,
+    })
+}
 
-    if !should_stop {
-        stop_reading_internal(app, state)?;
-        return Ok(());
-    }
+/// Creates the speak bar managed by the app.
+#[must_use]
+pub fn speak_bar(app: &AppHandle) -> SpeakBarState {
+    let host_app = app.clone();
+    Speaker::new(
+        PluginTts { app: app.clone() },
+        Box::new(move |state, effect| host(&host_app, state, effect)),
+    )
+}
 
-    let pos = *state.current_position.read().map_err(|e| e.to_string())?;
-    let rate = *state.rate.read().map_err(|e| e.to_string())?;
-    let voice_id = state.voice_id.read().map_err(|e| e.to_string())?.clone();
-    let text = {
-        let positions = state.paragraphs.read().map_err(|e| e.to_string())?;
-        positions[pos].clone()
-    };
+/// Carries out the effects the speaker leaves to the app.
+fn host(app: &AppHandle, state: &SpeakBarState, effect: Effect) -> Result<(), String> {
+    match effect {
+        Effect::Wait { ticket, pause } => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(pause).await;
+                let state = app.state::<SpeakBarState>();
+                let effects = state.lock().player.pause_elapsed(ticket);
+                if let Err(e) = state.run(effects) {
+                    eprintln!("failed to continue reading: {e}");
+                }
+            });
+            Ok(())
+        }
+        Effect::StateChanged => {
+            let changed = {
+                let mut bar = state.lock();
+                sync_session(app, &mut bar);
+                bar.state_changed()
+            };
+            #[cfg(any(target_os = "android", target_os = "ios"))]
+            let _ = if changed.mode == Mode::Reader {
+                update_media_session(app, state)
+            } else {
+                app.media_session().clear().map_err(|e| e.to_string())
+            };
+            app.emit("speakbar:state-changed", changed)
+                .map_err(|e| e.to_string())
+        }
+        Effect::Word { position, word } => app
+            .emit("speakbar:word", WordChanged { position, word })
+            .map_err(|e| e.to_string()),
+        Effect::Finished { continue_queue } => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app.state::<SpeakBarState>();
+                if let Err(e) = continue_after(&app, &state, continue_queue).await {
+                    eprintln!("failed to finish article: {e}");
+                }
+            });
+            Ok(())
+        }
+        // Carried out by the speaker.
+        Effect::Speak(_) | Effect::Stop => Ok(()),
+    }
+}
 
-    let is_playing = *state.is_playing.read().map_err(|e| e.to_string())?;
-    let mode = Mode::from_is_playing(is_playing);
+/// Loads `article_id` and reads it from the first paragraph.
+async fn play_article(
+    app: &AppHandle,
+    state: &SpeakBarState,
+    article_id: i32,
+) -> Result<(), String> {
+    load_article_for_reading(app, state, article_id).await?;
+    let effects = state.lock().player.play(None);
     app.emit(
-        "speakbar:state-changed",
-        StateChanged {
-            position: Some(pos),
-            mode,
+        "speakbar:article-changed",
+        ArticleChanged {
+            article_id: Some(article_id),
         },
     )
     .map_err(|e| e.to_string())?;
+    state.run(effects)
+}
 
-    #[cfg(any(target_os = "android", target_os = "ios"))]
-    let _ = update_media_session(&app).await;
-
-    let speak_req = tauri_plugin_tts::SpeakRequest {
-        text,
-        rate,
-        voice_id,
-        pitch: 1.0,
-        volume: 1.0,
-        language: None,
-        queue_mode: tauri_plugin_tts::QueueMode::Flush,
+/// Called when the current article was read to the end: drops it from the
+/// queue, deletes it when auto delete is on, and returns the article to
+/// read next.
+async fn finish_article(app: &AppHandle, state: &SpeakBarState) -> Result<Option<i32>, String> {
+    let Some(current) = state.lock().article.id else {
+        return Ok(None);
     };
-
-    if let Err(e) = app.tts().speak(speak_req) {
+    let (advance, deleted) = {
+        let instances = app.state::<tauri_plugin_sql::DbInstances>();
+        let instances = instances.0.write().await;
+        let db = instances.get(DB_URL).ok_or("db not loaded")?;
+        match db {
+            tauri_plugin_sql::DbPool::Sqlite(pool) => {
+                let queue = super::queue::queue_ids(pool).await?;
+                let auto_delete = get_setting_value(pool, super::queue::QUEUE_AUTO_DELETE)
+                    .await
+                    .is_some_and(|value| value == "true");
+                let advance = QueueAdvance::after(&queue, current, auto_delete);
+                super::queue::remove_queued(pool, current).await?;
+                let deleted = advance.delete
+                    && super::articles::soft_delete_article(pool, current).await? > 0;
+                (advance, deleted)
+            }
+        }
+    };
+    if advance.unloads(deleted) {
+        state.lock().article.id = None;
         app.emit(
-            "speakbar:state-changed",
-            StateChanged {
-                position: None,
-                mode: Mode::View,
-            },
+            "speakbar:article-changed",
+            ArticleChanged { article_id: None },
         )
         .map_err(|e| e.to_string())?;
-        return Err(e.to_string());
     }
+    Ok(advance.next)
+}
 
-    Ok(())
+/// Finishes the article and plays the next queued one, unless the sleep
+/// timer ended reading with this article.
+async fn continue_after(
+    app: &AppHandle,
+    state: &SpeakBarState,
+    continue_queue: bool,
+) -> Result<(), String> {
+    let next = finish_article(app, state).await?;
+    match next {
+        Some(next) if continue_queue => play_article(app, state, next).await,
+        _ => Ok(()),
+    }
 }
 
-fn stop_reading_internal(app: &AppHandle, state: &State<'_, SpeakBarState>) -> Result<(), String> {
-    *state.is_playing.write().map_err(|e| e.to_string())? = false;
+#[tauri::command]
+pub async fn init_reading(
+    app: AppHandle,
+    rate: f32,
+    article_id: i32,
+    state: State<'_, SpeakBarState>,
+) -> Result<Option<VoiceProfile>, String> {
+    {
+        let mut bar = state.lock();
+        if bar.player.is_playing() && bar.article.id == Some(article_id) {
+            // The queue advanced to this article while it was already playing.
+            return Ok(bar.player.profile().cloned());
+        }
+        bar.player.set_rate(rate);
+    }
+    load_article_for_reading(&app, &state, article_id).await?;
 
-    #[cfg(any(target_os = "android", target_os = "ios"))]
-    let _ = app.media_session().clear();
+    let app_clone = app.clone();
+    state.attach(Arc::new(move |event| {
+        let Some(state) = app_clone.try_state::<SpeakBarState>() else {
+            return;
+        };
+        if let Err(e) = state.handle(event) {
+            eprintln!("failed to handle {event:?}: {e}");
+        }
+    }));
 
-    app.emit(
-        "speakbar:state-changed",
-        StateChanged {
-            position: None,
-            mode: Mode::View,
-        },
-    )
-    .map_err(|e| e.to_string())?;
+    Ok(state.lock().player.profile().cloned())
+}
 
-    Ok(())
+#[tauri::command]
+pub async fn start_reading(
+    start_para: Option<usize>,
+    state: State<'_, SpeakBarState>,
+) -> Result<(), String> {
+    let effects = state.lock().player.play(start_para);
+    state.run(effects)
+}
+
+/// Starts a listening session when playback started and ends the running
+/// one when it stopped or moved to another article.
+fn sync_session(app: &AppHandle, bar: &mut SpeakBar) {
+    let playing = bar.article.id.filter(|_| bar.player.is_playing());
+    if bar.article.session.map(|session| session.article_id) == playing {
+        return;
+    }
+    end_listening(app, bar);
+    if let Some(article_id) = playing {
+        bar.article.session = Some(ListeningSession {
+            article_id,
+            start_para: bar.player.position(),
+            started: Instant::now(),
+        });
+    }
+}
+
+/// Ends the running listening session and records it in the background.
+fn end_listening(app: &AppHandle, bar: &mut SpeakBar) {
+    let Some(listening) = bar.article.session.take() else {
+        return;
+    };
+    let session = Session {
+        article_id: listening.article_id,
+        kind: SessionKind::Listen,
+        start_para: listening.start_para,
+        end_para: bar.player.position(),
+        duration_secs: listening.started.elapsed().as_secs_f64(),
+        rate: Some(bar.player.rate()),
+        finished: bar.player.playback() == Playback::Finished,
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let instances = app.state::<tauri_plugin_sql::DbInstances>();
+        let instances = instances.0.read().await;
+        let Some(tauri_plugin_sql::DbPool::Sqlite(pool)) = instances.get(DB_URL) else {
+            return;
+        };
+        if let Err(e) = super::stats::record_session(pool, &session).await {
+            eprintln!("failed to record listening session: {e}");
+        }
+    });
 }
 
 #[cfg(any(target_os = "android", target_os = "ios"))]
-async fn update_media_session(app: &AppHandle) -> Result<(), String> {
-    if let Some(state) = app.try_state::<SpeakBarState>() {
-        let is_playing = *state.is_playing.read().map_err(|e| e.to_string())?;
-        let title = state.title.read().map_err(|e| e.to_string())?.clone();
-        let title = if title.is_empty() {
+fn update_media_session(app: &AppHandle, state: &SpeakBarState) -> Result<(), String> {
+    use crate::reading::{current_section, elapsed_secs};
+
+    let media = {
+        let bar = state.lock();
+        let player = &bar.player;
+        let article = &bar.article;
+        let title = if article.title.is_empty() {
             "Untitled".to_string()
         } else {
-            title
+            article.title.clone()
         };
+        MediaState {
+            title: Some(title),
+            artist: Some(article.site.clone()).filter(|site| !site.is_empty()),
+            // Shown as the subtitle.
+            album: current_section(&article.toc, player.position()).map(|entry| entry.text.clone()),
+            artwork_url: Some(article.image_url.clone()).filter(|url| !url.is_empty()),
+            duration: Some(f64::from(elapsed_secs(
+                player.units(),
+                player.units().len(),
+                player.rate(),
+            ))),
+            position: Some(f64::from(elapsed_secs(
+                player.units(),
+                player.position(),
+                player.rate(),
+            ))),
+            is_playing: Some(player.is_playing()),
+            ..Default::default()
+        }
+    };
 
-        app.media_session()
-            .update_state(MediaState {
-                title: Some(title),
-                is_playing: Some(is_playing),
-                ..Default::default()
-            })
-            .map_err(|e| e.to_string())?;
-    }
+    app.media_session()
+        .update_state(media)
+        .map_err(|e| e.to_string())?;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn stop_reading(app: AppHandle, state: State<'_, SpeakBarState>) -> Result<(), String> {
-    let _ = app.tts().stop();
-    stop_reading_internal(&app, &state)
+pub async fn stop_reading(state: State<'_, SpeakBarState>) -> Result<(), String> {
+    let effects = state.lock().player.pause();
+    state.run(effects)
 }
 
+/// Changes the rate, restarting the sentence being spoken so the change is
+/// heard at once.
 #[tauri::command]
 pub async fn change_rate(rate: f32, state: State<'_, SpeakBarState>) -> Result<(), String> {
-    *state.rate.write().map_err(|e| e.to_string())? = rate;
-    Ok(())
+    let effects = state.lock().player.change_rate(rate);
+    state.run(effects)
 }
 
 #[tauri::command]
 pub async fn get_read_state(state: State<'_, SpeakBarState>) -> Result<ReadState, String> {
-    let is_playing = *state.is_playing.read().map_err(|e| e.to_string())?;
-    let position = *state.current_position.read().map_err(|e| e.to_string())?;
-
+    let bar = state.lock();
     Ok(ReadState {
-        mode: Mode::from_is_playing(is_playing),
-        position,
+        mode: Mode::from_is_playing(bar.player.is_playing()),
+        playback: bar.player.playback(),
+        pause_reason: bar.player.pause_reason(),
+        position: bar.player.position(),
+        sleep_timer: bar.player.sleep_timer_info(),
+        voice_profile: bar.player.profile().cloned(),
+        word: bar.player.word(),
     })
 }
 
+/// Starts, replaces or (with `None`) cancels the sleep timer.
+#[tauri::command]
+pub async fn set_sleep_timer(
+    app: AppHandle,
+    timer: Option<SleepTimer>,
+    state: State<'_, SpeakBarState>,
+) -> Result<(), String> {
+    let active = timer.map(ActiveSleepTimer::new);
+    state.lock().player.set_sleep_timer(active);
+
+    if let Some(deadline) = active.and_then(|active| active.deadline) {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(deadline.saturating_duration_since(Instant::now())).await;
+            let state = app.state::<SpeakBarState>();
+            // Does nothing when a newer timer replaced this one.
+            let effects = state.lock().player.sleep_timer_expired(deadline);
+            if let Err(e) = state.run(effects) {
+                eprintln!("failed to stop reading: {e}");
+            }
+        });
+    }
+
+    let changed = state.lock().state_changed();
+    app.emit("speakbar:state-changed", changed)
+        .map_err(|e| e.to_string())
+}
+
+/// Changes the voice, restarting the sentence being spoken when it sounds
+/// different.
 #[tauri::command]
 pub async fn set_voice_id(
     voice_id: Option<String>,
     state: State<'_, SpeakBarState>,
 ) -> Result<(), String> {
-    *state.voice_id.write().map_err(|e| e.to_string())? = voice_id;
-    Ok(())
+    let effects = state.lock().player.change_voice_id(voice_id);
+    state.run(effects)
 }
 
+/// Reads from the start of TOC entry `index`.
 #[tauri::command]
-pub async fn cleanup_reading(
+pub async fn jump_to_heading(index: usize, state: State<'_, SpeakBarState>) -> Result<(), String> {
+    let effects = {
+        let mut bar = state.lock();
+        let para = bar
+            .article
+            .toc
+            .get(index)
+            .map(|entry| entry.para)
+            .ok_or_else(|| format!("Heading {index} not found"))?;
+        bar.player.play(Some(para))
+    };
+    state.run(effects)
+}
+
+/// Skips forward or back by sentence, block, heading section or a fixed
+/// time. Does nothing when there is nothing to skip to.
+#[tauri::command]
+pub async fn skip_reading(
+    unit: SkipUnit,
+    forward: bool,
+    state: State<'_, SpeakBarState>,
+) -> Result<(), String> {
+    let effects = {
+        let mut bar = state.lock();
+        let player = &bar.player;
+        let target = skip_target(
+            player.outline(),
+            player.units(),
+            player.position(),
+            unit,
+            forward,
+            player.rate(),
+        );
+        match target {
+            Some(target) => bar.player.skip_to(target),
+            None => return Ok(()),
+        }
+    };
+    state.run(effects)
+}
+
+/// Moves to the unit spoken `secs` into the article (media seek). Like
+/// other skips, does nothing but move the position when not playing.
+#[tauri::command]
+pub async fn seek_reading(secs: f32, state: State<'_, SpeakBarState>) -> Result<(), String> {
+    let effects = {
+        let mut bar = state.lock();
+        let player = &bar.player;
+        match seek_target(player.units(), secs, player.rate()) {
+            Some(target) => bar.player.skip_to(target),
+            None => return Ok(()),
+        }
+    };
+    state.run(effects)
+}
+
+/// Skips `offset` articles through the listening queue (media next and
+/// previous). Does nothing at either end of the queue.
+#[tauri::command]
+pub async fn skip_queued_article(
+    app: AppHandle,
+    offset: isize,
+    state: State<'_, SpeakBarState>,
+) -> Result<(), String> {
+    let current = state.lock().article.id;
+    let next = {
+        let instances = app.state::<tauri_plugin_sql::DbInstances>();
+        let instances = instances.0.read().await;
+        let db = instances.get(DB_URL).ok_or("db not loaded")?;
+        match db {
+            tauri_plugin_sql::DbPool::Sqlite(pool) => {
+                let queue = super::queue::queue_ids(pool).await?;
+                queue_neighbour(&queue, current, offset)
+            }
+        }
+    };
+    match next {
+        Some(next) => play_article(&app, &state, next).await,
+        None => Ok(()),
+    }
+}
+
+/// Carries out an action from the media notification, a headset or a car:
+/// play and pause, next and previous by the `mediaSkipUnit` setting (an
+/// article of the queue by default), and seek.
+#[tauri::command]
+pub async fn media_action(
     app: AppHandle,
+    event: MediaActionEvent,
     state: State<'_, SpeakBarState>,
 ) -> Result<(), String> {
-    let _ = app.tts().stop();
+    let forward = match event.action.unwrap_or_default() {
+        MediaAction::Play => return start_reading(None, state).await,
+        MediaAction::Pause | MediaAction::Stop => return stop_reading(state).await,
+        MediaAction::Seek => {
+            return match event.seek_position {
+                Some(secs) => seek_reading(secs, state).await,
+                None => Ok(()),
+            };
+        }
+        MediaAction::Next => true,
+        MediaAction::Previous => false,
+    };
+    let unit = {
+        let instances = app.state::<tauri_plugin_sql::DbInstances>();
+        let instances = instances.0.read().await;
+        let db = instances.get(DB_URL).ok_or("db not loaded")?;
+        match db {
+            tauri_plugin_sql::DbPool::Sqlite(pool) => get_setting_value(pool, MEDIA_SKIP_UNIT)
+                .await
+                .and_then(|unit| serde_json::from_value::<SkipUnit>(unit.into()).ok()),
+        }
+    };
+    match unit {
+        Some(unit) => skip_reading(unit, forward, state).await,
+        None => skip_queued_article(app, if forward { 1 } else { -1 }, state).await,
+    }
+}
 
-    *state.paragraphs.write().map_err(|e| e.to_string())? = Vec::new();
-    *state.title.write().map_err(|e| e.to_string())? = String::new();
-    *state.current_position.write().map_err(|e| e.to_string())? = 0;
-    *state.is_playing.write().map_err(|e| e.to_string())? = false;
+/// Releases the speak bar when the article view closes. `article_id` is the
+/// article of the closing view; nothing is released when the speak bar has
+/// already moved on to another article.
+#[tauri::command]
+pub async fn cleanup_reading(
+    app: AppHandle,
+    article_id: Option<i32>,
+    state: State<'_, SpeakBarState>,
+) -> Result<(), String> {
+    let effects = {
+        let mut bar = state.lock();
+        if article_id.is_some() && article_id != bar.article.id {
+            return Ok(());
+        }
+        end_listening(&app, &mut bar);
+        bar.article.id = None;
+        bar.article.title.clear();
+        bar.article.toc.clear();
+        bar.player.reset()
+    };
 
     #[cfg(any(target_os = "android", target_os = "ios"))]
     let _ = app.media_session().clear();
 
-    for id in state
-        .tts_listener_ids
-        .read()
-        .map_err(|e| e.to_string())?
-        .iter()
-    {
-        app.unlisten(*id);
-    }
-    state
-        .tts_listener_ids
-        .write()
-        .map_err(|e| e.to_string())?
-        .clear();
-
-    Ok(())
+    state.detach();
+    state.run(effects)
 }
@@ -0,0 +1,212 @@
+use crate::models::{BacklogPoint, CountStat, DB_URL, SiteStat, TimeStat};
+use crate::stats::{Period, Session, SessionKind, since, top_sites};
+use sqlx::{SqlitePool, query, query_as, query_scalar};
+use tauri::State;
+use tauri_plugin_sql::DbInstances;
+
+/// Stores today's backlog size, replacing an earlier snapshot of the day.
+async fn snapshot_backlog(pool: &SqlitePool) -> Result<(), String> {
+    query(
+        r"
+        INSERT INTO backlog_history (day, size)
+        SELECT date('now'), COUNT(*) FROM articles WHERE is_deleted = 0
+        ON CONFLICT(day) DO UPDATE SET size = excluded.size
+        ",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Records a session ending now. Short sessions are ignored.
+pub(crate) async fn record_session(pool: &SqlitePool, session: &Session) -> Result<(), String> {
+    if !session.is_recorded() {
+        return Ok(());
+    }
+    query(
+        r"
+        INSERT INTO reading_sessions (
+            article_id, url, kind, started_at, ended_at, start_para, end_para,
+            duration_secs, rate, finished, article_created_at
+        )
+        SELECT id, url, $1, datetime('now', $2), datetime('now'), $3, $4, $5, $6, $7, created_at
+        FROM articles WHERE id = $8
+        ",
+    )
+    .bind(session.kind.as_str())
+    .bind(format!("-{:.0} seconds", session.duration_secs))
+    .bind(i64::try_from(session.start_para).map_err(|e| e.to_string())?)
+    .bind(i64::try_from(session.end_para).map_err(|e| e.to_string())?)
+    .bind(session.duration_secs)
+    .bind(session.rate)
+    .bind(session.finished)
+    .bind(session.article_id)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    snapshot_backlog(pool).await
+}
+
+/// Records time spent in the reader view.
+#[tauri::command]
+pub async fn record_reading_session(
+    article_id: i32,
+    start_para: usize,
+    end_para: usize,
+    duration_secs: f64,
+    finished: bool,
+    db_instances: State<'_, DbInstances>,
+) -> Result<(), String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            let session = Session {
+                article_id,
+                kind: SessionKind::Read,
+                start_para,
+                end_para,
+                duration_secs,
+                rate: None,
+                finished,
+            };
+            record_session(pool, &session).await
+        }
+    }
+}
+
+/// Seconds listened and read per period over the last `days` days.
+#[tauri::command]
+pub async fn get_time_stats(
+    period: Period,
+    days: u32,
+    db_instances: State<'_, DbInstances>,
+) -> Result<Vec<TimeStat>, String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => query_as::<_, TimeStat>(
+            r"
+            SELECT strftime($1, started_at, 'localtime') AS period,
+                   TOTAL(CASE WHEN kind = 'listen' THEN duration_secs END) AS listened_secs,
+                   TOTAL(CASE WHEN kind = 'read' THEN duration_secs END) AS read_secs
+            FROM reading_sessions
+            WHERE started_at >= datetime('now', $2)
+            GROUP BY period
+            ORDER BY period
+            ",
+        )
+        .bind(period.sql_format())
+        .bind(since(days))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string()),
+    }
+}
+
+/// Articles finished per period over the last `days` days.
+#[tauri::command]
+pub async fn get_finished_stats(
+    period: Period,
+    days: u32,
+    db_instances: State<'_, DbInstances>,
+) -> Result<Vec<CountStat>, String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => query_as::<_, CountStat>(
+            r"
+            SELECT strftime($1, ended_at, 'localtime') AS period,
+                   COUNT(DISTINCT url) AS count
+            FROM reading_sessions
+            WHERE finished = 1 AND ended_at >= datetime('now', $2)
+            GROUP BY period
+            ORDER BY period
+            ",
+        )
+        .bind(period.sql_format())
+        .bind(since(days))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string()),
+    }
+}
+
+/// Daily backlog sizes over the last `days` days, including today.
+#[tauri::command]
+pub async fn get_backlog_history(
+    days: u32,
+    db_instances: State<'_, DbInstances>,
+) -> Result<Vec<BacklogPoint>, String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            snapshot_backlog(pool).await?;
+            query_as::<_, BacklogPoint>(
+                "SELECT day, size FROM backlog_history WHERE day >= date('now', $1) ORDER BY day",
+            )
+            .bind(since(days))
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Sites with the most time spent over the last `days` days.
+#[tauri::command]
+pub async fn get_top_sites(
+    days: u32,
+    limit: usize,
+    db_instances: State<'_, DbInstances>,
+) -> Result<Vec<SiteStat>, String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            let sessions: Vec<(String, f64, bool)> = query_as(
+                r"
+                SELECT url, duration_secs, finished
+                FROM reading_sessions
+                WHERE started_at >= datetime('now', $1)
+                ",
+            )
+            .bind(since(days))
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+            Ok(top_sites(&sessions, limit))
+        }
+    }
+}
+
+/// Average age in days of articles when they were first finished, over the
+/// last `days` days.
+#[tauri::command]
+pub async fn get_completion_age(
+    days: u32,
+    db_instances: State<'_, DbInstances>,
+) -> Result<Option<f64>, String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => query_scalar(
+            r"
+            SELECT AVG(age) FROM (
+                SELECT MIN(julianday(ended_at)) - julianday(article_created_at) AS age
+                FROM reading_sessions
+                WHERE finished = 1
+                  AND article_created_at IS NOT NULL
+                  AND ended_at >= datetime('now', $1)
+                GROUP BY url
+            )
+            ",
+        )
+        .bind(since(days))
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string()),
+    }
+}
@@ -1,12 +1,19 @@
+use crate::commands::pronunciation::save_rule;
 use crate::commands::settings::{get_setting, set_setting};
-use crate::models::{ArticleSync, DB_URL};
-use blake3;
+use crate::models::{ArticleSync, DB_URL, PronunciationRule};
+use crate::sync::{
+    self, ArticleContent, ArticleEntry, Folder, KeyFile, Manifest, RecordSet, SyncKey, SyncedRecord,
+};
 use chrono::{NaiveDateTime, Utc};
+use futures::stream::{self, StreamExt};
 use reqwest_dav::types::list_cmd::{ListEntity, ListFile};
-use reqwest_dav::types::{Auth, Depth};
+use reqwest_dav::types::{Auth, DecodeError, Depth, Error as DavError};
 use reqwest_dav::{Client, ClientBuilder};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tauri::{AppHandle, Runtime, State, ipc::Channel};
+use tauri_plugin_http::reqwest::{Method, Response, StatusCode, header};
 use tauri_plugin_sql::DbInstances;
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -15,11 +22,6 @@ pub struct SyncProgress {
     pub total_count: usize,
 }
 
-fn url_to_path(url: &str) -> String {
-    let hash = blake3::hash(url.as_bytes());
-    format!("{}.json", hash.to_hex())
-}
-
 fn setup_webdav_client(url: String, username: String, password: String, auth_type: &str) -> Client {
     let auth = match auth_type {
         "basic" => Auth::Basic(username, password),
@@ -39,156 +41,678 @@ fn iso_to_timestamp(iso_str: &str) -> i64 {
         .map_or(0, |naive| naive.and_utc().timestamp())
 }
 
-async fn get_remote_entities(
-    client: &Client,
-    sync_path: &str,
-    last_synced_at: i64,
-) -> Result<Vec<ListFile>, String> {
-    if client.list(sync_path, Depth::Number(0)).await.is_err() {
-        client.mkcol(sync_path).await.map_err(|e| e.to_string())?;
+/// The status the server answered a failed request with, if it did.
+fn error_status(error: &DavError) -> Option<u16> {
+    match error {
+        DavError::Reqwest(e) => e.status().map(|status| status.as_u16()),
+        DavError::Decode(DecodeError::StatusMismatched(e)) => Some(e.response_code),
+        DavError::Decode(DecodeError::Server(e)) => Some(e.response_code),
+        _ => None,
     }
+}
 
-    let entities = client
-        .list(sync_path, Depth::Number(1))
-        .await
-        .map_err(|e| e.to_string())?;
+/// Requests the file at `path`, `None` only when the server has no such
+/// file: a server that is down or refuses access must not pass for an
+/// empty folder.
+async fn download(client: &Client, path: &str) -> Result<Option<Response>, String> {
+    let resp = match client.get(path).await {
+        Ok(resp) => resp,
+        Err(e) if error_status(&e) == Some(StatusCode::NOT_FOUND.as_u16()) => return Ok(None),
+        Err(e) => return Err(format!("{path}: {e}")),
+    };
+    match resp.status() {
+        StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => Ok(Some(resp)),
+        status => Err(format!("{path}: {status}")),
+    }
+}
 
-    Ok(entities
-        .into_iter()
-        .filter_map(|e| {
-            if let ListEntity::File(file) = e
-                && file.last_modified.timestamp() > last_synced_at
-            {
-                Some(file)
-            } else {
-                None
+/// Requests in flight at once when moving many files.
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// Merges of a shared file, the manifest or a record set, to try while
+/// other devices keep writing it.
+const MERGE_ATTEMPTS: usize = 5;
+
+/// A sync folder on the server and how its files are stored.
+struct Remote<'a> {
+    client: &'a Client,
+    path: &'a str,
+    folder: Folder,
+}
+
+impl Remote<'_> {
+    fn path_of(&self, name: &str) -> String {
+        format!("{}/{name}", self.path)
+    }
+
+    /// Downloads and opens the file `name`, `None` when it does not exist.
+    async fn get(&self, name: &str) -> Result<Option<Vec<u8>>, String> {
+        Ok(self.get_tagged(name).await?.map(|(bytes, _)| bytes))
+    }
+
+    /// Like `get`, along with the `ETag` of the downloaded version.
+    async fn get_tagged(&self, name: &str) -> Result<Option<(Vec<u8>, Option<String>)>, String> {
+        let Some((bytes, etag)) = self.get_sealed(name).await? else {
+            return Ok(None);
+        };
+        Ok(Some((self.folder.open(name, bytes)?, etag)))
+    }
+
+    /// Downloads the file `name` as stored, without opening it.
+    async fn get_sealed(&self, name: &str) -> Result<Option<(Vec<u8>, Option<String>)>, String> {
+        let Some(resp) = download(self.client, &self.path_of(name)).await? else {
+            return Ok(None);
+        };
+        let etag = resp
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
+        Ok(Some((bytes.to_vec(), etag)))
+    }
+
+    async fn put(&self, name: &str, plain: Vec<u8>) -> Result<(), String> {
+        self.client
+            .put(&self.path_of(name), self.folder.seal(name, plain)?)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Uploads the file `name` only if the server still holds the version
+    /// tagged `etag`, or no file at all when `None`. Returns `false`, having
+    /// written nothing, when another device changed the file meanwhile.
+    async fn put_if(&self, name: &str, plain: Vec<u8>, etag: Option<&str>) -> Result<bool, String> {
+        let path = self.path_of(name);
+        let (precondition, value) = match etag {
+            Some(etag) => (header::IF_MATCH, etag),
+            None => (header::IF_NONE_MATCH, "*"),
+        };
+        let resp = self
+            .client
+            .start_request(Method::PUT, &path)
+            .await
+            .map_err(|e| e.to_string())?
+            .header(precondition, value)
+            .body(self.folder.seal(name, plain)?)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        match resp.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(format!("{path}: {status}")),
+        }
+    }
+
+    /// Writes the file `name` over the version read by `get_tagged`: `None`
+    /// when there was no file, `Some(None)` when the server sent no `ETag`,
+    /// which leaves nothing to check against. Returns `false` like `put_if`.
+    async fn put_over(
+        &self,
+        name: &str,
+        plain: Vec<u8>,
+        etag: Option<Option<&str>>,
+    ) -> Result<bool, String> {
+        match etag {
+            Some(None) => self.put(name, plain).await.map(|()| true),
+            Some(Some(etag)) => self.put_if(name, plain, Some(etag)).await,
+            None => self.put_if(name, plain, None).await,
+        }
+    }
+
+    /// Lists the folder with a single PROPFIND, creating it when missing.
+    /// Returns the version of every file by name. Any other failure is an
+    /// error, so an unreachable server does not pass for an empty folder.
+    async fn list(&self) -> Result<BTreeMap<String, String>, String> {
+        let entities = match self.client.list(self.path, Depth::Number(1)).await {
+            Ok(entities) => entities,
+            Err(e) if error_status(&e) == Some(StatusCode::NOT_FOUND.as_u16()) => {
+                self.client
+                    .mkcol(self.path)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                return Ok(BTreeMap::new());
             }
+            Err(e) => return Err(format!("{}: {e}", self.path)),
+        };
+        Ok(entities
+            .into_iter()
+            .filter_map(|entity| match entity {
+                ListEntity::File(file) => {
+                    let name = file.href.split('/').next_back()?.to_string();
+                    Some((name, file_version(&file)))
+                }
+                ListEntity::Folder(_) => None,
+            })
+            .collect())
+    }
+
+    /// The version of the file `name`, `None` when it does not exist.
+    async fn version(&self, name: &str) -> Option<String> {
+        let entities = self
+            .client
+            .list(&self.path_of(name), Depth::Number(0))
+            .await
+            .ok()?;
+        entities.into_iter().find_map(|entity| match entity {
+            ListEntity::File(file) => Some(file_version(&file)),
+            ListEntity::Folder(_) => None,
         })
-        .collect())
+    }
 }
 
-async fn get_local_sync_data(
-    pool: &sqlx::SqlitePool,
-    last_synced_at: i64,
-) -> Result<Vec<ArticleSync>, String> {
+/// The ETag of a listed file, or its modification time when the server
+/// sends none.
+fn file_version(file: &ListFile) -> String {
+    file.tag
+        .clone()
+        .unwrap_or_else(|| file.last_modified.to_rfc3339())
+}
+
+async fn get_local_articles(pool: &sqlx::SqlitePool) -> Result<Vec<ArticleSync>, String> {
     sqlx::query_as::<_, ArticleSync>(
         r"
         SELECT url, created_at, updated_at, is_deleted
         FROM articles
-        WHERE datetime(updated_at) > datetime(?, 'unixepoch')
     ",
     )
-    .bind(last_synced_at)
     .fetch_all(pool)
     .await
     .map_err(|e| e.to_string())
 }
 
-async fn reconcile_and_process(
-    client: &Client,
+async fn load_content(pool: &sqlx::SqlitePool, url: &str) -> Result<ArticleContent, String> {
+    sqlx::query_as::<_, ArticleContent>(
+        r"
+        SELECT title, body, text_content, site_name, image_url
+        FROM articles
+        WHERE url = ?
+    ",
+    )
+    .bind(url)
+    .fetch_optional(pool)
+    .await
+    .map(Option::unwrap_or_default)
+    .map_err(|e| e.to_string())
+}
+
+/// Stores the manifest record of `url`, with its content when it must be
+/// downloaded. A failed download leaves the article untouched, so the
+/// next sync pulls it again.
+async fn pull_article(
+    remote: &Remote<'_>,
     pool: &sqlx::SqlitePool,
-    sync_path: &str,
-    local_articles: Vec<ArticleSync>,
-    remote_entities: Vec<ListFile>,
-    progress_channel: Channel<SyncProgress>,
+    url: &str,
+    record: &SyncedRecord<ArticleEntry>,
+    sync_content: bool,
 ) -> Result<(), String> {
-    use std::collections::HashSet;
+    let mut content = None;
+    if sync_content && !record.is_deleted {
+        let local_hash = load_content(pool, url).await?.hash();
+        let remote_hash = record.value.content_hash.as_deref();
+        if sync::needs_content(local_hash.as_deref(), remote_hash) {
+            let bytes = remote
+                .get(&remote.folder.content_name(url))
+                .await?
+                .ok_or_else(|| format!("Missing content of {url}"))?;
+            content = Some(ArticleContent::decode(&bytes)?);
+        }
+    }
 
-    let mut all_hashes = HashSet::new();
+    sqlx::query(
+        r"
+        INSERT INTO articles (url, created_at, updated_at, is_deleted, title, body, text_content)
+        VALUES ($1, $2, $3, $4, '', '', '')
+        ON CONFLICT(url) DO UPDATE SET
+            created_at = excluded.created_at,
+            updated_at = excluded.updated_at,
+            is_deleted = excluded.is_deleted,
+            title = CASE WHEN excluded.is_deleted = 1 THEN '' ELSE title END,
+            body = CASE WHEN excluded.is_deleted = 1 THEN '' ELSE body END,
+            text_content = CASE WHEN excluded.is_deleted = 1 THEN '' ELSE text_content END
+    ",
+    )
+    .bind(url)
+    .bind(&record.value.created_at)
+    .bind(&record.updated_at)
+    .bind(i32::from(record.is_deleted))
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
 
-    for article in &local_articles {
-        all_hashes.insert(url_to_path(&article.url));
+    if let Some(content) = content {
+        sqlx::query(
+            r"
+            UPDATE articles SET
+                title = $1,
+                body = $2,
+                text_content = $3,
+                site_name = $4,
+                image_url = $5
+            WHERE url = $6 AND is_deleted = 0
+        ",
+        )
+        .bind(&content.title)
+        .bind(&content.body)
+        .bind(&content.text_content)
+        .bind(&content.site_name)
+        .bind(&content.image_url)
+        .bind(url)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
     }
+    Ok(())
+}
 
-    for entity in &remote_entities {
-        if let Some(filename) = entity.href.split('/').next_back() {
-            all_hashes.insert(filename.to_string());
+/// Uploads the content file of a local record that replaces `previous`,
+/// or removes it once the article is deleted.
+async fn push_content(
+    remote: &Remote<'_>,
+    pool: &sqlx::SqlitePool,
+    url: &str,
+    record: &SyncedRecord<ArticleEntry>,
+    previous: Option<&SyncedRecord<ArticleEntry>>,
+) -> Result<(), String> {
+    let previous_hash = previous.and_then(|p| p.value.content_hash.as_ref());
+    let name = remote.folder.content_name(url);
+    if record.is_deleted {
+        if previous_hash.is_some() {
+            // Fails harmlessly when another device removed it already.
+            let _ = remote.client.delete(&remote.path_of(&name)).await;
         }
+        return Ok(());
+    }
+    if record.value.content_hash.is_none() || record.value.content_hash.as_ref() == previous_hash {
+        return Ok(());
     }
+    let content = load_content(pool, url).await?;
+    remote.put(&name, content.encode()?).await
+}
 
-    let total = all_hashes.len();
-    for (i, hash) in all_hashes.iter().enumerate() {
-        let path = format!("{sync_path}/{hash}");
+/// Reconciles the articles through the manifest. `known_state` is the
+/// folder state returned by the last sync; when neither it nor the local
+/// articles changed, nothing is read or written. Returns the new state.
+async fn sync_manifest(
+    remote: &Remote<'_>,
+    pool: &sqlx::SqlitePool,
+    last_synced_at: i64,
+    known_state: Option<&str>,
+    sync_content: bool,
+    progress_channel: &Channel<SyncProgress>,
+) -> Result<String, String> {
+    let files = remote.list().await?;
+    let manifest_name = remote.folder.set_name(sync::MANIFEST_SET);
+    let legacy_files: BTreeMap<String, String> = files
+        .iter()
+        .filter(|(name, _)| sync::is_record_name(name))
+        .map(|(name, version)| (name.clone(), version.clone()))
+        .collect();
+    let state = sync::folder_state(files.get(&manifest_name).map(String::as_str), &legacy_files);
 
-        let local_article = local_articles.iter().find(|a| &url_to_path(&a.url) == hash);
+    let local_articles = get_local_articles(pool).await?;
+    let changed = |article: &ArticleSync| iso_to_timestamp(&article.updated_at) > last_synced_at;
+    if known_state == Some(state.as_str()) && !local_articles.iter().any(changed) {
+        return Ok(state);
+    }
 
-        let remote_resp = client.get(&path).await;
-        let remote_article = if let Ok(resp) = remote_resp {
-            let content = resp.text().await.map_err(|e| e.to_string())?;
-            serde_json::from_str::<ArticleSync>(&content).ok()
-        } else {
-            None
+    let mut result = Ok(());
+    for _ in 0..MERGE_ATTEMPTS {
+        // `None` when there is no manifest yet, so one written meanwhile is
+        // not overwritten. `Some(None)` when the server sends no `ETag`.
+        let (mut manifest, etag) = match remote.get_tagged(&manifest_name).await? {
+            Some((bytes, etag)) => (Manifest::decode(&bytes)?, Some(etag)),
+            None => (Manifest::default(), None),
         };
 
-        match (local_article, remote_article) {
-            (Some(local), Some(remote)) => {
-                let local_ts = iso_to_timestamp(&local.updated_at);
-                let remote_ts = iso_to_timestamp(&remote.updated_at);
-
-                if local_ts > remote_ts {
-                    let content = serde_json::to_string(&local).map_err(|e| e.to_string())?;
-                    client
-                        .put(&path, content)
-                        .await
-                        .map_err(|e| e.to_string())?;
-                } else if remote_ts > local_ts {
-                    sqlx::query(
-                        r"
-                        UPDATE articles SET
-                            updated_at = $1,
-                            is_deleted = $2,
-                            title = CASE WHEN $2 = 1 THEN '' ELSE title END,
-                            body = CASE WHEN $2 = 1 THEN '' ELSE body END,
-                            text_content = CASE WHEN $2 = 1 THEN '' ELSE text_content END
-                        WHERE url = $3
-                    ",
-                    )
-                    .bind(&remote.updated_at)
-                    .bind(remote.is_deleted)
-                    .bind(&remote.url)
-                    .execute(pool)
-                    .await
-                    .map_err(|e| e.to_string())?;
-                }
-            }
-            (Some(local), None) => {
-                let content = serde_json::to_string(&local).map_err(|e| e.to_string())?;
-                client
-                    .put(&path, content)
-                    .await
-                    .map_err(|e| e.to_string())?;
-            }
-            (None, Some(remote)) => {
-                sqlx::query(r"
-                    INSERT INTO articles (url, created_at, updated_at, is_deleted, title, body, text_content)
-                    VALUES ($1, $2, $3, $4, '', '', '')
-                    ON CONFLICT(url) DO UPDATE SET
-                        created_at = excluded.created_at,
-                        updated_at = excluded.updated_at,
-                        is_deleted = excluded.is_deleted,
-                        title = CASE WHEN excluded.is_deleted = 1 THEN '' ELSE title END,
-                        body = CASE WHEN excluded.is_deleted = 1 THEN '' ELSE body END,
-                        text_content = CASE WHEN excluded.is_deleted = 1 THEN '' ELSE text_content END
-                ")
-                    .bind(&remote.url)
-                    .bind(&remote.created_at)
-                    .bind(&remote.updated_at)
-                    .bind(remote.is_deleted)
-                    .execute(pool)
-                    .await
-                    .map_err(|e| e.to_string())?;
+        // Fold in the record files of older versions that changed. Only a
+        // failed download is retried; a file that cannot be opened or read
+        // would fail every sync.
+        let stale = manifest.stale_legacy(&legacy_files);
+        let legacy_changed = !stale.is_empty();
+        let mut fetched = stream::iter(stale)
+            .map(|name| async move {
+                let bytes = remote.get_sealed(&name).await;
+                (name, bytes)
+            })
+            .buffer_unordered(MAX_CONCURRENT_REQUESTS);
+        while let Some((name, bytes)) = fetched.next().await {
+            let plain = bytes?.and_then(|(bytes, _)| remote.folder.open(&name, bytes).ok());
+            manifest.merge_legacy_file(&name, &legacy_files[&name], plain.as_deref());
+        }
+
+        let mut local = RecordSet::new();
+        for article in &get_local_articles(pool).await? {
+            let known_hash = manifest
+                .articles
+                .get(&article.url)
+                .and_then(|record| record.value.content_hash.clone());
+            let mut article = article.clone();
+            article.content_hash = if article.is_deleted == 1 {
+                None
+            } else if sync_content {
+                // Every article, so content added while content sync was off
+                // is uploaded too. Without content here, keep what other
+                // devices uploaded.
+                load_content(pool, &article.url)
+                    .await?
+                    .hash()
+                    .or(known_hash)
+            } else {
+                known_hash
+            };
+            let (url, record) = sync::article_record(&article);
+            local.insert(url, record);
+        }
+        let merged = sync::merge_articles(&local, &manifest.articles);
+
+        let total = merged.pull.len() + merged.push.len();
+        let mut processed = 0;
+        let mut progress = || {
+            processed += 1;
+            let _ = progress_channel.send(SyncProgress {
+                count_processed: processed,
+                total_count: total,
+            });
+        };
+
+        let mut pulls = stream::iter(&merged.pull)
+            .map(|(url, record)| pull_article(remote, pool, url, record, sync_content))
+            .buffer_unordered(MAX_CONCURRENT_REQUESTS);
+        while let Some(outcome) = pulls.next().await {
+            if let Err(e) = outcome {
+                eprintln!("{e}");
+                result = Err(e);
             }
-            (None, None) => {}
+            progress();
+        }
+
+        let mut pushes = stream::iter(&merged.push)
+            .map(|url| {
+                let record = &merged.records[url];
+                let previous = manifest.articles.get(url);
+                async move {
+                    if sync_content {
+                        push_content(remote, pool, url, record, previous).await
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .buffer_unordered(MAX_CONCURRENT_REQUESTS);
+        while let Some(outcome) = pushes.next().await {
+            // The manifest must not name content that is not on the server.
+            outcome?;
+            progress();
         }
+        drop(pushes);
 
-        let _ = progress_channel.send(SyncProgress {
-            count_processed: i + 1,
-            total_count: total,
-        });
+        if merged.push.is_empty() && !legacy_changed {
+            return result.map(|()| state);
+        }
+        let manifest = Manifest {
+            articles: merged.records,
+            ..manifest
+        };
+        let body = manifest.encode()?;
+        let etag = etag.as_ref().map(Option::as_deref);
+        if remote.put_over(&manifest_name, body, etag).await? {
+            let version = remote.version(&manifest_name).await;
+            return result.map(|()| sync::folder_state(version.as_deref(), &legacy_files));
+        }
+        // Another device wrote the manifest since it was read: merge its
+        // copy instead.
     }
+    Err("The sync manifest keeps changing, sync again later".to_string())
+}
+
+#[derive(sqlx::FromRow)]
+struct SettingRow {
+    name: String,
+    value: String,
+    updated_at: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct RuleRow {
+    #[sqlx(flatten)]
+    rule: PronunciationRule,
+    updated_at: String,
+    is_deleted: i32,
+}
+
+async fn local_settings(pool: &sqlx::SqlitePool) -> Result<RecordSet<String>, String> {
+    let rows = sqlx::query_as::<_, SettingRow>("SELECT name, value, updated_at FROM settings")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(sync::synced_settings(
+        rows.into_iter()
+            .map(|row| {
+                let record = SyncedRecord {
+                    updated_at: row.updated_at,
+                    is_deleted: false,
+                    value: row.value,
+                };
+                (row.name, record)
+            })
+            .collect(),
+    ))
+}
+
+async fn local_rules(pool: &sqlx::SqlitePool) -> Result<RecordSet<PronunciationRule>, String> {
+    let rows = sqlx::query_as::<_, RuleRow>(
+        r"
+        SELECT match_pattern, replacement, is_regex, priority, enabled,
+               language, domain, scope, whole_word, case_insensitive,
+               preserve_case, phoneme, updated_at, is_deleted
+        FROM pronunciation_rules
+    ",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let record = SyncedRecord {
+                updated_at: row.updated_at,
+                is_deleted: row.is_deleted == 1,
+                value: row.rule,
+            };
+            (sync::rule_key(&record.value), record)
+        })
+        .collect())
+}
+
+/// Downloads the record set `set`, empty when no device uploaded it yet,
+/// along with its `ETag` as `Remote::put_over` takes it.
+async fn get_record_set<T: DeserializeOwned>(
+    remote: &Remote<'_>,
+    set: &str,
+) -> Result<(RecordSet<T>, Option<Option<String>>), String> {
+    match remote.get_tagged(&remote.folder.set_name(set)).await? {
+        Some((bytes, etag)) => {
+            let records =
+                serde_json::from_slice(&bytes).map_err(|e| format!("Invalid {set} file: {e}"))?;
+            Ok((records, Some(etag)))
+        }
+        None => Ok((RecordSet::new(), None)),
+    }
+}
+
+/// Uploads the record set `set` over the version read by `get_record_set`.
+/// Returns `false`, having written nothing, when another device changed it
+/// meanwhile.
+async fn put_record_set<T: Serialize>(
+    remote: &Remote<'_>,
+    set: &str,
+    records: &RecordSet<T>,
+    etag: Option<Option<&str>>,
+) -> Result<bool, String> {
+    let body = serde_json::to_vec(records).map_err(|e| e.to_string())?;
+    remote
+        .put_over(&remote.folder.set_name(set), body, etag)
+        .await
+}
+
+/// Reconciles the synced settings and the pronunciation rules, record by
+/// record. Like the manifest, each set is only written over the version
+/// that was read, and merged again when another device wrote it first.
+async fn sync_user_data(remote: &Remote<'_>, pool: &sqlx::SqlitePool) -> Result<(), String> {
+    sync_settings(remote, pool).await?;
+    sync_rules(remote, pool).await?;
+
+    // The server keeps the deletions for the other devices.
+    sqlx::query("DELETE FROM pronunciation_rules WHERE is_deleted = 1")
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
     Ok(())
 }
 
+async fn sync_settings(remote: &Remote<'_>, pool: &sqlx::SqlitePool) -> Result<(), String> {
+    for _ in 0..MERGE_ATTEMPTS {
+        let (records, etag) = get_record_set(remote, sync::SETTINGS_SET).await?;
+        let merged = sync::merge(
+            &local_settings(pool).await?,
+            &sync::synced_settings(records),
+        );
+        for (name, record) in &merged.pull {
+            sqlx::query(
+                r"
+                INSERT INTO settings (name, value, default_value, updated_at)
+                VALUES ($1, $2, '', $3)
+                ON CONFLICT(name) DO UPDATE SET
+                    value = $2,
+                    updated_at = $3
+            ",
+            )
+            .bind(name)
+            .bind(&record.value)
+            .bind(&record.updated_at)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        let etag = etag.as_ref().map(Option::as_deref);
+        if merged.push.is_empty()
+            || put_record_set(remote, sync::SETTINGS_SET, &merged.records, etag).await?
+        {
+            return Ok(());
+        }
+    }
+    Err("The synced settings keep changing, sync again later".to_string())
+}
+
+async fn sync_rules(remote: &Remote<'_>, pool: &sqlx::SqlitePool) -> Result<(), String> {
+    for _ in 0..MERGE_ATTEMPTS {
+        let (records, etag) = get_record_set(remote, sync::PRONUNCIATION_SET).await?;
+        let merged = sync::merge(&local_rules(pool).await?, &sync::keyed_rules(records));
+        for record in merged.pull.values() {
+            if record.is_deleted {
+                let rule = &record.value;
+                sqlx::query(
+                    r"
+                    UPDATE pronunciation_rules
+                    SET is_deleted = 1, updated_at = $5
+                    WHERE match_pattern = $1 AND language = $2 AND domain = $3 AND scope = $4
+                ",
+                )
+                .bind(&rule.match_pattern)
+                .bind(&rule.language)
+                .bind(&rule.domain)
+                .bind(&rule.scope)
+                .bind(&record.updated_at)
+                .execute(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+            } else if let Err(e) = save_rule(pool, &record.value, Some(&record.updated_at)).await {
+                // A rule this version cannot compile stays on the server.
+                eprintln!("{e}");
+            }
+        }
+        let etag = etag.as_ref().map(Option::as_deref);
+        if merged.push.is_empty()
+            || put_record_set(remote, sync::PRONUNCIATION_SET, &merged.records, etag).await?
+        {
+            return Ok(());
+        }
+    }
+    Err("The synced pronunciation rules keep changing, sync again later".to_string())
+}
+
+async fn read_key_file(client: &Client, sync_path: &str) -> Result<Option<KeyFile>, String> {
+    let path = format!("{sync_path}/{}", sync::KEY_FILE_NAME);
+    let Some(resp) = download(client, &path).await? else {
+        return Ok(None);
+    };
+    let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
+    serde_json::from_slice(&bytes)
+        .map(Some)
+        .map_err(|e| format!("Invalid sync key file: {e}"))
+}
+
+/// The files of a sync folder in the clear, see `sync::plain_files`. A
+/// manifest that does not open in the clear fails too.
+async fn plain_files(plain: &Remote<'_>) -> Result<Vec<String>, String> {
+    let files = plain.list().await?;
+    let manifest_name = plain.folder.set_name(sync::MANIFEST_SET);
+    let manifest = match plain.get(&manifest_name).await? {
+        Some(bytes) => Some(Manifest::decode(&bytes).map_err(|e| {
+            format!("The sync folder is not in the clear, restore its key file: {e}")
+        })?),
+        None => None,
+    };
+    sync::plain_files(files.keys(), manifest.as_ref())
+}
+
+/// Moves a sync folder in the clear to encryption: merges the changes other
+/// devices left there, removes their files and uploads the key file. The
+/// sync that follows uploads everything again, encrypted. A folder holding
+/// encrypted files is left alone.
+async fn encrypt_folder(
+    plain: &Remote<'_>,
+    pool: &sqlx::SqlitePool,
+    passphrase: &str,
+    last_synced_at: i64,
+    sync_content: bool,
+    progress_channel: &Channel<SyncProgress>,
+) -> Result<Folder, String> {
+    let (key, key_file) = SyncKey::create(passphrase)?;
+
+    if !plain_files(plain).await?.is_empty() {
+        sync_manifest(
+            plain,
+            pool,
+            last_synced_at,
+            None,
+            sync_content,
+            progress_channel,
+        )
+        .await?;
+        sync_user_data(plain, pool).await?;
+    }
+
+    for name in plain_files(plain).await? {
+        plain
+            .client
+            .delete(&plain.path_of(&name))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let body = serde_json::to_vec(&key_file).map_err(|e| e.to_string())?;
+    plain
+        .client
+        .put(&plain.path_of(sync::KEY_FILE_NAME), body)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Folder::encrypted(key))
+}
+
 #[tauri::command]
 pub async fn sync_articles<R: Runtime>(
     _app_handle: AppHandle<R>,
@@ -207,6 +731,10 @@ pub async fn sync_articles<R: Runtime>(
             .execute(pool)
             .await
             .map_err(|e| e.to_string())?;
+        sqlx::query("DELETE FROM pronunciation_rules WHERE is_deleted = 1")
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
         return Ok(());
     }
 
@@ -224,6 +752,14 @@ pub async fn sync_articles<R: Runtime>(
         .await
         .unwrap_or_default();
 
+    let sync_content = get_setting("webdavSyncContent".to_string(), db_instances.clone())
+        .await
+        .unwrap_or_default()
+        == "true";
+    let passphrase = get_setting("webdavPassphrase".to_string(), db_instances.clone())
+        .await
+        .unwrap_or_default();
+
     let client = setup_webdav_client(url, username, password, &auth_type);
     let sync_path = format!("{}/.io.github.sak.read.it.later", &path);
 
@@ -231,25 +767,59 @@ pub async fn sync_articles<R: Runtime>(
     let tauri_plugin_sql::DbPool::Sqlite(pool) = instances.get(DB_URL).ok_or("db not loaded")?;
 
     let new_synced_at = Utc::now().timestamp();
-    let last_synced_at = get_setting("lastSyncedAt".to_string(), db_instances.clone())
+    let mut last_synced_at = get_setting("lastSyncedAt".to_string(), db_instances.clone())
         .await
         .unwrap_or_else(|_| "0".to_string())
         .parse::<i64>()
         .unwrap_or(0);
 
-    let remote_entities = get_remote_entities(&client, &sync_path, last_synced_at).await?;
-    let local_articles = get_local_sync_data(pool, last_synced_at).await?;
+    let mut known_state = get_setting("webdavFolderState".to_string(), db_instances.clone())
+        .await
+        .ok();
+
+    let mut remote = Remote {
+        client: &client,
+        path: &sync_path,
+        folder: Folder::default(),
+    };
+    match read_key_file(&client, &sync_path).await? {
+        Some(key_file) if !passphrase.is_empty() => {
+            remote.folder = Folder::encrypted(SyncKey::unlock(&passphrase, &key_file)?);
+        }
+        Some(_) => {
+            return Err(
+                "The sync folder is encrypted, enter its passphrase in the WebDAV settings"
+                    .to_string(),
+            );
+        }
+        None if passphrase.is_empty() => {}
+        None => {
+            remote.folder = encrypt_folder(
+                &remote,
+                pool,
+                &passphrase,
+                last_synced_at,
+                sync_content,
+                &progress_channel,
+            )
+            .await?;
+            last_synced_at = 0;
+            known_state = None;
+        }
+    }
 
-    reconcile_and_process(
-        &client,
+    let state = sync_manifest(
+        &remote,
         pool,
-        &sync_path,
-        local_articles,
-        remote_entities,
-        progress_channel,
+        last_synced_at,
+        known_state.as_deref(),
+        sync_content,
+        &progress_channel,
     )
     .await?;
+    sync_user_data(&remote, pool).await?;
 
+    set_setting("webdavFolderState".to_string(), state, db_instances.clone()).await?;
     set_setting(
         "lastSyncedAt".to_string(),
         new_synced_at.to_string(),
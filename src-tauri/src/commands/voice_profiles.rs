@@ -0,0 +1,212 @@
+use super::speakbar::{SpeakBarState, apply_voice_profile};
+use crate::models::{DB_URL, VoiceProfile};
+use crate::parse::primary_language;
+use sqlx::{SqlitePool, query, query_as, query_scalar};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_sql::DbInstances;
+
+async fn get_all_profiles(pool: &SqlitePool) -> Result<Vec<VoiceProfile>, String> {
+    query_as::<_, VoiceProfile>(
+        "SELECT id, name, voice_id, rate, pitch, volume, language FROM voice_profiles ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+fn validate_profile(profile: &VoiceProfile) -> Result<(), String> {
+    if profile.name.trim().is_empty() {
+        return Err("Profile name is empty".to_string());
+    }
+    if !(0.1..=10.0).contains(&profile.rate) {
+        return Err(format!("Invalid rate: {}", profile.rate));
+    }
+    if !(0.5..=2.0).contains(&profile.pitch) {
+        return Err(format!("Invalid pitch: {}", profile.pitch));
+    }
+    if !(0.0..=1.0).contains(&profile.volume) {
+        return Err(format!("Invalid volume: {}", profile.volume));
+    }
+    Ok(())
+}
+
+/// Saves a profile by name. A profile saved with a language replaces the
+/// previous default profile of that language.
+async fn save_profile(pool: &SqlitePool, profile: &VoiceProfile) -> Result<VoiceProfile, String> {
+    validate_profile(profile)?;
+    let language = primary_language(&profile.language);
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    if !language.is_empty() {
+        query("UPDATE voice_profiles SET language = '' WHERE language = $1 AND name != $2")
+            .bind(&language)
+            .bind(profile.name.trim())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let saved = query_as::<_, VoiceProfile>(
+        r"
+        INSERT INTO voice_profiles (name, voice_id, rate, pitch, volume, language)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT(name) DO UPDATE SET
+            voice_id = $2,
+            rate = $3,
+            pitch = $4,
+            volume = $5,
+            language = $6
+        RETURNING id, name, voice_id, rate, pitch, volume, language
+        ",
+    )
+    .bind(profile.name.trim())
+    .bind(profile.voice_id.as_deref().filter(|id| !id.is_empty()))
+    .bind(profile.rate)
+    .bind(profile.pitch)
+    .bind(profile.volume)
+    .bind(&language)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(saved)
+}
+
+/// The profile for an article: its own override, else the default profile
+/// of `language`.
+pub(crate) async fn resolve_voice_profile(
+    pool: &SqlitePool,
+    article_id: i32,
+    language: &str,
+) -> Result<Option<VoiceProfile>, String> {
+    let profile = query_as::<_, VoiceProfile>(
+        r"
+        SELECT p.id, p.name, p.voice_id, p.rate, p.pitch, p.volume, p.language
+        FROM article_voice_profiles a
+        JOIN voice_profiles p ON p.id = a.profile_id
+        WHERE a.article_id = $1
+        ",
+    )
+    .bind(article_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if profile.is_some() || language.is_empty() {
+        return Ok(profile);
+    }
+    query_as::<_, VoiceProfile>(
+        r"
+        SELECT id, name, voice_id, rate, pitch, volume, language
+        FROM voice_profiles
+        WHERE language = $1
+        ",
+    )
+    .bind(primary_language(language))
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_voice_profiles(
+    db_instances: State<'_, DbInstances>,
+) -> Result<Vec<VoiceProfile>, String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => get_all_profiles(pool).await,
+    }
+}
+
+#[tauri::command]
+pub async fn save_voice_profile(
+    profile: VoiceProfile,
+    db_instances: State<'_, DbInstances>,
+) -> Result<VoiceProfile, String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => save_profile(pool, &profile).await,
+    }
+}
+
+#[tauri::command]
+pub async fn delete_voice_profile(
+    id: i64,
+    db_instances: State<'_, DbInstances>,
+) -> Result<(), String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            query("DELETE FROM article_voice_profiles WHERE profile_id = $1")
+                .bind(id)
+                .execute(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+            query("DELETE FROM voice_profiles WHERE id = $1")
+                .bind(id)
+                .execute(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+}
+
+/// The profile chosen for an article, or `None` when it follows its
+/// language.
+#[tauri::command]
+pub async fn get_article_voice_profile(
+    article_id: i32,
+    db_instances: State<'_, DbInstances>,
+) -> Result<Option<i64>, String> {
+    let instances = db_instances.0.read().await;
+    let db = instances.get(DB_URL).ok_or("db not loaded")?;
+    match db {
+        tauri_plugin_sql::DbPool::Sqlite(pool) => {
+            query_scalar("SELECT profile_id FROM article_voice_profiles WHERE article_id = $1")
+                .bind(article_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Overrides the profile of an article, or clears the override when
+/// `profile_id` is `None`. Returns the profile now applied when the article
+/// is loaded in the speak bar.
+#[tauri::command]
+pub async fn set_article_voice_profile(
+    app: AppHandle,
+    article_id: i32,
+    profile_id: Option<i64>,
+    state: State<'_, SpeakBarState>,
+) -> Result<Option<VoiceProfile>, String> {
+    {
+        let instances = app.state::<DbInstances>();
+        let instances = instances.0.read().await;
+        let db = instances.get(DB_URL).ok_or("db not loaded")?;
+        match db {
+            tauri_plugin_sql::DbPool::Sqlite(pool) => {
+                let statement = match profile_id {
+                    Some(profile_id) => query(
+                        r"
+                        INSERT INTO article_voice_profiles (article_id, profile_id)
+                        VALUES ($1, $2)
+                        ON CONFLICT(article_id) DO UPDATE SET profile_id = $2
+                        ",
+                    )
+                    .bind(article_id)
+                    .bind(profile_id),
+                    None => query("DELETE FROM article_voice_profiles WHERE article_id = $1")
+                        .bind(article_id),
+                };
+                statement.execute(pool).await.map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    if state.lock().article.id != Some(article_id) {
+        return Ok(None);
+    }
+    apply_voice_profile(&app, &state).await
+}
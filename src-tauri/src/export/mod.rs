@@ -0,0 +1,332 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::models::TocEntry;
+
+mod wav;
+
+pub use wav::{Pcm, WavFormat, parse_wav, write_wav};
+
+// Audio Export:
+//
+// 1. The spoken units of the speak bar (after block policies,
+//    normalization and pronunciation rules) are synthesized one at a time
+//    by a `SynthesisBackend`, which returns a PCM WAV per unit.
+// 2. Units are concatenated with the speak bar pauses between them. All
+//    units must share the sample format of the first one.
+// 3. Every TOC heading starts a chapter at its first unit; text before
+//    the first heading has no chapter.
+// 4. WAV files carry the chapters as cue points with labels. OGG and MP3
+//    are encoded by `ffmpeg` from the WAV with an FFMETADATA chapter file.
+
+pub const BACKEND_SETTING: &str = "exportBackend";
+pub const DEFAULT_BACKEND: &str = "espeak-ng";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    Wav,
+    Ogg,
+    Mp3,
+}
+
+impl AudioFormat {
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Ogg => "ogg",
+            AudioFormat::Mp3 => "mp3",
+        }
+    }
+
+    #[must_use]
+    pub fn mime(self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Ogg => "audio/ogg",
+            AudioFormat::Mp3 => "audio/mpeg",
+        }
+    }
+
+    fn codec(self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "pcm_s16le",
+            AudioFormat::Ogg => "libvorbis",
+            AudioFormat::Mp3 => "libmp3lame",
+        }
+    }
+}
+
+/// Voice settings for the whole export, as multipliers of the engine
+/// defaults like the speak bar uses them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voice {
+    pub language: String,
+    pub rate: f32,
+    pub pitch: f32,
+    pub volume: f32,
+}
+
+pub trait SynthesisBackend {
+    /// Synthesizes `text` into a PCM WAV file.
+    fn synthesize(&self, text: &str, voice: &Voice) -> Result<Vec<u8>, String>;
+}
+
+/// The `espeak-ng` command-line engine.
+pub struct Espeak {
+    pub program: PathBuf,
+}
+
+impl Default for Espeak {
+    fn default() -> Self {
+        Self {
+            program: PathBuf::from("espeak-ng"),
+        }
+    }
+}
+
+/// espeak-ng defaults: 175 words per minute, pitch 50 of 0-99, amplitude
+/// 100 of 0-200.
+const ESPEAK_WPM: f32 = 175.0;
+const ESPEAK_PITCH: f32 = 50.0;
+const ESPEAK_AMPLITUDE: f32 = 100.0;
+
+impl Espeak {
+    /// Command line for `text`; SSML units (with phoneme hints) need `-m`
+    /// or the markup is read out.
+    fn args(text: &str, voice: &Voice) -> Vec<String> {
+        let scaled = |default: f32, factor: f32, max: f32| {
+            format!("{:.0}", (default * factor).clamp(0.0, max))
+        };
+        let mut args = vec![
+            "--stdout".to_string(),
+            "--stdin".to_string(),
+            "-s".to_string(),
+            scaled(ESPEAK_WPM, voice.rate, 900.0),
+            "-p".to_string(),
+            scaled(ESPEAK_PITCH, voice.pitch, 99.0),
+            "-a".to_string(),
+            scaled(ESPEAK_AMPLITUDE, voice.volume, 200.0),
+        ];
+        if text.starts_with("<speak>") {
+            args.push("-m".to_string());
+        }
+        if !voice.language.is_empty() {
+            args.push("-v".to_string());
+            args.push(voice.language.to_lowercase());
+        }
+        args
+    }
+}
+
+impl SynthesisBackend for Espeak {
+    fn synthesize(&self, text: &str, voice: &Voice) -> Result<Vec<u8>, String> {
+        let mut child = Command::new(&self.program)
+            .args(Self::args(text, voice))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("could not start {}: {e}", self.program.display()))?;
+        // Written while the output is read: espeak-ng speaks a long text
+        // before reading all of it, and blocks once nobody drains stdout.
+        let mut stdin = child.stdin.take().ok_or("espeak-ng has no stdin")?;
+        let input = text.as_bytes().to_vec();
+        let writer = std::thread::spawn(move || stdin.write_all(&input));
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        let written = writer
+            .join()
+            .map_err(|_| "espeak-ng input thread panicked")?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        written.map_err(|e| e.to_string())?;
+        Ok(output.stdout)
+    }
+}
+
+/// Returns the backend stored in the `exportBackend` setting.
+pub fn backend(name: &str) -> Result<Box<dyn SynthesisBackend + Send>, String> {
+    match name {
+        "" | DEFAULT_BACKEND => Ok(Box::new(Espeak::default())),
+        other => Err(format!("unknown synthesis backend: {other}")),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub title: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+#[derive(Debug)]
+pub struct Rendered {
+    pub format: WavFormat,
+    pub data: Vec<u8>,
+    pub chapters: Vec<Chapter>,
+    pub duration_ms: u64,
+}
+
+/// Synthesizes and concatenates the units. `pauses[i]` is the silence
+/// after unit `i`; `progress` is called after each unit with the number of
+/// units done and the total.
+pub fn render(
+    units: &[String],
+    pauses: &[Duration],
+    toc: &[TocEntry],
+    backend: &dyn SynthesisBackend,
+    voice: &Voice,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<Rendered, String> {
+    let mut format: Option<WavFormat> = None;
+    let mut data = Vec::new();
+    let mut starts = Vec::with_capacity(units.len());
+    for (i, text) in units.iter().enumerate() {
+        let elapsed = format.map_or(0, |format| format.duration_ms(data.len()));
+        starts.push(elapsed);
+        if !text.trim().is_empty() {
+            let pcm = parse_wav(&backend.synthesize(text, voice)?)?;
+            if format.is_some_and(|format| format != pcm.format) {
+                return Err("synthesis backend changed the sample format".to_string());
+            }
+            let current = *format.get_or_insert(pcm.format);
+            data.extend_from_slice(&pcm.data);
+            let pause = pauses.get(i).copied().unwrap_or_default();
+            let pause_ms = u64::try_from(pause.as_millis()).unwrap_or(u64::MAX);
+            data.extend_from_slice(&current.silence(pause_ms));
+        }
+        progress(i + 1, units.len());
+    }
+    let format = format.ok_or("nothing to export")?;
+    let duration_ms = format.duration_ms(data.len());
+    Ok(Rendered {
+        format,
+        data,
+        chapters: chapters(toc, &starts, duration_ms),
+        duration_ms,
+    })
+}
+
+/// A chapter per heading, from its first unit to the next heading.
+#[must_use]
+pub fn chapters(toc: &[TocEntry], starts: &[u64], duration_ms: u64) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = toc
+        .iter()
+        .filter_map(|entry| {
+            Some(Chapter {
+                title: entry.text.clone(),
+                start_ms: *starts.get(entry.para)?,
+                end_ms: duration_ms,
+            })
+        })
+        .collect();
+    for i in 1..chapters.len() {
+        chapters[i - 1].end_ms = chapters[i].start_ms;
+    }
+    chapters.retain(|chapter| chapter.start_ms < chapter.end_ms);
+    chapters
+}
+
+fn escape_metadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if matches!(ch, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// FFMETADATA file with the title and the chapters in milliseconds.
+#[must_use]
+pub fn ffmetadata(title: &str, chapters: &[Chapter]) -> String {
+    let mut metadata = format!(";FFMETADATA1\ntitle={}\n", escape_metadata(title));
+    for chapter in chapters {
+        let _ = write!(
+            metadata,
+            "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            chapter.start_ms,
+            chapter.end_ms,
+            escape_metadata(&chapter.title)
+        );
+    }
+    metadata
+}
+
+/// Temporary file removed on drop.
+struct TempFile(PathBuf);
+
+/// Tells apart the files of exports running at the same time.
+static NEXT_TEMP_FILE: AtomicUsize = AtomicUsize::new(0);
+
+impl TempFile {
+    /// A path in `dir` no other export uses, not created yet.
+    fn unique(dir: &Path, name: &str) -> Self {
+        let id = NEXT_TEMP_FILE.fetch_add(1, Ordering::Relaxed);
+        Self(dir.join(format!(
+            "read_later_export_{}_{id}_{name}",
+            std::process::id()
+        )))
+    }
+
+    fn new(dir: &Path, name: &str, contents: &[u8]) -> Result<Self, String> {
+        let file = Self::unique(dir, name);
+        std::fs::write(&file.0, contents).map_err(|e| e.to_string())?;
+        Ok(file)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Encodes the rendered audio. WAV is written directly, other formats go
+/// through `ffmpeg`.
+pub fn encode(rendered: &Rendered, title: &str, format: AudioFormat) -> Result<Vec<u8>, String> {
+    let wav = write_wav(&rendered.format, &rendered.data, &rendered.chapters);
+    if format == AudioFormat::Wav {
+        return Ok(wav);
+    }
+    let dir = std::env::temp_dir();
+    let input = TempFile::new(&dir, "input.wav", &wav)?;
+    let metadata = TempFile::new(
+        &dir,
+        "metadata.txt",
+        ffmetadata(title, &rendered.chapters).as_bytes(),
+    )?;
+    let output = TempFile::unique(&dir, &format!("output.{}", format.extension()));
+    let status = Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(&input.0)
+        .arg("-i")
+        .arg(&metadata.0)
+        .args([
+            "-map_metadata",
+            "1",
+            "-map_chapters",
+            "1",
+            "-c:a",
+            format.codec(),
+        ])
+        .arg(&output.0)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("could not start ffmpeg: {e}"))?;
+    if !status.status.success() {
+        return Err(String::from_utf8_lossy(&status.stderr).trim().to_string());
+    }
+    std::fs::read(&output.0).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod test_export;
@@ -0,0 +1,192 @@
+use super::*;
+use std::cell::RefCell;
+use std::time::Duration;
+
+const FORMAT: WavFormat = WavFormat {
+    channels: 1,
+    sample_rate: 1000,
+    bits_per_sample: 16,
+};
+
+/// Ten milliseconds of samples per character.
+struct FakeBackend {
+    spoken: RefCell<Vec<String>>,
+}
+
+impl FakeBackend {
+    fn new() -> Self {
+        Self {
+            spoken: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl SynthesisBackend for FakeBackend {
+    fn synthesize(&self, text: &str, _voice: &Voice) -> Result<Vec<u8>, String> {
+        self.spoken.borrow_mut().push(text.to_string());
+        let data = vec![1; text.chars().count() * 10 * 2];
+        Ok(write_wav(&FORMAT, &data, &[]))
+    }
+}
+
+fn voice() -> Voice {
+    Voice {
+        language: "en".to_string(),
+        rate: 1.0,
+        pitch: 1.0,
+        volume: 1.0,
+    }
+}
+
+fn heading(text: &str, para: usize) -> TocEntry {
+    TocEntry {
+        level: 2,
+        text: text.to_string(),
+        para,
+        anchor: String::new(),
+    }
+}
+
+#[test]
+fn test_wav_roundtrip() {
+    let data: Vec<u8> = (0..40).collect();
+    let chapters = vec![Chapter {
+        title: "One".to_string(),
+        start_ms: 5,
+        end_ms: 20,
+    }];
+    let wav = write_wav(&FORMAT, &data, &chapters);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(
+        u32::from_le_bytes(wav[4..8].try_into().unwrap()) as usize,
+        wav.len() - 8
+    );
+    assert!(wav.windows(4).any(|w| w == b"cue "));
+    assert!(wav.windows(4).any(|w| w == b"labl"));
+    assert_eq!(
+        parse_wav(&wav),
+        Ok(Pcm {
+            format: FORMAT,
+            data,
+        })
+    );
+}
+
+#[test]
+fn test_parse_streamed_wav() {
+    let mut wav = write_wav(&FORMAT, &[0; 8], &[]);
+    let data_size = wav.len() - 12;
+    wav[data_size..data_size + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    wav.push(7);
+    let pcm = parse_wav(&wav).unwrap();
+    assert_eq!(pcm.data.len(), 8);
+    assert!(parse_wav(b"RIFF....WAVEjunk").is_err());
+}
+
+#[test]
+fn test_render_with_pauses_and_chapters() {
+    let backend = FakeBackend::new();
+    let units = vec![
+        "Intro".to_string(),
+        "One".to_string(),
+        String::new(),
+        "Two".to_string(),
+        "End".to_string(),
+    ];
+    let pauses = vec![Duration::from_millis(100), Duration::ZERO];
+    let toc = vec![heading("First", 1), heading("Second", 3)];
+    let mut progress = Vec::new();
+    let rendered = render(&units, &pauses, &toc, &backend, &voice(), |done, total| {
+        progress.push((done, total));
+    })
+    .unwrap();
+
+    assert_eq!(*backend.spoken.borrow(), vec!["Intro", "One", "Two", "End"]);
+    assert_eq!(progress.last(), Some(&(5, 5)));
+    assert_eq!(rendered.duration_ms, 50 + 100 + 30 + 30 + 30);
+    assert_eq!(
+        rendered.chapters,
+        vec![
+            Chapter {
+                title: "First".to_string(),
+                start_ms: 150,
+                end_ms: 180,
+            },
+            Chapter {
+                title: "Second".to_string(),
+                start_ms: 180,
+                end_ms: 240,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_render_nothing() {
+    let backend = FakeBackend::new();
+    let result = render(&[String::new()], &[], &[], &backend, &voice(), |_, _| {});
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_ffmetadata() {
+    let chapters = vec![Chapter {
+        title: "A=B; #1".to_string(),
+        start_ms: 0,
+        end_ms: 1500,
+    }];
+    assert_eq!(
+        ffmetadata("Title", &chapters),
+        ";FFMETADATA1\ntitle=Title\n\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=1500\ntitle=A\\=B\\; \\#1\n"
+    );
+}
+
+#[test]
+fn test_espeak_args() {
+    let voice = Voice {
+        language: "DE".to_string(),
+        rate: 1.2,
+        pitch: 3.0,
+        volume: 0.5,
+    };
+    assert_eq!(
+        Espeak::args("Hallo.", &voice),
+        vec![
+            "--stdout", "--stdin", "-s", "210", "-p", "99", "-a", "50", "-v", "de"
+        ]
+    );
+    assert!(backend("say").is_err());
+
+    // SSML units are read as markup.
+    let args = Espeak::args("<speak>Hallo.</speak>", &voice);
+    assert_eq!(args[8..], ["-m", "-v", "de"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_espeak_long_text() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Echoes its input, far more than a pipe holds.
+    let script =
+        TempFile::new(&std::env::temp_dir(), "espeak.sh", b"#!/bin/sh\nexec cat\n").unwrap();
+    std::fs::set_permissions(&script.0, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let espeak = Espeak {
+        program: script.0.clone(),
+    };
+    let text = "word ".repeat(100_000);
+    assert_eq!(espeak.synthesize(&text, &voice()).unwrap(), text.as_bytes());
+}
+
+#[test]
+fn test_temp_files_unique() {
+    let dir = std::env::temp_dir();
+    let first = TempFile::new(&dir, "input.wav", b"one").unwrap();
+    let second = TempFile::new(&dir, "input.wav", b"two").unwrap();
+    assert_ne!(first.0, second.0);
+    assert_eq!(std::fs::read(&first.0).unwrap(), b"one");
+    let path = first.0.clone();
+    drop(first);
+    assert!(!path.exists());
+    assert!(second.0.exists());
+}
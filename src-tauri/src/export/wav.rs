@@ -0,0 +1,141 @@
+use super::Chapter;
+
+/// Sample format of a PCM WAV stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavFormat {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+}
+
+impl WavFormat {
+    #[must_use]
+    pub fn block_align(&self) -> u16 {
+        self.channels * self.bits_per_sample.div_ceil(8)
+    }
+
+    /// Sample frames in `ms` milliseconds.
+    #[must_use]
+    pub fn frames(&self, ms: u64) -> u64 {
+        ms * u64::from(self.sample_rate) / 1000
+    }
+
+    /// Milliseconds of `bytes` of sample data.
+    #[must_use]
+    pub fn duration_ms(&self, bytes: usize) -> u64 {
+        let frames = bytes as u64 / u64::from(self.block_align().max(1));
+        frames * 1000 / u64::from(self.sample_rate.max(1))
+    }
+
+    /// Sample data of `ms` milliseconds of silence.
+    #[must_use]
+    pub fn silence(&self, ms: u64) -> Vec<u8> {
+        let fill = if self.bits_per_sample == 8 { 0x80 } else { 0 };
+        let len = self.frames(ms) * u64::from(self.block_align());
+        vec![fill; usize::try_from(len).unwrap_or(0)]
+    }
+}
+
+/// Decoded PCM WAV.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pcm {
+    pub format: WavFormat,
+    pub data: Vec<u8>,
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Reads a PCM WAV file. Engines writing to a pipe cannot seek back to fix
+/// the sizes, so a data chunk running past the end is cut at the end.
+pub fn parse_wav(bytes: &[u8]) -> Result<Pcm, String> {
+    if bytes.get(0..4) != Some(b"RIFF") || bytes.get(8..12) != Some(b"WAVE") {
+        return Err("not a WAV file".to_string());
+    }
+    let mut format = None;
+    let mut at = 12;
+    while let (Some(id), Some(size)) = (bytes.get(at..at + 4), u32_at(bytes, at + 4)) {
+        let start = at + 8;
+        let end = start.saturating_add(size as usize).min(bytes.len());
+        match id {
+            b"fmt " => {
+                if u16_at(bytes, start) != Some(1) {
+                    return Err("WAV is not PCM".to_string());
+                }
+                format = Some(WavFormat {
+                    channels: u16_at(bytes, start + 2).ok_or("truncated WAV")?,
+                    sample_rate: u32_at(bytes, start + 4).ok_or("truncated WAV")?,
+                    bits_per_sample: u16_at(bytes, start + 14).ok_or("truncated WAV")?,
+                });
+            }
+            b"data" => {
+                let format = format.ok_or("WAV data before format")?;
+                let mut data = bytes[start..end].to_vec();
+                data.truncate(data.len() - data.len() % usize::from(format.block_align().max(1)));
+                return Ok(Pcm { format, data });
+            }
+            _ => {}
+        }
+        at = end + size as usize % 2;
+    }
+    Err("WAV has no data".to_string())
+}
+
+fn chunk(out: &mut Vec<u8>, id: [u8; 4], body: &[u8]) {
+    out.extend_from_slice(&id);
+    out.extend_from_slice(&u32::try_from(body.len()).unwrap_or(u32::MAX).to_le_bytes());
+    out.extend_from_slice(body);
+    if body.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+/// Writes a PCM WAV file with a cue point and label per chapter.
+#[must_use]
+pub fn write_wav(format: &WavFormat, data: &[u8], chapters: &[Chapter]) -> Vec<u8> {
+    let mut body = b"WAVE".to_vec();
+
+    let mut fmt = Vec::with_capacity(16);
+    fmt.extend_from_slice(&1u16.to_le_bytes());
+    fmt.extend_from_slice(&format.channels.to_le_bytes());
+    fmt.extend_from_slice(&format.sample_rate.to_le_bytes());
+    let byte_rate = format.sample_rate * u32::from(format.block_align());
+    fmt.extend_from_slice(&byte_rate.to_le_bytes());
+    fmt.extend_from_slice(&format.block_align().to_le_bytes());
+    fmt.extend_from_slice(&format.bits_per_sample.to_le_bytes());
+    chunk(&mut body, *b"fmt ", &fmt);
+    chunk(&mut body, *b"data", data);
+
+    if !chapters.is_empty() {
+        let mut cue = u32::try_from(chapters.len())
+            .unwrap_or(u32::MAX)
+            .to_le_bytes()
+            .to_vec();
+        let mut labels = b"adtl".to_vec();
+        for (id, chapter) in (1u32..).zip(chapters) {
+            let frame = u32::try_from(format.frames(chapter.start_ms)).unwrap_or(u32::MAX);
+            cue.extend_from_slice(&id.to_le_bytes());
+            cue.extend_from_slice(&frame.to_le_bytes());
+            cue.extend_from_slice(b"data");
+            cue.extend_from_slice(&0u32.to_le_bytes());
+            cue.extend_from_slice(&0u32.to_le_bytes());
+            cue.extend_from_slice(&frame.to_le_bytes());
+
+            let mut label = id.to_le_bytes().to_vec();
+            label.extend_from_slice(chapter.title.as_bytes());
+            label.push(0);
+            chunk(&mut labels, *b"labl", &label);
+        }
+        chunk(&mut body, *b"cue ", &cue);
+        chunk(&mut body, *b"LIST", &labels);
+    }
+
+    let mut out = Vec::with_capacity(body.len() + 8);
+    chunk(&mut out, *b"RIFF", &body);
+    out
+}
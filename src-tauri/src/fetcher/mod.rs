@@ -1,3 +1,4 @@
+use kuchikikiki::traits::TendrilSink;
 use readabilityrs::Readability;
 use std::future::Future;
 use std::pin::Pin;
@@ -54,10 +55,47 @@ pub fn new_fetcher(
     }
 }
 
+/// An article extracted from a fetched page.
+pub struct FetchedArticle {
+    pub title: String,
+    pub body: String,
+    pub text_content: String,
+    pub site_name: String,
+    pub image_url: String,
+}
+
+/// The `og:site_name` and the absolute `og:image` URL of a page, empty when
+/// missing.
+fn page_metadata(html: &str, url: &str) -> (String, String) {
+    let document = kuchikikiki::parse_html().one(html);
+    let meta = |property: &str| {
+        document
+            .select_first(&format!("meta[property=\"{property}\"]"))
+            .ok()
+            .and_then(|node| {
+                node.attributes
+                    .borrow()
+                    .get("content")
+                    .map(|content| content.trim().to_string())
+            })
+            .unwrap_or_default()
+    };
+    let site_name = meta("og:site_name");
+    let image = meta("og:image");
+    let image_url = if image.is_empty() {
+        image
+    } else {
+        url::Url::parse(url)
+            .and_then(|base| base.join(&image))
+            .map_or(image, String::from)
+    };
+    (site_name, image_url)
+}
+
 pub async fn fetch_parse_update_article(
     article_url: &str,
     fetcher: &mut dyn Fetcher,
-) -> Result<(String, String, String), String> {
+) -> Result<FetchedArticle, String> {
     let html = fetcher.fetch().await?;
 
     let options = readabilityrs::ReadabilityOptions::builder()
@@ -75,6 +113,13 @@ pub async fn fetch_parse_update_article(
     };
     let body = article_data.content.unwrap_or_default();
     let text_content = article_data.text_content.unwrap_or_default();
+    let (site_name, image_url) = page_metadata(&html, article_url);
 
-    Ok((title, body, text_content))
+    Ok(FetchedArticle {
+        title,
+        body,
+        text_content,
+        site_name,
+        image_url,
+    })
 }
@@ -33,3 +33,23 @@ pub fn pick_and_write_json<T: Serialize>(
     let writer = BufWriter::new(file);
     serde_json::to_writer(writer, data).map_err(|e| format!("Failed to write file: {e}"))
 }
+
+pub fn pick_and_write_bytes(
+    app: &AppHandle,
+    data: &[u8],
+    filename: &str,
+    _mime: &str,
+    extension: &str,
+) -> Result<(), String> {
+    let Some(file_path) = app
+        .dialog()
+        .file()
+        .add_filter(extension, &[extension])
+        .set_file_name(filename)
+        .blocking_save_file()
+    else {
+        return Err("No save location selected".into());
+    };
+    let path = file_path.as_path().ok_or("could not get a path")?;
+    std::fs::write(path, data).map_err(|e| format!("Could not write file: {e}"))
+}
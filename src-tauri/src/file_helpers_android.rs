@@ -39,3 +39,21 @@ pub fn pick_and_write_json<T: Serialize>(
         Err("No save location selected".into())
     }
 }
+
+pub fn pick_and_write_bytes(
+    app: &AppHandle,
+    data: &[u8],
+    filename: &str,
+    mime: &str,
+    _extension: &str,
+) -> Result<(), String> {
+    let api = app.android_fs();
+    if let Ok(Some(file_path)) = api.file_picker().save_file(None, filename, Some(mime), true) {
+        let mut file: File = api
+            .open_file_writable(&file_path)
+            .map_err(|e| e.to_string())?;
+        std::io::Write::write_all(&mut file, data).map_err(|e| format!("Could not write file: {e}"))
+    } else {
+        Err("No save location selected".into())
+    }
+}
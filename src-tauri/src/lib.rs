@@ -1,4 +1,5 @@
 pub mod commands;
+pub mod export;
 pub mod fetcher;
 #[cfg(not(target_os = "android"))]
 pub mod file_helpers;
@@ -7,7 +8,14 @@ pub mod file_helpers_android;
 #[cfg(target_os = "android")]
 pub use file_helpers_android as file_helpers;
 pub mod models;
+pub mod normalize;
 pub mod parse;
+pub mod pronunciation;
+pub mod reading;
+pub mod stats;
+pub mod sync;
+
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -34,8 +42,10 @@ pub fn run() {
             .plugin(tauri_plugin_mobile_sharetarget::init());
     }
     builder
-        .manage(commands::speakbar::SpeakBarState::default())
-        .setup(|_app| Ok(()))
+        .setup(|app| {
+            app.manage(commands::speakbar::speak_bar(app.handle()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             crate::commands::get_articles,
             crate::commands::get_article,
@@ -55,12 +65,43 @@ pub fn run() {
             crate::commands::get_read_state,
             crate::commands::set_voice_id,
             crate::commands::cleanup_reading,
+            crate::commands::export_audio,
+            crate::commands::record_reading_session,
+            crate::commands::get_time_stats,
+            crate::commands::get_finished_stats,
+            crate::commands::get_backlog_history,
+            crate::commands::get_top_sites,
+            crate::commands::get_completion_age,
+            crate::commands::set_sleep_timer,
+            crate::commands::skip_queued_article,
+            crate::commands::skip_reading,
+            crate::commands::seek_reading,
+            crate::commands::media_action,
+            crate::commands::audio_changed,
+            crate::commands::jump_to_heading,
+            crate::commands::reload_speech_settings,
+            crate::commands::get_voice_profiles,
+            crate::commands::save_voice_profile,
+            crate::commands::delete_voice_profile,
+            crate::commands::get_article_voice_profile,
+            crate::commands::set_article_voice_profile,
+            crate::commands::get_queue,
+            crate::commands::add_to_queue,
+            crate::commands::remove_from_queue,
+            crate::commands::move_in_queue,
+            crate::commands::clear_queue,
             crate::commands::sync_articles,
             crate::commands::get_pronunciation_rules,
             crate::commands::save_pronunciation_rule,
             crate::commands::delete_pronunciation_rule,
+            crate::commands::preview_pronunciation_rules,
             crate::commands::pick_pronunciation_import_file,
             crate::commands::pick_pronunciation_export_file,
+            crate::commands::get_segmentation_rules,
+            crate::commands::save_segmentation_rule,
+            crate::commands::delete_segmentation_rule,
+            crate::commands::pick_segmentation_import_file,
+            crate::commands::pick_segmentation_export_file,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
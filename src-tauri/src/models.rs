@@ -14,6 +14,73 @@ pub struct Article {
     pub title: String,
     pub body: String,
     pub url: String,
+    /// `og:site_name` of the page, empty when unknown.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub site_name: String,
+    /// Absolute `og:image` URL of the page, empty when unknown.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub image_url: String,
+    /// Table of contents, filled in by `get_article`.
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub toc: Vec<TocEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, sqlx::FromRow)]
+pub struct QueueEntry {
+    pub article_id: i32,
+    pub title: String,
+    pub url: String,
+}
+
+/// Voice settings applied to an article. A profile with a `language` is the
+/// default for articles detected in that language.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, sqlx::FromRow)]
+pub struct VoiceProfile {
+    #[serde(default)]
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub voice_id: Option<String>,
+    #[serde(default = "default_one")]
+    pub rate: f32,
+    #[serde(default = "default_one")]
+    pub pitch: f32,
+    #[serde(default = "default_one")]
+    pub volume: f32,
+    #[serde(default)]
+    pub language: String,
+}
+
+/// Seconds listened and read in one day or week.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, sqlx::FromRow)]
+pub struct TimeStat {
+    pub period: String,
+    pub listened_secs: f64,
+    pub read_secs: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, sqlx::FromRow)]
+pub struct CountStat {
+    pub period: String,
+    pub count: i64,
+}
+
+/// Number of unread articles at the end of `day`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, sqlx::FromRow)]
+pub struct BacklogPoint {
+    pub day: String,
+    pub size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SiteStat {
+    pub site: String,
+    pub secs: f64,
+    pub sessions: usize,
+    pub finished: usize,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
@@ -23,6 +90,79 @@ pub struct Snippet {
     pub suffix: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    #[default]
+    Prose,
+    Code,
+    Table,
+    /// The alt text of an image.
+    Image,
+    /// Part of a `figcaption`.
+    Caption,
+}
+
+/// Position of a unit inside a table.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub struct TableCell {
+    /// Index of the table in the article.
+    pub table: usize,
+    pub row: usize,
+    pub column: usize,
+    /// Set for `th` cells and cells in `thead`.
+    pub header: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum InlineKind {
+    Link,
+    Footnote,
+}
+
+/// A link or footnote reference inside a unit.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct InlineRef {
+    pub kind: InlineKind,
+    pub text: String,
+    /// Byte offset of `text` in the unit text.
+    pub start: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TtsParagraph {
+    pub text: String,
+    pub kind: BlockKind,
+    /// Index of the block element (paragraph, list item, heading, ...) the
+    /// unit belongs to.
+    pub block: usize,
+    /// Heading level when the unit is part of a heading.
+    pub heading: Option<u8>,
+    /// Set when the unit is part of a table cell.
+    pub cell: Option<TableCell>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub refs: Vec<InlineRef>,
+}
+
+/// A heading of the processed article.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TocEntry {
+    pub level: u8,
+    pub text: String,
+    /// First `tts_para` index of the heading.
+    pub para: usize,
+    /// Element id of the heading, generated when the source has none.
+    pub anchor: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessedArticle {
+    pub html: String,
+    pub paragraphs: Vec<TtsParagraph>,
+    pub toc: Vec<TocEntry>,
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct ArticleEntry {
     pub id: i32,
@@ -37,6 +177,10 @@ pub struct ArticleSync {
     pub created_at: String,
     pub updated_at: String,
     pub is_deleted: i32,
+    /// Hash of the synced content, when content sync is on.
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -55,12 +199,81 @@ pub struct Setting {
     pub default_value: String,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Clone, sqlx::FromRow)]
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, sqlx::FromRow)]
 pub struct PronunciationRule {
     pub match_pattern: String,
     pub replacement: String,
     #[serde(default)]
     pub is_regex: bool,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Language the rule applies to; empty for all languages.
+    #[serde(default)]
+    pub language: String,
+    /// Article domain the rule applies to (subdomains included); empty for all.
+    #[serde(default)]
+    pub domain: String,
+    /// `prose`, `code` or empty for both.
+    #[serde(default)]
+    pub scope: String,
+    #[serde(default)]
+    pub whole_word: bool,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Give the replacement the case of the matched text.
+    #[serde(default)]
+    pub preserve_case: bool,
+    /// IPA pronunciation passed to SSML capable engines; empty for none.
+    #[serde(default)]
+    pub phoneme: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+/// A single replacement made by a pronunciation rule. Offsets are byte
+/// offsets into the text the rule was applied to.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RuleMatch {
+    pub start: usize,
+    pub end: usize,
+    pub matched: String,
+    pub replacement: String,
+}
+
+/// A pronunciation rule that changed the text, in the order it ran.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RuleFiring {
+    pub match_pattern: String,
+    pub is_regex: bool,
+    pub priority: i32,
+    pub matches: Vec<RuleMatch>,
+    pub output: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RulePreview {
+    pub input: String,
+    pub normalized: String,
+    pub output: String,
+    pub trace: Vec<RuleFiring>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, sqlx::FromRow)]
+pub struct SegmentationRule {
+    #[serde(default)]
+    pub language: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub is_regex: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -123,5 +336,63 @@ pub fn get_migrations() -> Vec<Migration> {
             sql: include_str!("../migrations/2026-07-28-000001_add_is_regex.sql"),
             kind: MigrationKind::Up,
         },
+        Migration {
+            version: 8,
+            description: "add_segmentation_rules",
+            sql: include_str!("../migrations/2026-10-19-000000_add_segmentation_rules.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "add_pronunciation_rule_scope",
+            sql: include_str!("../migrations/2026-10-19-000001_add_pronunciation_rule_scope.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "add_pronunciation_rule_case_phoneme",
+            sql: include_str!(
+                "../migrations/2026-10-19-000002_add_pronunciation_rule_case_phoneme.sql"
+            ),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "add_listening_queue",
+            sql: include_str!("../migrations/2026-10-19-000003_add_listening_queue.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "add_voice_profiles",
+            sql: include_str!("../migrations/2026-10-19-000004_add_voice_profiles.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "add_reading_sessions",
+            sql: include_str!("../migrations/2026-10-19-000005_add_reading_sessions.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "add_article_metadata",
+            sql: include_str!("../migrations/2026-10-19-000006_add_article_metadata.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "add_user_data_sync",
+            sql: include_str!("../migrations/2026-10-19-000007_add_user_data_sync.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "key_pronunciation_rules_by_scope",
+            sql: include_str!(
+                "../migrations/2026-10-19-000008_key_pronunciation_rules_by_scope.sql"
+            ),
+            kind: MigrationKind::Up,
+        },
     ]
 }
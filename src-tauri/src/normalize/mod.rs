@@ -0,0 +1,820 @@
+use regex::{Captures, Regex};
+
+use crate::models::BlockKind;
+use crate::parse::primary_language;
+
+// TTS Normalization Rules:
+//
+// Applied to each paragraph before the user pronunciation rules, in this
+// order, each step toggleable through `NormalizeOptions`. Code is read
+// verbatim and never normalized.
+//
+// 1. URLs collapse to their domain (`https://www.rust-lang.org/learn`
+//    becomes `rust-lang.org`).
+// 2. ISO dates are spelled with the month name of the language.
+// 3. `HH:MM` times are read as hours and minutes when a preposition of the
+//    language (`at 9:30`) or `am`/`pm` marks them as times, so references
+//    like `John 3:16` stay as they are.
+// 4. Currency amounts with `$`, `€`, `£`, `¥` and `K`/`M`/`B` suffixes
+//    are read as amount, multiplier and currency name.
+// 5. Numeric ranges (`10-20`, `1990–1995`) use the language's "to". A
+//    hyphen after a decimal (`2.0-3`) is left alone.
+// 6. Units (`3.5GHz`, `10×`, `3 x 4`, `20%`) are spelled out.
+// 7. English ordinals (`21st`) are spelled out.
+// 8. Roman numerals after heading keywords (`Chapter IV`) and at the start
+//    of headings (`IV. The Return`) become arabic numbers. A lone `C`, `D`,
+//    `L` or `M` is left alone, as it is more likely a letter (`Appendix C`,
+//    `M. Smith`).
+// 9. Symbols (`C++`, `C#`, `&`) are spelled out.
+//
+// Languages without a lexicon only get URL collapsing and symbols.
+
+pub const SETTING_PREFIX: &str = "normalize_";
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    pub urls: bool,
+    pub dates: bool,
+    pub times: bool,
+    pub currencies: bool,
+    pub ranges: bool,
+    pub units: bool,
+    pub ordinals: bool,
+    pub roman_numerals: bool,
+    pub symbols: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            urls: true,
+            dates: true,
+            times: true,
+            currencies: true,
+            ranges: true,
+            units: true,
+            ordinals: true,
+            roman_numerals: true,
+            symbols: true,
+        }
+    }
+}
+
+impl NormalizeOptions {
+    /// Applies a `normalize_<step>` setting; unknown names are ignored.
+    pub fn apply_setting(&mut self, name: &str, value: &str) {
+        let enabled = value != "false";
+        match name.strip_prefix(SETTING_PREFIX).unwrap_or(name) {
+            "urls" => self.urls = enabled,
+            "dates" => self.dates = enabled,
+            "times" => self.times = enabled,
+            "currencies" => self.currencies = enabled,
+            "ranges" => self.ranges = enabled,
+            "units" => self.units = enabled,
+            "ordinals" => self.ordinals = enabled,
+            "roman_numerals" => self.roman_numerals = enabled,
+            "symbols" => self.symbols = enabled,
+            _ => {}
+        }
+    }
+}
+
+struct Lexicon {
+    months: [&'static str; 12],
+    date: fn(&Lexicon, u32, usize, &str) -> String,
+    time: fn(u32, u32) -> String,
+    /// Words that mark the `HH:MM` after them as a time.
+    time_words: &'static str,
+    to: &'static str,
+    and: &'static str,
+    times: &'static str,
+    currencies: [(&'static str, &'static str, &'static str); 4],
+    multipliers: [&'static str; 3],
+    units: &'static [(&'static str, &'static str, &'static str)],
+    headings: &'static str,
+}
+
+const EN_UNITS: &[(&str, &str, &str)] = &[
+    ("GHz", "gigahertz", "gigahertz"),
+    ("MHz", "megahertz", "megahertz"),
+    ("kHz", "kilohertz", "kilohertz"),
+    ("Hz", "hertz", "hertz"),
+    ("TB", "terabyte", "terabytes"),
+    ("GB", "gigabyte", "gigabytes"),
+    ("MB", "megabyte", "megabytes"),
+    ("KB", "kilobyte", "kilobytes"),
+    ("kB", "kilobyte", "kilobytes"),
+    ("km/h", "kilometer per hour", "kilometers per hour"),
+    ("mph", "mile per hour", "miles per hour"),
+    ("km", "kilometer", "kilometers"),
+    ("cm", "centimeter", "centimeters"),
+    ("mm", "millimeter", "millimeters"),
+    ("kg", "kilogram", "kilograms"),
+    ("mg", "milligram", "milligrams"),
+    ("ms", "millisecond", "milliseconds"),
+    ("°C", "degree Celsius", "degrees Celsius"),
+    ("°F", "degree Fahrenheit", "degrees Fahrenheit"),
+    ("%", "percent", "percent"),
+];
+
+const DE_UNITS: &[(&str, &str, &str)] = &[
+    ("GHz", "Gigahertz", "Gigahertz"),
+    ("MHz", "Megahertz", "Megahertz"),
+    ("kHz", "Kilohertz", "Kilohertz"),
+    ("Hz", "Hertz", "Hertz"),
+    ("TB", "Terabyte", "Terabyte"),
+    ("GB", "Gigabyte", "Gigabyte"),
+    ("MB", "Megabyte", "Megabyte"),
+    ("KB", "Kilobyte", "Kilobyte"),
+    ("kB", "Kilobyte", "Kilobyte"),
+    ("km/h", "Kilometer pro Stunde", "Kilometer pro Stunde"),
+    ("km", "Kilometer", "Kilometer"),
+    ("cm", "Zentimeter", "Zentimeter"),
+    ("mm", "Millimeter", "Millimeter"),
+    ("kg", "Kilogramm", "Kilogramm"),
+    ("mg", "Milligramm", "Milligramm"),
+    ("ms", "Millisekunde", "Millisekunden"),
+    ("°C", "Grad Celsius", "Grad Celsius"),
+    ("°F", "Grad Fahrenheit", "Grad Fahrenheit"),
+    ("%", "Prozent", "Prozent"),
+];
+
+const FR_UNITS: &[(&str, &str, &str)] = &[
+    ("GHz", "gigahertz", "gigahertz"),
+    ("MHz", "mégahertz", "mégahertz"),
+    ("kHz", "kilohertz", "kilohertz"),
+    ("Hz", "hertz", "hertz"),
+    ("To", "téraoctet", "téraoctets"),
+    ("Go", "gigaoctet", "gigaoctets"),
+    ("Mo", "mégaoctet", "mégaoctets"),
+    ("Ko", "kilooctet", "kilooctets"),
+    ("km/h", "kilomètre heure", "kilomètres heure"),
+    ("km", "kilomètre", "kilomètres"),
+    ("cm", "centimètre", "centimètres"),
+    ("mm", "millimètre", "millimètres"),
+    ("kg", "kilogramme", "kilogrammes"),
+    ("mg", "milligramme", "milligrammes"),
+    ("ms", "milliseconde", "millisecondes"),
+    ("°C", "degré Celsius", "degrés Celsius"),
+    ("%", "pour cent", "pour cent"),
+];
+
+const ES_UNITS: &[(&str, &str, &str)] = &[
+    ("GHz", "gigahercio", "gigahercios"),
+    ("MHz", "megahercio", "megahercios"),
+    ("Hz", "hercio", "hercios"),
+    ("TB", "terabyte", "terabytes"),
+    ("GB", "gigabyte", "gigabytes"),
+    ("MB", "megabyte", "megabytes"),
+    ("km/h", "kilómetro por hora", "kilómetros por hora"),
+    ("km", "kilómetro", "kilómetros"),
+    ("cm", "centímetro", "centímetros"),
+    ("mm", "milímetro", "milímetros"),
+    ("kg", "kilogramo", "kilogramos"),
+    ("mg", "miligramo", "miligramos"),
+    ("ms", "milisegundo", "milisegundos"),
+    ("°C", "grado Celsius", "grados Celsius"),
+    ("%", "por ciento", "por ciento"),
+];
+
+const PT_UNITS: &[(&str, &str, &str)] = &[
+    ("GHz", "gigahertz", "gigahertz"),
+    ("MHz", "megahertz", "megahertz"),
+    ("Hz", "hertz", "hertz"),
+    ("TB", "terabyte", "terabytes"),
+    ("GB", "gigabyte", "gigabytes"),
+    ("MB", "megabyte", "megabytes"),
+    ("km/h", "quilômetro por hora", "quilômetros por hora"),
+    ("km", "quilômetro", "quilômetros"),
+    ("cm", "centímetro", "centímetros"),
+    ("mm", "milímetro", "milímetros"),
+    ("kg", "quilograma", "quilogramas"),
+    ("mg", "miligrama", "miligramas"),
+    ("ms", "milissegundo", "milissegundos"),
+    ("°C", "grau Celsius", "graus Celsius"),
+    ("%", "por cento", "por cento"),
+];
+
+const EN: Lexicon = Lexicon {
+    months: [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ],
+    date: |lex, day, month, year| format!("{} {day}, {year}", lex.months[month]),
+    time: |hour, minute| match minute {
+        0 => format!("{hour} o'clock"),
+        1..=9 => format!("{hour} oh {minute}"),
+        _ => format!("{hour} {minute}"),
+    },
+    time_words: "at|by|from|until|till|to|before|after|around|since|between",
+    to: "to",
+    and: "and",
+    times: "times",
+    currencies: [
+        ("$", "dollar", "dollars"),
+        ("€", "euro", "euros"),
+        ("£", "pound", "pounds"),
+        ("¥", "yen", "yen"),
+    ],
+    multipliers: ["thousand", "million", "billion"],
+    units: EN_UNITS,
+    headings: "Chapter|Part|Book|Section|Volume|Act|Scene|Appendix",
+};
+
+const DE: Lexicon = Lexicon {
+    months: [
+        "Januar",
+        "Februar",
+        "März",
+        "April",
+        "Mai",
+        "Juni",
+        "Juli",
+        "August",
+        "September",
+        "Oktober",
+        "November",
+        "Dezember",
+    ],
+    date: |lex, day, month, year| format!("{day}. {} {year}", lex.months[month]),
+    time: |hour, minute| match minute {
+        0 => format!("{hour} Uhr"),
+        _ => format!("{hour} Uhr {minute}"),
+    },
+    time_words: "um|ab|bis|gegen|von|vor|nach|seit|zwischen",
+    to: "bis",
+    and: "und",
+    times: "mal",
+    currencies: [
+        ("$", "Dollar", "Dollar"),
+        ("€", "Euro", "Euro"),
+        ("£", "Pfund", "Pfund"),
+        ("¥", "Yen", "Yen"),
+    ],
+    multipliers: ["Tausend", "Millionen", "Milliarden"],
+    units: DE_UNITS,
+    headings: "Kapitel|Teil|Band|Buch|Abschnitt|Akt|Anhang",
+};
+
+const FR: Lexicon = Lexicon {
+    months: [
+        "janvier",
+        "février",
+        "mars",
+        "avril",
+        "mai",
+        "juin",
+        "juillet",
+        "août",
+        "septembre",
+        "octobre",
+        "novembre",
+        "décembre",
+    ],
+    date: |lex, day, month, year| format!("{day} {} {year}", lex.months[month]),
+    time: |hour, minute| match minute {
+        0 => format!("{hour} heures"),
+        _ => format!("{hour} heures {minute}"),
+    },
+    time_words: "à|vers|de|dès|jusqu'à|avant|après|entre",
+    to: "à",
+    and: "et",
+    times: "fois",
+    currencies: [
+        ("$", "dollar", "dollars"),
+        ("€", "euro", "euros"),
+        ("£", "livre", "livres"),
+        ("¥", "yen", "yens"),
+    ],
+    multipliers: ["mille", "millions", "milliards"],
+    units: FR_UNITS,
+    headings: "Chapitre|Partie|Livre|Tome|Section|Acte|Annexe",
+};
+
+const ES: Lexicon = Lexicon {
+    months: [
+        "enero",
+        "febrero",
+        "marzo",
+        "abril",
+        "mayo",
+        "junio",
+        "julio",
+        "agosto",
+        "septiembre",
+        "octubre",
+        "noviembre",
+        "diciembre",
+    ],
+    date: |lex, day, month, year| format!("{day} de {} de {year}", lex.months[month]),
+    time: |hour, minute| match minute {
+        0 => format!("{hour} en punto"),
+        _ => format!("{hour} y {minute}"),
+    },
+    time_words: "las|la|desde|hasta|entre",
+    to: "a",
+    and: "y",
+    times: "veces",
+    currencies: [
+        ("$", "dólar", "dólares"),
+        ("€", "euro", "euros"),
+        ("£", "libra", "libras"),
+        ("¥", "yen", "yenes"),
+    ],
+    multipliers: ["mil", "millones", "mil millones"],
+    units: ES_UNITS,
+    headings: "Capítulo|Parte|Libro|Tomo|Sección|Acto|Anexo",
+};
+
+const PT: Lexicon = Lexicon {
+    months: [
+        "janeiro",
+        "fevereiro",
+        "março",
+        "abril",
+        "maio",
+        "junho",
+        "julho",
+        "agosto",
+        "setembro",
+        "outubro",
+        "novembro",
+        "dezembro",
+    ],
+    date: |lex, day, month, year| format!("{day} de {} de {year}", lex.months[month]),
+    time: |hour, minute| match minute {
+        0 => format!("{hour} horas"),
+        _ => format!("{hour} e {minute}"),
+    },
+    time_words: "às|as|à|a|das|até|entre",
+    to: "a",
+    and: "e",
+    times: "vezes",
+    currencies: [
+        ("$", "dólar", "dólares"),
+        ("€", "euro", "euros"),
+        ("£", "libra", "libras"),
+        ("¥", "iene", "ienes"),
+    ],
+    multipliers: ["mil", "milhões", "bilhões"],
+    units: PT_UNITS,
+    headings: "Capítulo|Parte|Livro|Tomo|Seção|Ato|Anexo",
+};
+
+fn lexicon(language: &str) -> Option<&'static Lexicon> {
+    match primary_language(language).as_str() {
+        "en" => Some(&EN),
+        "de" => Some(&DE),
+        "fr" => Some(&FR),
+        "es" => Some(&ES),
+        "pt" => Some(&PT),
+        _ => None,
+    }
+}
+
+fn compile(pattern: &str) -> Regex {
+    Regex::new(pattern).expect("built-in normalization pattern must compile")
+}
+
+/// Compiled normalization pass for one language. Build it once per article
+/// and reuse it for every paragraph.
+pub struct Normalizer {
+    options: NormalizeOptions,
+    lexicon: Option<&'static Lexicon>,
+    is_english: bool,
+    url: Regex,
+    date: Regex,
+    time: Regex,
+    currency_prefix: Regex,
+    currency_suffix: Regex,
+    range: Regex,
+    unit: Option<Regex>,
+    times: Regex,
+    ordinal: Regex,
+    roman_keyword: Option<Regex>,
+    roman_leading: Regex,
+    cpp: Regex,
+    sharp: Regex,
+    ampersand: Regex,
+}
+
+impl Normalizer {
+    #[must_use]
+    pub fn new(language: &str, options: NormalizeOptions) -> Self {
+        let lexicon = lexicon(language);
+        let unit_alternatives = lexicon.map_or_else(String::new, |lex| {
+            lex.units
+                .iter()
+                .map(|(unit, _, _)| regex::escape(unit))
+                .collect::<Vec<_>>()
+                .join("|")
+        });
+        Self {
+            options,
+            lexicon,
+            is_english: primary_language(language) == "en",
+            url: compile(r"\b(?:https?://|www\.)[^\s<>]*[^\s<>.,;:!?)\]]"),
+            date: compile(r"\b(\d{4})-(\d{2})-(\d{2})\b"),
+            time: compile(&format!(
+                r"(?:\b((?i:{})\s+))?\b([01]?\d|2[0-3]):([0-5]\d)(?::[0-5]\d)?\b(\s?(?i:[ap]m)\b)?",
+                lexicon.map_or("at", |lex| lex.time_words)
+            )),
+            currency_prefix: compile(
+                r"([$€£¥])\s?(\d+(?:[.,]\d+)*)(?:\s?(K|M|B|bn|k|thousand|million|billion)\b)?",
+            ),
+            currency_suffix: compile(r"\b(\d+(?:[.,]\d+)*)\s?([$€£¥])"),
+            range: compile(r"\b(\d+(?:[.,]\d+)*)\s?([-–])\s?(\d+(?:[.,]\d+)*)\b"),
+            unit: (!unit_alternatives.is_empty()).then(|| {
+                compile(&format!(
+                    r"\b(\d+(?:[.,]\d+)?)\s?({unit_alternatives})(\w?)"
+                ))
+            }),
+            times: compile(r"\b(\d+(?:[.,]\d+)?)(?:\s?×|x\b|\sx\s(\d))"),
+            ordinal: compile(r"\b(\d+)(?:st|nd|rd|th)\b"),
+            roman_keyword: lexicon
+                .map(|lex| compile(&format!(r"\b({})\s+([IVXLCDM]+)\b", lex.headings))),
+            roman_leading: compile(r"^(\s*)([IVXLCDM]+)([.:])(\s)"),
+            cpp: compile(r"\b([A-Za-z])\+\+"),
+            sharp: compile(r"\b([CF])#"),
+            ampersand: compile(r"\s&\s"),
+        }
+    }
+
+    #[must_use]
+    pub fn normalize(&self, text: &str) -> String {
+        self.normalize_block(text, false)
+    }
+
+    /// Like `normalize`, also reading a leading roman numeral as a number.
+    #[must_use]
+    pub fn normalize_heading(&self, text: &str) -> String {
+        self.normalize_block(text, true)
+    }
+
+    /// Normalizes one unit of an article: headings with `normalize_heading`,
+    /// code not at all.
+    #[must_use]
+    pub fn normalize_unit(&self, text: &str, kind: BlockKind, heading: bool) -> String {
+        match kind {
+            BlockKind::Code => text.to_string(),
+            _ if heading => self.normalize_heading(text),
+            _ => self.normalize(text),
+        }
+    }
+
+    fn normalize_block(&self, text: &str, heading: bool) -> String {
+        let options = &self.options;
+        let mut result = text.to_string();
+        if options.urls {
+            result = self.normalize_urls(&result);
+        }
+        if let Some(lex) = self.lexicon {
+            if options.dates {
+                result = self.normalize_dates(lex, &result);
+            }
+            if options.times {
+                result = self.normalize_times(lex, &result);
+            }
+            if options.currencies {
+                result = self.normalize_currencies(lex, &result);
+            }
+            if options.ranges {
+                result = self.normalize_ranges(lex, &result);
+            }
+            if options.units {
+                result = self.normalize_units(lex, &result);
+            }
+            if options.roman_numerals {
+                result = self.normalize_roman_numerals(&result, heading);
+            }
+        }
+        if options.ordinals && self.is_english {
+            result = self
+                .ordinal
+                .replace_all(&result, |caps: &Captures| {
+                    caps[1]
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|n| *n < 1_000_000)
+                        .map_or_else(|| caps[0].to_string(), english_ordinal)
+                })
+                .into_owned();
+        }
+        if options.symbols {
+            result = self.normalize_symbols(&result);
+        }
+        result
+    }
+
+    fn normalize_urls(&self, text: &str) -> String {
+        self.url
+            .replace_all(text, |caps: &Captures| {
+                let raw = &caps[0];
+                let with_scheme = if raw.starts_with("www.") {
+                    format!("https://{raw}")
+                } else {
+                    raw.to_string()
+                };
+                url::Url::parse(&with_scheme)
+                    .ok()
+                    .and_then(|u| {
+                        u.host_str()
+                            .map(|h| h.trim_start_matches("www.").to_string())
+                    })
+                    .unwrap_or_else(|| raw.to_string())
+            })
+            .into_owned()
+    }
+
+    fn normalize_dates(&self, lex: &Lexicon, text: &str) -> String {
+        self.date
+            .replace_all(text, |caps: &Captures| {
+                let month = caps[2].parse::<usize>().unwrap_or(0);
+                let day = caps[3].parse::<u32>().unwrap_or(0);
+                if (1..=12).contains(&month) && (1..=31).contains(&day) {
+                    (lex.date)(lex, day, month - 1, &caps[1])
+                } else {
+                    caps[0].to_string()
+                }
+            })
+            .into_owned()
+    }
+
+    fn normalize_times(&self, lex: &Lexicon, text: &str) -> String {
+        self.time
+            .replace_all(text, |caps: &Captures| {
+                let (word, suffix) = (caps.get(1), caps.get(4));
+                if word.is_none() && suffix.is_none() {
+                    return caps[0].to_string();
+                }
+                let hour = caps[2].parse::<u32>().unwrap_or(0);
+                let minute = caps[3].parse::<u32>().unwrap_or(0);
+                format!(
+                    "{}{}{}",
+                    word.map_or("", |m| m.as_str()),
+                    (lex.time)(hour, minute),
+                    suffix.map_or("", |m| m.as_str())
+                )
+            })
+            .into_owned()
+    }
+
+    fn normalize_currencies(&self, lex: &Lexicon, text: &str) -> String {
+        let currency = |symbol: &str, amount: &str, has_multiplier: bool| {
+            lex.currencies
+                .iter()
+                .find(|(s, _, _)| *s == symbol)
+                .map_or(symbol, |(_, singular, plural)| {
+                    if amount == "1" && !has_multiplier {
+                        singular
+                    } else {
+                        plural
+                    }
+                })
+                .to_string()
+        };
+        let result = self
+            .currency_prefix
+            .replace_all(text, |caps: &Captures| {
+                let amount = &caps[2];
+                let multiplier = caps.get(3).map(|m| match m.as_str() {
+                    "K" | "k" | "thousand" => lex.multipliers[0],
+                    "M" | "million" => lex.multipliers[1],
+                    _ => lex.multipliers[2],
+                });
+                let name = currency(&caps[1], amount, multiplier.is_some());
+                match multiplier {
+                    Some(multiplier) => format!("{amount} {multiplier} {name}"),
+                    None => format!("{amount} {name}"),
+                }
+            })
+            .into_owned();
+        self.currency_suffix
+            .replace_all(&result, |caps: &Captures| {
+                format!("{} {}", &caps[1], currency(&caps[2], &caps[1], false))
+            })
+            .into_owned()
+    }
+
+    fn normalize_ranges(&self, lex: &Lexicon, text: &str) -> String {
+        self.range
+            .replace_all(text, |caps: &Captures| {
+                let (from, to) = (&caps[1], &caps[3]);
+                let is_en_dash = &caps[2] == "–";
+                let is_increasing = matches!(
+                    (from.parse::<u64>(), to.parse::<u64>()),
+                    (Ok(from), Ok(to)) if from < to
+                );
+                if is_en_dash || (from.len() == to.len() && from.len() <= 4 && is_increasing) {
+                    format!("{from} {} {to}", lex.to)
+                } else {
+                    caps[0].to_string()
+                }
+            })
+            .into_owned()
+    }
+
+    fn normalize_units(&self, lex: &Lexicon, text: &str) -> String {
+        let result = self.unit.as_ref().map_or_else(
+            || text.to_string(),
+            |re| {
+                re.replace_all(text, |caps: &Captures| {
+                    let value = &caps[1];
+                    let unit = lex.units.iter().find(|(unit, _, _)| *unit == &caps[2]);
+                    match unit {
+                        Some((_, singular, plural)) if caps[3].is_empty() => {
+                            let name = if value == "1" { singular } else { plural };
+                            format!("{value} {name}")
+                        }
+                        _ => caps[0].to_string(),
+                    }
+                })
+                .into_owned()
+            },
+        );
+        self.times
+            .replace_all(&result, |caps: &Captures| match caps.get(2) {
+                Some(factor) => format!("{} {} {}", &caps[1], lex.times, factor.as_str()),
+                None => format!("{} {}", &caps[1], lex.times),
+            })
+            .into_owned()
+    }
+
+    fn normalize_roman_numerals(&self, text: &str, heading: bool) -> String {
+        let mut result = self.roman_keyword.as_ref().map_or_else(
+            || text.to_string(),
+            |re| {
+                re.replace_all(text, |caps: &Captures| {
+                    roman_numeral(&caps[2])
+                        .map_or_else(|| caps[0].to_string(), |n| format!("{} {n}", &caps[1]))
+                })
+                .into_owned()
+            },
+        );
+        if heading {
+            result = self
+                .roman_leading
+                .replace(&result, |caps: &Captures| {
+                    roman_numeral(&caps[2]).map_or_else(
+                        || caps[0].to_string(),
+                        |n| format!("{}{n}{}{}", &caps[1], &caps[3], &caps[4]),
+                    )
+                })
+                .into_owned();
+        }
+        result
+    }
+
+    fn normalize_symbols(&self, text: &str) -> String {
+        let and = self.lexicon.map_or("&", |lex| lex.and);
+        let result = self.cpp.replace_all(text, "$1 plus plus").into_owned();
+        let result = self.sharp.replace_all(&result, "$1 sharp").into_owned();
+        self.ampersand
+            .replace_all(&result, format!(" {and} ").as_str())
+            .into_owned()
+    }
+}
+
+const ROMAN_VALUES: [(u32, &str); 13] = [
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+/// A roman numeral found in text, skipping the single letters that are
+/// rarely meant as numbers.
+fn roman_numeral(roman: &str) -> Option<u32> {
+    if roman.len() == 1 && !matches!(roman, "I" | "V" | "X") {
+        return None;
+    }
+    roman_to_number(roman)
+}
+
+/// Parses a canonical Roman numeral (`XIV`); non-canonical forms such as
+/// `IIII` or `IC` return `None`.
+#[must_use]
+pub fn roman_to_number(roman: &str) -> Option<u32> {
+    let mut rest = roman;
+    let mut total = 0;
+    for (value, symbol) in ROMAN_VALUES {
+        while let Some(stripped) = rest.strip_prefix(symbol) {
+            rest = stripped;
+            total += value;
+        }
+    }
+    (rest.is_empty() && total > 0 && to_roman(total) == roman).then_some(total)
+}
+
+fn to_roman(mut number: u32) -> String {
+    let mut result = String::new();
+    for (value, symbol) in ROMAN_VALUES {
+        while number >= value {
+            result.push_str(symbol);
+            number -= value;
+        }
+    }
+    result
+}
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Spells out a number below one million in English (`342` becomes
+/// `three hundred forty-two`).
+#[must_use]
+pub fn english_number(n: u64) -> String {
+    match n {
+        0..=19 => ONES[usize::try_from(n).unwrap_or_default()].to_string(),
+        20..=99 => {
+            let tens = TENS[usize::try_from(n / 10).unwrap_or_default()];
+            match n % 10 {
+                0 => tens.to_string(),
+                ones => format!("{tens}-{}", english_number(ones)),
+            }
+        }
+        100..=999 => match n % 100 {
+            0 => format!("{} hundred", english_number(n / 100)),
+            rest => format!(
+                "{} hundred {}",
+                english_number(n / 100),
+                english_number(rest)
+            ),
+        },
+        _ => match n % 1000 {
+            0 => format!("{} thousand", english_number(n / 1000)),
+            rest => format!(
+                "{} thousand {}",
+                english_number(n / 1000),
+                english_number(rest)
+            ),
+        },
+    }
+}
+
+/// Spells out an English ordinal (`21` becomes `twenty-first`).
+#[must_use]
+pub fn english_ordinal(n: u64) -> String {
+    let cardinal = english_number(n);
+    let split = cardinal.rfind([' ', '-']).map_or(0, |pos| pos + 1);
+    let (head, last) = cardinal.split_at(split);
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        word if word.ends_with('y') => format!("{}ieth", &word[..word.len() - 1]),
+        word => format!("{word}th"),
+    };
+    format!("{head}{last}")
+}
+
+#[cfg(test)]
+mod test_normalize;
@@ -0,0 +1,172 @@
+use super::*;
+
+fn en(text: &str) -> String {
+    Normalizer::new("en", NormalizeOptions::default()).normalize(text)
+}
+
+fn de(text: &str) -> String {
+    Normalizer::new("de-DE", NormalizeOptions::default()).normalize(text)
+}
+
+#[test]
+fn test_urls_collapse_to_domain() {
+    assert_eq!(
+        en("Read https://www.rust-lang.org/learn/get-started?x=1. Then code."),
+        "Read rust-lang.org. Then code."
+    );
+    assert_eq!(
+        en("See www.example.com/path, it helps"),
+        "See example.com, it helps"
+    );
+}
+
+#[test]
+fn test_dates() {
+    assert_eq!(en("Released 2026-10-18."), "Released October 18, 2026.");
+    assert_eq!(de("Am 2026-10-18."), "Am 18. Oktober 2026.");
+    assert_eq!(en("Build 2026-13-45"), "Build 2026-13-45");
+}
+
+#[test]
+fn test_times() {
+    assert_eq!(
+        en("Meet at 14:30, by 9:05 or at 10:00."),
+        "Meet at 14 30, by 9 oh 5 or at 10 o'clock."
+    );
+    assert_eq!(en("Leaves 3:45 pm"), "Leaves 3 45 pm");
+    assert_eq!(de("Um 14:30 oder um 9:00."), "Um 14 Uhr 30 oder um 9 Uhr.");
+    // Verses and ratios are not times.
+    assert_eq!(en("John 3:16 says"), "John 3:16 says");
+    assert_eq!(en("A 16:9 screen"), "A 16:9 screen");
+}
+
+#[test]
+fn test_currencies() {
+    assert_eq!(
+        en("It raised $1.2M and €5."),
+        "It raised 1.2 million dollars and 5 euros."
+    );
+    assert_eq!(
+        en("Costs $1 or £20 billion"),
+        "Costs 1 dollar or 20 billion pounds"
+    );
+    assert_eq!(
+        de("Kostet 5 € oder $3K"),
+        "Kostet 5 Euro oder 3 Tausend Dollar"
+    );
+}
+
+#[test]
+fn test_ranges() {
+    assert_eq!(
+        en("Pages 10-20 and 1990–1995."),
+        "Pages 10 to 20 and 1990 to 1995."
+    );
+    assert_eq!(en("Call 555-1234, score 5-3"), "Call 555-1234, score 5-3");
+    assert_eq!(de("Seiten 10-20"), "Seiten 10 bis 20");
+    assert_eq!(en("Version 2.0-3"), "Version 2.0-3");
+    assert_eq!(en("Takes 1.5–2 hours"), "Takes 1.5 to 2 hours");
+}
+
+#[test]
+fn test_units() {
+    assert_eq!(
+        en("A 3.5GHz CPU, 1 GB RAM, 10× faster, 20% less."),
+        "A 3.5 gigahertz CPU, 1 gigabyte RAM, 10 times faster, 20 percent less."
+    );
+    assert_eq!(en("Took 5 msgs and 4x4 cars"), "Took 5 msgs and 4x4 cars");
+    assert_eq!(en("A 3 x 4 grid"), "A 3 times 4 grid");
+    assert_eq!(en("In 1990-1995 x"), "In 1990 to 1995 x");
+    assert_eq!(en("Windows 10 x64"), "Windows 10 x64");
+    assert_eq!(en("Got 2 x86 machines"), "Got 2 x86 machines");
+    assert_eq!(
+        de("Mit 100 km/h und 10x"),
+        "Mit 100 Kilometer pro Stunde und 10 mal"
+    );
+}
+
+#[test]
+fn test_ordinals() {
+    assert_eq!(
+        en("The 1st, 22nd and 103rd."),
+        "The first, twenty-second and one hundred third."
+    );
+    assert_eq!(de("Der 1st"), "Der 1st");
+}
+
+#[test]
+fn test_roman_numerals() {
+    assert_eq!(en("Chapter IV begins"), "Chapter 4 begins");
+    let normalizer = Normalizer::new("en", NormalizeOptions::default());
+    assert_eq!(
+        normalizer.normalize_heading("XII. The Return"),
+        "12. The Return"
+    );
+    assert_eq!(normalizer.normalize_heading("C. Results"), "C. Results");
+    assert_eq!(en("XII. The Return"), "XII. The Return");
+    assert_eq!(en("M. Smith said hi"), "M. Smith said hi");
+    assert_eq!(en("See Appendix C"), "See Appendix C");
+    assert_eq!(en("Section D"), "Section D");
+    assert_eq!(en("Part II and Section V"), "Part 2 and Section 5");
+    assert_eq!(en("Henry VIII was king"), "Henry VIII was king");
+    assert_eq!(de("Kapitel XIV"), "Kapitel 14");
+    assert_eq!(roman_to_number("IIII"), None);
+    assert_eq!(roman_to_number("IC"), None);
+    assert_eq!(roman_to_number("MCMXCIV"), Some(1994));
+}
+
+#[test]
+fn test_symbols() {
+    assert_eq!(en("C++ & C# & F#"), "C plus plus and C sharp and F sharp");
+    assert_eq!(de("Tom & Jerry"), "Tom und Jerry");
+}
+
+#[test]
+fn test_unknown_language() {
+    let normalizer = Normalizer::new("xx", NormalizeOptions::default());
+    assert_eq!(
+        normalizer.normalize("On 2026-10-18 see https://example.com/a & C++"),
+        "On 2026-10-18 see example.com & C plus plus"
+    );
+}
+
+#[test]
+fn test_options_disable_steps() {
+    let mut options = NormalizeOptions::default();
+    options.apply_setting("normalize_dates", "false");
+    options.apply_setting("normalize_urls", "false");
+    options.apply_setting("normalize_unknown", "false");
+    let normalizer = Normalizer::new("en", options);
+    assert_eq!(
+        normalizer.normalize("On 2026-10-18 see https://example.com at 10:00"),
+        "On 2026-10-18 see https://example.com at 10 o'clock"
+    );
+}
+
+#[test]
+fn test_english_numbers() {
+    assert_eq!(english_number(0), "zero");
+    assert_eq!(english_number(342), "three hundred forty-two");
+    assert_eq!(english_number(12_005), "twelve thousand five");
+    assert_eq!(english_ordinal(12), "twelfth");
+    assert_eq!(english_ordinal(40), "fortieth");
+    assert_eq!(english_ordinal(101), "one hundred first");
+}
+
+#[test]
+fn test_code_is_not_normalized() {
+    let normalizer = Normalizer::new("en", NormalizeOptions::default());
+    let code = "let size = 10-20 * 3 x 4; // 50%";
+    assert_eq!(
+        normalizer.normalize_unit(code, BlockKind::Code, false),
+        code
+    );
+    assert_eq!(
+        normalizer.normalize_unit("Costs 50%", BlockKind::Prose, false),
+        "Costs 50 percent"
+    );
+    assert_eq!(
+        normalizer.normalize_unit("XII. The Return", BlockKind::Prose, true),
+        "12. The Return"
+    );
+}
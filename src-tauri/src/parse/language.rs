@@ -0,0 +1,115 @@
+// Language Detection:
+//
+// 1. Letters are counted per script. Arabic, Devanagari, Kannada, Han and
+//    Cyrillic text maps straight to ar, hi, kn, zh and ru.
+// 2. Latin text is scored by counting common function words of en, de,
+//    es, fr and pt. The highest score wins; no hits means unknown.
+// 3. Only the first `SAMPLE_CHARS` characters are looked at.
+
+const SAMPLE_CHARS: usize = 4000;
+
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "of", "to", "is", "that", "it", "with", "for", "was", "this", "are",
+            "you", "not", "be", "have",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "und", "das", "ist", "nicht", "ein", "eine", "mit", "sich", "auf", "für",
+            "den", "zu", "ich", "auch",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "de", "que", "y", "los", "las", "en", "por", "una", "es", "con", "para",
+            "del", "se", "no",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "et", "des", "est", "une", "un", "du", "que", "pas", "pour", "dans",
+            "qui", "il", "de",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "a", "os", "de", "que", "e", "não", "uma", "um", "do", "da", "em", "para", "com",
+            "é", "se",
+        ],
+    ),
+];
+
+#[derive(Default)]
+struct ScriptCounts {
+    latin: usize,
+    arabic: usize,
+    devanagari: usize,
+    kannada: usize,
+    han: usize,
+    cyrillic: usize,
+}
+
+fn count_scripts(text: &str) -> ScriptCounts {
+    let mut counts = ScriptCounts::default();
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        match c {
+            '\u{0600}'..='\u{06FF}' => counts.arabic += 1,
+            '\u{0900}'..='\u{097F}' => counts.devanagari += 1,
+            '\u{0C80}'..='\u{0CFF}' => counts.kannada += 1,
+            '\u{4E00}'..='\u{9FFF}' => counts.han += 1,
+            '\u{0400}'..='\u{04FF}' => counts.cyrillic += 1,
+            c if c.is_ascii_alphabetic() || ('\u{00C0}'..='\u{024F}').contains(&c) => {
+                counts.latin += 1;
+            }
+            _ => {}
+        }
+    }
+    counts
+}
+
+fn detect_latin(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    STOPWORDS
+        .iter()
+        .map(|(language, stopwords)| {
+            let hits = words
+                .iter()
+                .filter(|word| stopwords.contains(&word.as_str()))
+                .count();
+            (hits, *language)
+        })
+        .filter(|(hits, _)| *hits > 0)
+        .max_by_key(|(hits, _)| *hits)
+        .map(|(_, language)| language)
+}
+
+/// Guesses the primary language subtag of `text`. Returns `None` when the
+/// text has no letters or the language is not recognised.
+#[must_use]
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let sample: String = text.chars().take(SAMPLE_CHARS).collect();
+    let counts = count_scripts(&sample);
+    [
+        (counts.arabic, Some("ar")),
+        (counts.devanagari, Some("hi")),
+        (counts.kannada, Some("kn")),
+        (counts.han, Some("zh")),
+        (counts.cyrillic, Some("ru")),
+        (counts.latin, None),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .max_by_key(|(count, _)| *count)
+    .and_then(|(_, language)| language.or_else(|| detect_latin(&sample)))
+}
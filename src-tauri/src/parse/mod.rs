@@ -19,9 +19,22 @@ use std::cell::RefCell;
 //    For code/pre elements, each line (delimited by newline) is one
 //    tts_para unit; semicolons and braces also split code lines.
 // 4. Class ordering: `tts_para_N` is always prepended as the first class.
-//    Other classes (`tts_code_block`, `tts_anchor`) are appended after.
+//    Other classes (`tts_code_block`, `tts_anchor`, `tts_image`) are
+//    appended after.
+// 5. Images with a non-empty alt text in blocks without text are their own
+//    tts_para unit (`tts_image`); the alt text is the unit text. Whether it
+//    is read is decided by the speak bar.
 
-use crate::models::Snippet;
+use crate::models::{
+    BlockKind, InlineKind, InlineRef, ProcessedArticle, Snippet, TableCell, TocEntry, TtsParagraph,
+};
+
+mod language;
+mod segment;
+
+pub use language::detect_language;
+pub use segment::{SegmentRules, normalize_abbreviation, primary_language, rule_pattern};
+use segment::segment_sentences;
 
 fn make_qual_name(tag: &str) -> QualName {
     QualName::new(None, ns!(html), tag.into())
@@ -33,6 +46,11 @@ const HTML_CLOSE: &[u8] = b"</html>";
 const DIV_OPEN: &[u8] = b"<div> ";
 const DIV_CLOSE: &[u8] = b" </div>";
 
+struct TtsContext<'a> {
+    current_id: RefCell<u32>,
+    rules: &'a SegmentRules,
+}
+
 #[derive(Clone, Debug)]
 enum ContentItem {
     Text {
@@ -181,13 +199,13 @@ fn is_single_path_to_text(item: &ContentItem) -> bool {
     }
 }
 
-fn tag_innermost_text_element(node: &NodeRef, current_id: &RefCell<u32>) {
+fn tag_innermost_text_element(node: &NodeRef, ctx: &TtsContext) {
     if let Some(element) = node.as_element() {
         let has_elem_children = node.children().any(|c| c.as_element().is_some());
         if !has_elem_children {
-            tag_element(element, current_id);
+            tag_element(element, &ctx.current_id);
         } else if let Some(first_elem) = node.children().find(|c| c.as_element().is_some()) {
-            tag_innermost_text_element(&first_elem, current_id);
+            tag_innermost_text_element(&first_elem, ctx);
         }
     }
 }
@@ -264,116 +282,6 @@ fn build_flat_string(items: &[ContentItem]) -> String {
     result
 }
 
-fn segment_sentences(text: &str, max_len: usize) -> Vec<(usize, usize)> {
-    let mut sentences = Vec::new();
-    let chars: Vec<char> = text.chars().collect();
-    let len = chars.len();
-
-    if len == 0 {
-        return sentences;
-    }
-
-    let mut start = 0;
-    let mut i = 0;
-
-    while i < len {
-        let ch = chars[i];
-
-        if ch == '.' || ch == '!' || ch == '?' || ch == ';' {
-            if ch == '.' && i + 1 < len && chars[i + 1].is_lowercase() {
-                i += 1;
-                continue;
-            }
-
-            if ch == '.' && i + 1 < len && chars[i + 1].is_whitespace() {
-                let mut word_len = 0;
-                let mut j = i;
-                while j > 0 && !chars[j - 1].is_whitespace() && chars[j - 1] != '.' {
-                    word_len += 1;
-                    j -= 1;
-                }
-                if word_len <= 2 {
-                    i += 1;
-                    continue;
-                }
-            }
-
-            if i + 1 >= len || chars[i + 1].is_whitespace() {
-                let end = i + 1;
-
-                if end > start {
-                    let sentence_text: String = chars[start..end].iter().collect();
-                    if !sentence_text.trim().is_empty() {
-                        sentences.push((start, end));
-                    }
-                }
-
-                start = end;
-                i = end;
-                continue;
-            }
-        }
-
-        i += 1;
-    }
-
-    if start < len {
-        let sentence_text: String = chars[start..].iter().collect();
-        if !sentence_text.trim().is_empty() {
-            sentences.push((start, len));
-        }
-    }
-
-    let mut final_sentences = Vec::new();
-    for (start, end) in sentences {
-        let sentence_len = end - start;
-        if sentence_len <= max_len {
-            final_sentences.push((start, end));
-        } else {
-            let sentence_text: String = chars[start..end].iter().collect();
-            let sub_sentences = split_long_sentence(&sentence_text, start, max_len);
-            final_sentences.extend(sub_sentences);
-        }
-    }
-
-    final_sentences
-}
-
-fn split_long_sentence(text: &str, base_start: usize, max_len: usize) -> Vec<(usize, usize)> {
-    let mut result = Vec::new();
-    let chars: Vec<char> = text.chars().collect();
-    let len = chars.len();
-
-    let mut start = 0;
-    let mut last_break = 0;
-
-    for i in 0..len {
-        let ch = chars[i];
-
-        if ch == ',' || ch == ';' || ch == ':' || ch.is_whitespace() {
-            last_break = i;
-        }
-
-        if i - start >= max_len && last_break > start {
-            let break_text: String = chars[start..last_break].iter().collect();
-            if !break_text.trim().is_empty() {
-                result.push((base_start + start, base_start + last_break));
-            }
-            start = last_break + 1;
-            last_break = start;
-        }
-    }
-
-    if start < len {
-        let remaining: String = chars[start..].iter().collect();
-        if !remaining.trim().is_empty() {
-            result.push((base_start + start, base_start + len));
-        }
-    }
-
-    result
-}
-
 fn clip_items(items: &[ContentItem], start: usize, end: usize) -> Vec<ContentItem> {
     let mut result = Vec::new();
 
@@ -512,7 +420,7 @@ fn make_tts_para_span(text: String, current_id: &RefCell<u32>) -> NodeRef {
     span
 }
 
-fn process_node(node: &NodeRef, current_id: &RefCell<u32>) {
+fn process_node(node: &NodeRef, ctx: &TtsContext) {
     if let Some(element) = node.as_element() {
         let tag_name = element.name.local.as_ref();
 
@@ -522,19 +430,19 @@ fn process_node(node: &NodeRef, current_id: &RefCell<u32>) {
         }
 
         if is_code_tag(tag_name) && is_real_code_block(node) {
-            process_code_element(node, current_id);
+            process_code_element(node, ctx);
             return;
         }
 
-        process_element_tts(node, current_id);
+        process_element_tts(node, ctx);
     } else if node.as_document().is_some() {
         for child in node.children() {
-            process_node(&child, current_id);
+            process_node(&child, ctx);
         }
     }
 }
 
-fn process_element_tts(node: &NodeRef, current_id: &RefCell<u32>) {
+fn process_element_tts(node: &NodeRef, ctx: &TtsContext) {
     if let Some(element) = node.as_element() {
         let tag_name = element.name.local.as_ref();
         if is_code_tag(tag_name) && is_real_code_block(node) {
@@ -555,20 +463,20 @@ fn process_element_tts(node: &NodeRef, current_id: &RefCell<u32>) {
     if has_block_or_code_children {
         for child in &children {
             if child.as_element().is_some() {
-                process_node(child, current_id);
+                process_node(child, ctx);
             } else if let Some(text) = child.as_text() {
                 let text_content = text.borrow().clone();
                 if !text_content.trim().is_empty() {
-                    let sentences = segment_sentences(&text_content, MAX_LENGTH);
+                    let sentences = segment_sentences(&text_content, MAX_LENGTH, ctx.rules);
                     if sentences.len() <= 1 {
-                        let span = make_tts_para_span(text_content, current_id);
+                        let span = make_tts_para_span(text_content, &ctx.current_id);
                         child.insert_before(span);
                         child.detach();
                     } else {
                         let text_chars: Vec<char> = text_content.chars().collect();
                         for (start, end) in sentences {
                             let sentence_text: String = text_chars[start..end].iter().collect();
-                            let span = make_tts_para_span(sentence_text, current_id);
+                            let span = make_tts_para_span(sentence_text, &ctx.current_id);
                             child.insert_before(span);
                         }
                         child.detach();
@@ -584,14 +492,15 @@ fn process_element_tts(node: &NodeRef, current_id: &RefCell<u32>) {
     let flat_text = build_flat_string(&items);
 
     if flat_text.trim().is_empty() {
+        tag_images(node, ctx);
         return;
     }
 
-    let sentences = segment_sentences(&flat_text, MAX_LENGTH);
+    let sentences = segment_sentences(&flat_text, MAX_LENGTH, ctx.rules);
 
     if sentences.len() <= 1 {
         if let Some(element) = node.as_element() {
-            tag_element(element, current_id);
+            tag_element(element, &ctx.current_id);
         }
         return;
     }
@@ -609,7 +518,7 @@ fn process_element_tts(node: &NodeRef, current_id: &RefCell<u32>) {
         {
             let nodes = build_dom_from_items(&clipped);
             if let Some(elem) = nodes.first() {
-                tag_innermost_text_element(elem, current_id);
+                tag_innermost_text_element(elem, ctx);
                 node.append(elem.clone());
                 continue;
             }
@@ -618,7 +527,7 @@ fn process_element_tts(node: &NodeRef, current_id: &RefCell<u32>) {
         let span =
             NodeRef::new_element(QualName::new(None, ns!(html), local_name!("span")), vec![]);
         if let Some(element) = span.as_element() {
-            tag_element(element, current_id);
+            tag_element(element, &ctx.current_id);
         }
         let child_nodes = build_dom_from_items(&clipped);
         for child in child_nodes {
@@ -628,7 +537,28 @@ fn process_element_tts(node: &NodeRef, current_id: &RefCell<u32>) {
     }
 }
 
-fn process_code_element(node: &NodeRef, current_id: &RefCell<u32>) {
+fn image_alt(node: &NodeRef) -> Option<String> {
+    let element = node.as_element()?;
+    if element.name.local.as_ref() != "img" {
+        return None;
+    }
+    let alt = element.attributes.borrow().get("alt")?.trim().to_string();
+    (!alt.is_empty()).then_some(alt)
+}
+
+fn tag_images(node: &NodeRef, ctx: &TtsContext) {
+    let Ok(images) = node.select("img[alt]") else {
+        return;
+    };
+    for image in images {
+        if image_alt(image.as_node()).is_some() {
+            tag_element(&image, &ctx.current_id);
+            append_class(&mut image.attributes.borrow_mut(), "tts_image");
+        }
+    }
+}
+
+fn process_code_element(node: &NodeRef, ctx: &TtsContext) {
     if let Some(element) = node.as_element() {
         let text_content = node.text_contents();
         let has_newlines = text_content.contains('\n');
@@ -644,14 +574,14 @@ fn process_code_element(node: &NodeRef, current_id: &RefCell<u32>) {
                 }
 
                 for unit in units {
-                    let span = make_tts_para_span(unit, current_id);
+                    let span = make_tts_para_span(unit, &ctx.current_id);
                     node.append(span);
                 }
                 return;
             }
         }
 
-        tag_element(element, current_id);
+        tag_element(element, &ctx.current_id);
     }
 }
 
@@ -782,14 +712,230 @@ fn process_node_url(node: &NodeRef, url: &str) {
     }
 }
 
+fn has_class(node: &NodeRef, class: &str) -> bool {
+    node.as_element().is_some_and(|element| {
+        element
+            .attributes
+            .borrow()
+            .get("class")
+            .is_some_and(|classes| classes.split_whitespace().any(|c| c == class))
+    })
+}
+
+fn paragraph_kind(node: &NodeRef, cell: Option<&TableCell>) -> BlockKind {
+    if node
+        .inclusive_ancestors()
+        .any(|ancestor| has_class(&ancestor, "tts_code_block"))
+    {
+        BlockKind::Code
+    } else if has_class(node, "tts_image") {
+        BlockKind::Image
+    } else if cell.is_some() {
+        BlockKind::Table
+    } else if node
+        .inclusive_ancestors()
+        .any(|ancestor| element_name(&ancestor).as_deref() == Some("figcaption"))
+    {
+        BlockKind::Caption
+    } else {
+        BlockKind::Prose
+    }
+}
+
+fn element_name(node: &NodeRef) -> Option<String> {
+    node.as_element()
+        .map(|element| element.name.local.as_ref().to_string())
+}
+
+/// A fragment link is a footnote reference when it is marked as one or is
+/// a superscript.
+fn is_footnote_ref(anchor: &NodeRef, href: &str) -> bool {
+    let role = anchor
+        .as_element()
+        .and_then(|element| element.attributes.borrow().get("role").map(str::to_string));
+    href.starts_with('#')
+        && (role.as_deref() == Some("doc-noteref")
+            || anchor
+                .inclusive_ancestors()
+                .take(3)
+                .any(|ancestor| element_name(&ancestor).as_deref() == Some("sup")))
+}
+
+fn inline_ref(anchor: &NodeRef, start: usize) -> Option<InlineRef> {
+    let href = anchor
+        .as_element()?
+        .attributes
+        .borrow()
+        .get("href")?
+        .to_string();
+    let text = anchor.text_contents().trim().to_string();
+    if text.is_empty() {
+        return None;
+    }
+    let kind = if is_footnote_ref(anchor, &href) {
+        InlineKind::Footnote
+    } else if has_class(anchor, "tts_anchor") {
+        InlineKind::Link
+    } else {
+        return None;
+    };
+    Some(InlineRef { kind, text, start })
+}
+
+/// Links and footnote references in a unit, with their offsets in the
+/// trimmed unit text.
+fn inline_refs(node: &NodeRef) -> Vec<InlineRef> {
+    let raw = node.text_contents();
+    let lead = raw.len() - raw.trim_start().len();
+    if let Some(anchor) = node
+        .ancestors()
+        .find(|ancestor| element_name(ancestor).as_deref() == Some("a"))
+    {
+        return inline_ref(&anchor, 0).into_iter().collect();
+    }
+    let mut refs = Vec::new();
+    let mut offset = 0;
+    for descendant in node.inclusive_descendants() {
+        if let Some(text) = descendant.as_text() {
+            offset += text.borrow().len();
+        } else if element_name(&descendant).as_deref() == Some("a") {
+            let start = offset.saturating_sub(lead);
+            refs.extend(inline_ref(&descendant, start));
+        }
+    }
+    refs
+}
+
+fn is_cell(node: &NodeRef) -> bool {
+    matches!(element_name(node).as_deref(), Some("td" | "th"))
+}
+
+/// Locates the table cell holding `node`. `tables` collects the tables seen
+/// so far, in document order.
+fn table_cell(node: &NodeRef, tables: &mut Vec<NodeRef>) -> Option<TableCell> {
+    let cell = node.inclusive_ancestors().find(is_cell)?;
+    let row = cell
+        .ancestors()
+        .find(|ancestor| element_name(ancestor).as_deref() == Some("tr"))?;
+    let table = row
+        .ancestors()
+        .find(|ancestor| element_name(ancestor).as_deref() == Some("table"))?;
+    let table_index = tables.iter().position(|t| *t == table).unwrap_or_else(|| {
+        tables.push(table.clone());
+        tables.len() - 1
+    });
+    let row_index = table
+        .select("tr")
+        .ok()?
+        .position(|tr| *tr.as_node() == row)?;
+    let column = cell.preceding_siblings().filter(is_cell).count();
+    let header = element_name(&cell).as_deref() == Some("th")
+        || row
+            .ancestors()
+            .any(|ancestor| element_name(&ancestor).as_deref() == Some("thead"));
+    Some(TableCell {
+        table: table_index,
+        row: row_index,
+        column,
+        header,
+    })
+}
+
+fn block_of(node: &NodeRef) -> Option<NodeRef> {
+    node.inclusive_ancestors().find(|ancestor| {
+        ancestor
+            .as_element()
+            .is_some_and(|element| is_block_element(element.name.local.as_ref()))
+    })
+}
+
+fn heading_level(node: &NodeRef) -> Option<u8> {
+    node.as_element()?
+        .name
+        .local
+        .strip_prefix('h')
+        .and_then(|level| level.parse::<u8>().ok())
+        .filter(|level| (1..=6).contains(level))
+}
+
+/// Returns the id of a heading, giving it `tts_heading_N` when it has none.
+fn heading_anchor(node: &NodeRef, index: usize) -> String {
+    let Some(element) = node.as_element() else {
+        return String::new();
+    };
+    let mut attributes = element.attributes.borrow_mut();
+    match attributes.get("id").filter(|id| !id.trim().is_empty()) {
+        Some(id) => id.to_string(),
+        None => {
+            let id = format!("tts_heading_{index}");
+            attributes.insert("id", id.clone());
+            id
+        }
+    }
+}
+
+/// Collects the `tts_para` units in order, and a TOC entry for the first
+/// unit of every heading. Consecutive units with the same nearest block
+/// element share a block index.
+fn collect_paragraphs(document: &NodeRef) -> (Vec<TtsParagraph>, Vec<TocEntry>) {
+    let mut paragraphs: Vec<TtsParagraph> = Vec::new();
+    let mut toc = Vec::new();
+    let mut tables = Vec::new();
+    let mut last_block: Option<NodeRef> = None;
+    let mut block = 0;
+    for element in document.select("[class^=\"tts_para_\"]").unwrap() {
+        let node = element.as_node();
+        let block_node = block_of(node);
+        let new_block = paragraphs.is_empty() || block_node != last_block;
+        if !paragraphs.is_empty() && new_block {
+            block += 1;
+        }
+        let heading = block_node.as_ref().and_then(heading_level);
+        if new_block && let (Some(level), Some(heading_node)) = (heading, &block_node) {
+            let text = heading_node.text_contents().trim().to_string();
+            if !text.is_empty() {
+                toc.push(TocEntry {
+                    level,
+                    text,
+                    para: paragraphs.len(),
+                    anchor: heading_anchor(heading_node, toc.len()),
+                });
+            }
+        }
+        let text = image_alt(node).unwrap_or_else(|| node.text_contents().trim().to_string());
+        let cell = table_cell(node, &mut tables);
+        paragraphs.push(TtsParagraph {
+            text: if text.is_empty() { ".".to_string() } else { text },
+            kind: paragraph_kind(node, cell.as_ref()),
+            block,
+            heading,
+            cell,
+            refs: inline_refs(node),
+        });
+        last_block = block_node;
+    }
+    (paragraphs, toc)
+}
+
 #[must_use]
 pub fn process_html(frag: &str, url: &str) -> String {
+    process_article(frag, url, &SegmentRules::default()).html
+}
+
+/// Processes an article body for display and returns the TTS paragraphs in
+/// `tts_para_N` order and the table of contents alongside the HTML.
+#[must_use]
+pub fn process_article(frag: &str, url: &str, rules: &SegmentRules) -> ProcessedArticle {
     let ctx_name = QualName::new(None, ns!(html), local_name!("article"));
     let document = parse_fragment(ctx_name, vec![]).one(frag);
-    let current_id = RefCell::new(0);
+    let ctx = TtsContext {
+        current_id: RefCell::new(0),
+        rules,
+    };
 
-    process_node(&document, &current_id);
+    process_node(&document, &ctx);
     process_node_url(&document, url);
+    let (paragraphs, toc) = collect_paragraphs(&document);
 
     let mut bytes = Vec::new();
     document.serialize(&mut bytes).unwrap();
@@ -803,7 +949,12 @@ pub fn process_html(frag: &str, url: &str) -> String {
             bytes[start..].copy_from_slice(DIV_CLOSE);
         }
     }
-    String::from_utf8(bytes).unwrap_or_else(|_| "<p>not valid utf8</p>".to_string())
+    let html = String::from_utf8(bytes).unwrap_or_else(|_| "<p>not valid utf8</p>".to_string());
+    ProcessedArticle {
+        html,
+        paragraphs,
+        toc,
+    }
 }
 
 #[must_use]
@@ -873,3 +1024,7 @@ pub fn build_snippet(body: &str, query: Option<&str>) -> Snippet {
 
 #[cfg(test)]
 mod test_parse;
+#[cfg(test)]
+mod test_language;
+#[cfg(test)]
+mod test_segment;
@@ -0,0 +1,305 @@
+use regex::Regex;
+use std::collections::HashSet;
+
+use crate::models::SegmentationRule;
+
+// Sentence Segmentation Rules:
+//
+// 1. `.`, `!`, `?`, `;` and `…` end a sentence when followed by whitespace
+//    or by the end of the text.
+// 2. A terminator covered by a non-breaking pattern (`No. 5`, `pp. 12`)
+//    never ends a sentence.
+// 3. A `.` after a known abbreviation of the language (`Dr.`, `etc.`,
+//    `z.B.`) never ends a sentence. Neither does one after a single
+//    uppercase letter that follows a name or another initial and comes
+//    before a capitalized word (`J. R. R. Tolkien`, `John F. Kennedy`).
+// 4. Initialisms (`U.S.`) and ellipses (`...`, `…`) only end a sentence
+//    when the next word starts with an uppercase letter.
+// 5. Sentences longer than `max_len` are split at the last comma, colon,
+//    semicolon or whitespace before the limit.
+
+const COMMON_ABBREVIATIONS: &[&str] = &["e.g", "i.e", "etc", "vs", "ca", "cf"];
+
+const EN_ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "mt", "approx", "fig", "figs", "eq", "vol",
+    "ch", "sec", "dept", "est", "inc", "ltd", "co", "corp", "al", "jan", "feb", "apr", "jun",
+    "jul", "aug", "sep", "sept", "oct", "nov", "dec",
+];
+
+const DE_ABBREVIATIONS: &[&str] = &[
+    "z.b", "d.h", "u.a", "bzw", "usw", "dr", "prof", "nr", "str", "vgl", "ggf", "evtl", "inkl",
+    "bspw", "abb", "hr", "fr", "jh",
+];
+
+const FR_ABBREVIATIONS: &[&str] = &["m", "mme", "mlle", "dr", "p.ex", "env", "av", "bd", "fig"];
+
+const ES_ABBREVIATIONS: &[&str] = &[
+    "sr", "sra", "srta", "dr", "dra", "p.ej", "ud", "uds", "aprox", "pág", "fig",
+];
+
+const PT_ABBREVIATIONS: &[&str] = &["sr", "sra", "dr", "dra", "p.ex", "aprox", "pág", "fig"];
+
+const EN_PATTERNS: &[&str] = &[r"(?i)\b(?:no|nos|pp?)\.\s+\d"];
+
+const DE_PATTERNS: &[&str] = &[r"(?i)\b(?:nr|s|abs|art)\.\s+\d"];
+
+#[derive(Clone, Debug)]
+pub struct SegmentRules {
+    abbreviations: HashSet<String>,
+    patterns: Vec<Regex>,
+}
+
+/// Returns the primary subtag of a language tag (`en-US` becomes `en`).
+#[must_use]
+pub fn primary_language(language: &str) -> String {
+    language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// Normalizes a user supplied abbreviation (`Fig.` becomes `fig`).
+#[must_use]
+pub fn normalize_abbreviation(abbreviation: &str) -> String {
+    abbreviation.trim().trim_end_matches('.').to_lowercase()
+}
+
+/// The pattern of a user rule as stored: regexes as given, abbreviations
+/// normalized.
+#[must_use]
+pub fn rule_pattern(pattern: &str, is_regex: bool) -> String {
+    if is_regex {
+        pattern.to_string()
+    } else {
+        normalize_abbreviation(pattern)
+    }
+}
+
+impl SegmentRules {
+    /// Builds the built-in dictionary for `language` (a BCP 47 tag or
+    /// primary subtag). Unknown languages only get the common entries.
+    #[must_use]
+    pub fn for_language(language: &str) -> Self {
+        let (abbreviations, patterns): (&[&str], &[&str]) =
+            match primary_language(language).as_str() {
+                "en" => (EN_ABBREVIATIONS, EN_PATTERNS),
+                "de" => (DE_ABBREVIATIONS, DE_PATTERNS),
+                "fr" => (FR_ABBREVIATIONS, &[]),
+                "es" => (ES_ABBREVIATIONS, &[]),
+                "pt" => (PT_ABBREVIATIONS, &[]),
+                _ => (&[], &[]),
+            };
+        Self {
+            abbreviations: COMMON_ABBREVIATIONS
+                .iter()
+                .chain(abbreviations)
+                .map(|a| (*a).to_string())
+                .collect(),
+            patterns: patterns.iter().filter_map(|p| Regex::new(p).ok()).collect(),
+        }
+    }
+
+    /// Adds user defined rules that apply to `language`. Rules with an
+    /// empty language apply to every language.
+    pub fn extend(&mut self, language: &str, rules: &[SegmentationRule]) {
+        let language = primary_language(language);
+        for rule in rules {
+            if !rule.language.is_empty() && primary_language(&rule.language) != language {
+                continue;
+            }
+            if rule.is_regex {
+                if let Ok(re) = Regex::new(&rule.pattern) {
+                    self.patterns.push(re);
+                }
+            } else {
+                self.abbreviations
+                    .insert(normalize_abbreviation(&rule.pattern));
+            }
+        }
+    }
+
+    fn is_abbreviation(&self, word: &str) -> bool {
+        self.abbreviations.contains(&word.to_lowercase())
+    }
+
+    fn protected_chars(&self, text: &str, len: usize) -> Vec<bool> {
+        let mut protected = vec![false; len];
+        if self.patterns.is_empty() {
+            return protected;
+        }
+
+        let mut char_index = vec![len; text.len() + 1];
+        for (idx, (byte, _)) in text.char_indices().enumerate() {
+            char_index[byte] = idx;
+        }
+
+        for re in &self.patterns {
+            for m in re.find_iter(text) {
+                for flag in &mut protected[char_index[m.start()]..char_index[m.end()]] {
+                    *flag = true;
+                }
+            }
+        }
+        protected
+    }
+}
+
+impl Default for SegmentRules {
+    fn default() -> Self {
+        Self::for_language("en")
+    }
+}
+
+fn is_terminator(ch: char) -> bool {
+    matches!(ch, '.' | '!' | '?' | ';' | '…')
+}
+
+fn is_initialism(word: &str) -> bool {
+    word.contains('.')
+        && word
+            .split('.')
+            .all(|part| part.chars().count() == 1 && part.chars().all(char::is_alphabetic))
+}
+
+/// Whether the word before a single letter is a name or another initial,
+/// or there is none, so the letter reads as an initial.
+fn follows_name(before: &[char]) -> bool {
+    let end = before
+        .iter()
+        .rposition(|c| !c.is_whitespace())
+        .map_or(0, |p| p + 1);
+    let start = before[..end]
+        .iter()
+        .rposition(|c| c.is_whitespace())
+        .map_or(0, |p| p + 1);
+    let previous: String = before[start..end].iter().collect();
+    let previous = previous.trim_start_matches(|c: char| !c.is_alphanumeric());
+    previous.is_empty()
+        || (previous.chars().next().is_some_and(char::is_uppercase)
+            && previous.chars().all(|c| c.is_alphabetic() || c == '.'))
+}
+
+fn ends_sentence(chars: &[char], dot: usize, rules: &SegmentRules) -> bool {
+    let word_start = chars[..dot]
+        .iter()
+        .rposition(|c| c.is_whitespace())
+        .map_or(0, |p| p + 1);
+    let word: String = chars[word_start..dot].iter().collect();
+    let word = word.trim_start_matches(|c: char| !c.is_alphanumeric() && c != '.');
+    let next_is_upper = chars[dot + 1..]
+        .iter()
+        .find(|c| !c.is_whitespace())
+        .is_some_and(|c| c.is_uppercase());
+
+    if chars[dot] == '…' || word.is_empty() || word.ends_with('.') {
+        return next_is_upper;
+    }
+    if rules.is_abbreviation(word) {
+        return false;
+    }
+    let mut word_chars = word.chars();
+    if let (Some(first), None) = (word_chars.next(), word_chars.next())
+        && first.is_uppercase()
+    {
+        // `J. Smith`, `John F. Kennedy`, but not `so did I. Then`.
+        return !(next_is_upper && follows_name(&chars[..word_start]));
+    }
+    if is_initialism(word) {
+        return next_is_upper;
+    }
+    true
+}
+
+#[must_use]
+pub fn segment_sentences(text: &str, max_len: usize, rules: &SegmentRules) -> Vec<(usize, usize)> {
+    let mut sentences = Vec::new();
+    let chars: Vec<char> = text.chars().collect();
+    let len = chars.len();
+
+    if len == 0 {
+        return sentences;
+    }
+
+    let protected = rules.protected_chars(text, len);
+    let mut start = 0;
+
+    for i in 0..len {
+        let ch = chars[i];
+        if !is_terminator(ch) || protected[i] {
+            continue;
+        }
+        if i + 1 < len {
+            if !chars[i + 1].is_whitespace() {
+                continue;
+            }
+            if matches!(ch, '.' | '…') && !ends_sentence(&chars, i, rules) {
+                continue;
+            }
+        }
+
+        let end = i + 1;
+        let sentence_text: String = chars[start..end].iter().collect();
+        if !sentence_text.trim().is_empty() {
+            sentences.push((start, end));
+        }
+        start = end;
+    }
+
+    if start < len {
+        let sentence_text: String = chars[start..].iter().collect();
+        if !sentence_text.trim().is_empty() {
+            sentences.push((start, len));
+        }
+    }
+
+    let mut final_sentences = Vec::new();
+    for (start, end) in sentences {
+        let sentence_len = end - start;
+        if sentence_len <= max_len {
+            final_sentences.push((start, end));
+        } else {
+            let sentence_text: String = chars[start..end].iter().collect();
+            let sub_sentences = split_long_sentence(&sentence_text, start, max_len);
+            final_sentences.extend(sub_sentences);
+        }
+    }
+
+    final_sentences
+}
+
+fn split_long_sentence(text: &str, base_start: usize, max_len: usize) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+    let chars: Vec<char> = text.chars().collect();
+    let len = chars.len();
+
+    let mut start = 0;
+    let mut last_break = 0;
+
+    for i in 0..len {
+        let ch = chars[i];
+
+        if ch == ',' || ch == ';' || ch == ':' || ch.is_whitespace() {
+            last_break = i;
+        }
+
+        if i - start >= max_len && last_break > start {
+            let break_text: String = chars[start..last_break].iter().collect();
+            if !break_text.trim().is_empty() {
+                result.push((base_start + start, base_start + last_break));
+            }
+            start = last_break + 1;
+            last_break = start;
+        }
+    }
+
+    if start < len {
+        let remaining: String = chars[start..].iter().collect();
+        if !remaining.trim().is_empty() {
+            result.push((base_start + start, base_start + len));
+        }
+    }
+
+    result
+}
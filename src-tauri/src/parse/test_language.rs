@@ -0,0 +1,43 @@
+use super::language::*;
+
+#[test]
+fn test_detect_latin_languages() {
+    let cases = [
+        ("en", "The cat sat on the mat and it was happy with this."),
+        (
+            "de",
+            "Der Hund ist nicht mit dem Ball auf der Wiese, und das ist gut.",
+        ),
+        (
+            "es",
+            "El perro y los gatos están en la casa por la tarde con el niño.",
+        ),
+        (
+            "fr",
+            "Le chat est dans la maison et les enfants ne sont pas là.",
+        ),
+        (
+            "pt",
+            "O gato não está em casa com os meninos, e a porta é azul.",
+        ),
+    ];
+    for (expected, text) in cases {
+        assert_eq!(detect_language(text), Some(expected), "{text}");
+    }
+}
+
+#[test]
+fn test_detect_by_script() {
+    assert_eq!(detect_language("Привет, как дела? Это статья."), Some("ru"));
+    assert_eq!(detect_language("这是一篇关于编程的文章。"), Some("zh"));
+    assert_eq!(detect_language("هذه مقالة عن البرمجة."), Some("ar"));
+    assert_eq!(detect_language("यह प्रोग्रामिंग के बारे में लेख है।"), Some("hi"));
+    assert_eq!(detect_language("ಇದು ಒಂದು ಲೇಖನ."), Some("kn"));
+}
+
+#[test]
+fn test_detect_unknown() {
+    assert_eq!(detect_language(""), None);
+    assert_eq!(detect_language("1234 5678"), None);
+    assert_eq!(detect_language("xyzzy qwerty"), None);
+}
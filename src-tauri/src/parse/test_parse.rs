@@ -240,7 +240,7 @@ fn test_img_url_resolution() {
     );
     assert_eq!(
         output,
-        "<div> <p><img src=\"https://example.com/image.png\" alt=\"test\"></p> </div>"
+        "<div> <p><img src=\"https://example.com/image.png\" alt=\"test\" class=\"tts_para_0 tts_image\"></p> </div>"
     );
 }
 
@@ -282,7 +282,7 @@ fn test_heading_sentences() {
         has_class(&output, "tts_para_1"),
         "should have tts_para_1: {output}"
     );
-    assert_eq!(output, "<div> <h1><span class=\"tts_para_0\">Title here.</span><span class=\"tts_para_1\"> Subtitle here.</span></h1> </div>");
+    assert_eq!(output, "<div> <h1 id=\"tts_heading_0\"><span class=\"tts_para_0\">Title here.</span><span class=\"tts_para_1\"> Subtitle here.</span></h1> </div>");
 }
 
 #[test]
@@ -366,7 +366,7 @@ fn test_multiple_block_elements() {
         output.contains("Another paragraph."),
         "should contain another paragraph: {output}"
     );
-    assert_eq!(output, "<div> <h1 class=\"tts_para_0\">Title.</h1><p><span class=\"tts_para_1\">First paragraph.</span><span class=\"tts_para_2\"> Second sentence.</span></p><p class=\"tts_para_3\">Another paragraph.</p> </div>");
+    assert_eq!(output, "<div> <h1 class=\"tts_para_0\" id=\"tts_heading_0\">Title.</h1><p><span class=\"tts_para_1\">First paragraph.</span><span class=\"tts_para_2\"> Second sentence.</span></p><p class=\"tts_para_3\">Another paragraph.</p> </div>");
 }
 
 #[test]
@@ -464,7 +464,7 @@ fn test_preserves_html_structure() {
     );
     assert!(output.contains("<h1"), "should preserve h1: {output}");
     assert!(output.contains("<p"), "should preserve p: {output}");
-    assert_eq!(output, "<div> <article><h1 class=\"tts_para_0\">Title.</h1><p class=\"tts_para_1\">Content here.</p></article> </div>");
+    assert_eq!(output, "<div> <article><h1 class=\"tts_para_0\" id=\"tts_heading_0\">Title.</h1><p class=\"tts_para_1\">Content here.</p></article> </div>");
 }
 
 #[test]
@@ -536,7 +536,7 @@ fn test_bare_text_before_blocks() {
         output.contains("Paragraph."),
         "should contain paragraph: {output}"
     );
-    assert_eq!(output, "<div> <div><span class=\"tts_para_0\">Loose text.</span><h1 class=\"tts_para_1\">Title.</h1><p class=\"tts_para_2\">Paragraph.</p></div> </div>");
+    assert_eq!(output, "<div> <div><span class=\"tts_para_0\">Loose text.</span><h1 class=\"tts_para_1\" id=\"tts_heading_0\">Title.</h1><p class=\"tts_para_2\">Paragraph.</p></div> </div>");
 }
 
 #[test]
@@ -750,3 +750,169 @@ fn test_code_with_newline_and_inline_children_is_code_block() {
     );
     assert_eq!(output, "<div> <pre class=\"tts_code_block\"><span class=\"tts_para_0\">line1\n</span><span class=\"tts_para_1\">line2</span></pre> </div>");
 }
+
+#[test]
+fn test_process_article_paragraphs_and_kinds() {
+    let input = "<p>Hello there. Bye.</p><pre>let x = 1;\nlet y = 2;</pre>";
+    let article = process_article(input, "https://example.com", &SegmentRules::default());
+    assert_eq!(article.html, process_html_test(input));
+    assert_eq!(
+        article.paragraphs,
+        vec![
+            TtsParagraph {
+                text: "Hello there.".to_string(),
+                kind: BlockKind::Prose,
+                block: 0,
+                heading: None,
+                cell: None,
+                refs: vec![],
+            },
+            TtsParagraph {
+                text: "Bye.".to_string(),
+                kind: BlockKind::Prose,
+                block: 0,
+                heading: None,
+                cell: None,
+                refs: vec![],
+            },
+            TtsParagraph {
+                text: "let x = 1;".to_string(),
+                kind: BlockKind::Code,
+                block: 1,
+                heading: None,
+                cell: None,
+                refs: vec![],
+            },
+            TtsParagraph {
+                text: "let y = 2;".to_string(),
+                kind: BlockKind::Code,
+                block: 1,
+                heading: None,
+                cell: None,
+                refs: vec![],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_process_article_blocks_and_headings() {
+    let input = "<h2>Intro</h2><p>One. Two.</p><ul><li>Three</li><li>Four</li></ul>";
+    let article = process_article(input, "https://example.com", &SegmentRules::default());
+    let outline: Vec<(usize, Option<u8>)> = article
+        .paragraphs
+        .iter()
+        .map(|p| (p.block, p.heading))
+        .collect();
+    assert_eq!(
+        outline,
+        vec![(0, Some(2)), (1, None), (1, None), (2, None), (3, None)]
+    );
+}
+
+#[test]
+fn test_process_article_toc() {
+    let input = "<h1>Title</h1><p>Intro.</p><h2 id=\"usage\">Usage <em>notes</em>. More.</h2><p>Text.</p>";
+    let article = process_article(input, "https://example.com", &SegmentRules::default());
+    assert_eq!(
+        article.toc,
+        vec![
+            TocEntry {
+                level: 1,
+                text: "Title".to_string(),
+                para: 0,
+                anchor: "tts_heading_0".to_string(),
+            },
+            TocEntry {
+                level: 2,
+                text: "Usage notes. More.".to_string(),
+                para: 2,
+                anchor: "usage".to_string(),
+            },
+        ]
+    );
+    assert!(
+        article.html.contains("id=\"tts_heading_0\""),
+        "should add heading anchor: {}",
+        article.html
+    );
+}
+
+#[test]
+fn test_process_article_table_cells() {
+    let input = "<table><thead><tr><th>Name</th><th>Age</th></tr></thead>\
+                 <tbody><tr><td>Ann</td><td>31</td></tr></tbody></table>";
+    let article = process_article(input, "https://example.com", &SegmentRules::default());
+    let cells: Vec<(BlockKind, Option<TableCell>)> = article
+        .paragraphs
+        .iter()
+        .map(|p| (p.kind, p.cell))
+        .collect();
+    let cell = |row, column, header| {
+        (
+            BlockKind::Table,
+            Some(TableCell {
+                table: 0,
+                row,
+                column,
+                header,
+            }),
+        )
+    };
+    assert_eq!(
+        cells,
+        vec![
+            cell(0, 0, true),
+            cell(0, 1, true),
+            cell(1, 0, false),
+            cell(1, 1, false),
+        ]
+    );
+}
+
+#[test]
+fn test_process_article_images_and_refs() {
+    let input = "<figure><img src=\"/a.png\" alt=\"A cat\"><figcaption>A sleeping cat.</figcaption></figure>\
+                 <p>See <a href=\"/docs\">the docs</a> for details<sup><a href=\"#fn1\">1</a></sup>.</p>";
+    let article = process_article(input, "https://example.com", &SegmentRules::default());
+    assert_eq!(article.html, "<div> <figure><img src=\"https://example.com/a.png\" alt=\"A cat\" class=\"tts_para_0 tts_image\"><figcaption class=\"tts_para_1\">A sleeping cat.</figcaption></figure><p class=\"tts_para_2\">See <a href=\"https://example.com/docs\" class=\"tts_anchor\">the docs</a> for details<sup><a href=\"#fn1\">1</a></sup>.</p> </div>");
+    let units: Vec<(&str, BlockKind)> = article
+        .paragraphs
+        .iter()
+        .map(|p| (p.text.as_str(), p.kind))
+        .collect();
+    assert_eq!(
+        units,
+        vec![
+            ("A cat", BlockKind::Image),
+            ("A sleeping cat.", BlockKind::Caption),
+            ("See the docs for details1.", BlockKind::Prose),
+        ]
+    );
+    assert_eq!(
+        article.paragraphs[2].refs,
+        vec![
+            InlineRef {
+                kind: InlineKind::Link,
+                text: "the docs".to_string(),
+                start: 4,
+            },
+            InlineRef {
+                kind: InlineKind::Footnote,
+                text: "1".to_string(),
+                start: 24,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_decorative_image_is_not_a_unit() {
+    let input = "<p><img src=\"/a.png\" alt=\" \"></p>";
+    let article = process_article(input, "https://example.com", &SegmentRules::default());
+    assert_eq!(
+        article.html,
+        "<div> <p><img src=\"https://example.com/a.png\" alt=\" \"></p> </div>"
+    );
+    assert!(article.paragraphs.is_empty());
+}
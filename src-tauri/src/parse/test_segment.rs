@@ -0,0 +1,195 @@
+use super::segment::*;
+use crate::models::SegmentationRule;
+
+fn split(text: &str, rules: &SegmentRules) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    segment_sentences(text, 500, rules)
+        .into_iter()
+        .map(|(start, end)| chars[start..end].iter().collect::<String>())
+        .collect()
+}
+
+fn rule(language: &str, pattern: &str, is_regex: bool) -> SegmentationRule {
+    SegmentationRule {
+        language: language.to_string(),
+        pattern: pattern.to_string(),
+        is_regex,
+    }
+}
+
+#[test]
+fn test_three_letter_abbreviations_not_split() {
+    let rules = SegmentRules::for_language("en");
+    assert_eq!(
+        split(
+            "Apples, pears etc. are fruit. See Fig. 3 for approx. values.",
+            &rules
+        ),
+        vec![
+            "Apples, pears etc. are fruit.",
+            " See Fig. 3 for approx. values."
+        ]
+    );
+}
+
+#[test]
+fn test_titles_not_split() {
+    let rules = SegmentRules::for_language("en");
+    assert_eq!(
+        split("Dr. Smith met Mr. Jones. Then he left.", &rules),
+        vec!["Dr. Smith met Mr. Jones.", " Then he left."]
+    );
+}
+
+#[test]
+fn test_short_words_split() {
+    let rules = SegmentRules::for_language("en");
+    assert_eq!(
+        split("Let it go. Then move on.", &rules),
+        vec!["Let it go.", " Then move on."]
+    );
+}
+
+#[test]
+fn test_version_numbers_split() {
+    let rules = SegmentRules::for_language("en");
+    assert_eq!(
+        split("We upgraded to version 1.10. It is faster.", &rules),
+        vec!["We upgraded to version 1.10.", " It is faster."]
+    );
+}
+
+#[test]
+fn test_numbered_reference_not_split() {
+    let rules = SegmentRules::for_language("en");
+    assert_eq!(
+        split("See No. 5 and pp. 12 for details. Done.", &rules),
+        vec!["See No. 5 and pp. 12 for details.", " Done."]
+    );
+}
+
+#[test]
+fn test_ellipsis() {
+    let rules = SegmentRules::for_language("en");
+    assert_eq!(
+        split("Wait... and then. Well… Nothing happened.", &rules),
+        vec!["Wait... and then.", " Well…", " Nothing happened."]
+    );
+}
+
+#[test]
+fn test_initialisms() {
+    let rules = SegmentRules::for_language("en");
+    assert_eq!(
+        split(
+            "He went to the U.S.A. and stayed. He loved the U.S. Then he left.",
+            &rules
+        ),
+        vec![
+            "He went to the U.S.A. and stayed.",
+            " He loved the U.S.",
+            " Then he left."
+        ]
+    );
+}
+
+#[test]
+fn test_single_letter_initial_not_split() {
+    let rules = SegmentRules::for_language("en");
+    assert_eq!(
+        split("J. R. R. Tolkien wrote it. Read it.", &rules),
+        vec!["J. R. R. Tolkien wrote it.", " Read it."]
+    );
+}
+
+#[test]
+fn test_german_dictionary() {
+    let rules = SegmentRules::for_language("de-DE");
+    assert_eq!(
+        split(
+            "Obst, z.B. Äpfel, bzw. Birnen. Siehe Nr. 4. Fertig.",
+            &rules
+        ),
+        vec![
+            "Obst, z.B. Äpfel, bzw. Birnen.",
+            " Siehe Nr. 4.",
+            " Fertig."
+        ]
+    );
+}
+
+#[test]
+fn test_unknown_language_uses_common_entries() {
+    let rules = SegmentRules::for_language("xx");
+    assert_eq!(
+        split("Mr. Smith etc. Done.", &rules),
+        vec!["Mr.", " Smith etc. Done."]
+    );
+}
+
+#[test]
+fn test_user_abbreviation() {
+    let mut rules = SegmentRules::for_language("en");
+    rules.extend(
+        "en-GB",
+        &[
+            rule("en", "Corp.", false),
+            rule("", "Bros.", false),
+            rule("de", "Tel.", false),
+        ],
+    );
+    assert_eq!(
+        split("Warner Bros. made it. Call Tel. Now.", &rules),
+        vec!["Warner Bros. made it.", " Call Tel.", " Now."]
+    );
+}
+
+#[test]
+fn test_user_pattern() {
+    let mut rules = SegmentRules::for_language("en");
+    rules.extend("en", &[rule("", r"\bStep\.\s+\d", true)]);
+    assert_eq!(
+        split("Follow Step. 2 carefully. Done.", &rules),
+        vec!["Follow Step. 2 carefully.", " Done."]
+    );
+}
+
+#[test]
+fn test_other_terminators() {
+    let rules = SegmentRules::default();
+    assert_eq!(
+        split("Really?! Yes; sure. Ok", &rules),
+        vec!["Really?!", " Yes;", " sure.", " Ok"]
+    );
+}
+
+#[test]
+fn test_normalize_abbreviation() {
+    assert_eq!(normalize_abbreviation(" Fig. "), "fig");
+    assert_eq!(normalize_abbreviation("z.B."), "z.b");
+    assert_eq!(primary_language("pt_BR"), "pt");
+}
+
+#[test]
+fn test_rule_pattern() {
+    // Saving and deleting " Fig." both address the stored "fig".
+    assert_eq!(rule_pattern(" Fig.", false), "fig");
+    assert_eq!(rule_pattern(r"\bFig\.", true), r"\bFig\.");
+}
+
+#[test]
+fn test_single_letter_words_split() {
+    let rules = SegmentRules::for_language("en");
+    assert_eq!(
+        split("So did I. Then it rained.", &rules),
+        vec!["So did I.", " Then it rained."]
+    );
+    assert_eq!(
+        split("We went with plan A. Next came B.", &rules),
+        vec!["We went with plan A.", " Next came B."]
+    );
+    assert_eq!(
+        split("John F. Kennedy spoke. Then he left.", &rules),
+        vec!["John F. Kennedy spoke.", " Then he left."]
+    );
+}
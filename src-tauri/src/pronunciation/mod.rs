@@ -0,0 +1,335 @@
+use regex::{Captures, Regex, RegexBuilder};
+
+use crate::models::{BlockKind, PronunciationRule, RuleFiring, RuleMatch};
+use crate::parse::primary_language;
+
+// Pronunciation Rule Evaluation:
+//
+// 1. Rules run in ascending `priority`. Ties keep the historical order:
+//    plain rules before regex rules, then alphabetically by pattern.
+// 2. Disabled rules, rules that fail to compile and rules whose scope does
+//    not match the paragraph are skipped.
+// 3. Scope: `language` matches on the primary subtag, `domain` matches the
+//    article host and its subdomains, `scope` is `prose`, `code` or empty.
+// 4. Plain rules match literally and replace literally. `whole_word` adds
+//    word boundaries and `case_insensitive` ignores case for both kinds.
+// 5. Regex replacements are templates: `$1`, `${name}` and `$$`.
+//    `preserve_case` matches case insensitively and gives the replacement
+//    the case of the matched text (UPPER, Title or lower).
+// 6. A rule with a `phoneme` (IPA) emits `<phoneme>` SSML when the engine is
+//    built `with_ssml`, otherwise only the replacement is spoken.
+
+#[derive(Debug, Clone, Copy)]
+pub struct RuleContext<'a> {
+    pub language: &'a str,
+    pub domain: &'a str,
+    pub kind: BlockKind,
+}
+
+struct CompiledRule {
+    rule: PronunciationRule,
+    regex: Regex,
+}
+
+/// A phoneme hint collected while applying rules. Its position in the text
+/// is held by a private use character until the text is rendered.
+struct PhonemeHint {
+    text: String,
+    phoneme: String,
+}
+
+const HINT_BASE: u32 = 0xF_0000;
+const HINT_LIMIT: u32 = 0xFFFE;
+
+pub struct PronunciationEngine {
+    rules: Vec<CompiledRule>,
+    ssml: bool,
+}
+
+/// Compiles the matcher for a rule, honouring `whole_word` and
+/// `case_insensitive`. `preserve_case` implies case insensitive matching.
+pub fn compile_rule(rule: &PronunciationRule) -> Result<Regex, regex::Error> {
+    let pattern = if rule.is_regex {
+        rule.match_pattern.clone()
+    } else {
+        regex::escape(&rule.match_pattern)
+    };
+    let pattern = if rule.whole_word {
+        format!(r"\b(?:{pattern})\b")
+    } else {
+        pattern
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(rule.case_insensitive || rule.preserve_case)
+        .build()
+}
+
+/// Returns the host of `url` without a leading `www.`.
+#[must_use]
+pub fn domain_of(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|u| {
+            u.host_str()
+                .map(|h| h.trim_start_matches("www.").to_lowercase())
+        })
+        .unwrap_or_default()
+}
+
+fn matches_domain(rule_domain: &str, domain: &str) -> bool {
+    let rule_domain = rule_domain.trim().trim_start_matches("www.").to_lowercase();
+    rule_domain.is_empty() || domain == rule_domain || domain.ends_with(&format!(".{rule_domain}"))
+}
+
+fn matches_kind(scope: &str, kind: BlockKind) -> bool {
+    match scope {
+        "prose" => kind != BlockKind::Code,
+        "code" => kind == BlockKind::Code,
+        _ => true,
+    }
+}
+
+impl CompiledRule {
+    fn applies_to(&self, ctx: &RuleContext) -> bool {
+        let rule = &self.rule;
+        (rule.language.is_empty()
+            || primary_language(&rule.language) == primary_language(ctx.language))
+            && matches_domain(&rule.domain, ctx.domain)
+            && matches_kind(&rule.scope, ctx.kind)
+    }
+
+    /// The text spoken for one match: the expanded template, adjusted to
+    /// the case of the match when `preserve_case` is set.
+    fn spoken(&self, caps: &Captures) -> String {
+        let replacement = if self.rule.is_regex {
+            let mut replacement = String::new();
+            caps.expand(&self.rule.replacement, &mut replacement);
+            replacement
+        } else {
+            self.rule.replacement.clone()
+        };
+        if self.rule.preserve_case {
+            match_case(&caps[0], &replacement)
+        } else {
+            replacement
+        }
+    }
+
+    /// Replaces `spoken` with a hint marker when phoneme hints are collected.
+    fn emit(&self, spoken: String, hints: Option<&mut Vec<PhonemeHint>>) -> String {
+        match hints {
+            Some(hints) if !self.rule.phoneme.is_empty() => {
+                let Some(marker) = hint_marker(hints.len()) else {
+                    return spoken;
+                };
+                hints.push(PhonemeHint {
+                    text: spoken,
+                    phoneme: self.rule.phoneme.clone(),
+                });
+                marker.to_string()
+            }
+            _ => spoken,
+        }
+    }
+
+    fn apply(&self, text: &str, mut hints: Option<&mut Vec<PhonemeHint>>) -> String {
+        self.regex
+            .replace_all(text, |caps: &Captures| {
+                self.emit(self.spoken(caps), hints.as_deref_mut())
+            })
+            .into_owned()
+    }
+
+    /// Same as `apply`, but records every match. Returns `None` when the
+    /// rule does not match.
+    fn apply_traced(
+        &self,
+        text: &str,
+        mut hints: Option<&mut Vec<PhonemeHint>>,
+    ) -> Option<(String, Vec<RuleMatch>)> {
+        let mut matches = Vec::new();
+        let mut output = String::with_capacity(text.len());
+        let mut last = 0;
+        for caps in self.regex.captures_iter(text) {
+            let Some(m) = caps.get(0) else { continue };
+            let replacement = self.spoken(&caps);
+            output.push_str(&text[last..m.start()]);
+            output.push_str(&self.emit(replacement.clone(), hints.as_deref_mut()));
+            last = m.end();
+            matches.push(RuleMatch {
+                start: m.start(),
+                end: m.end(),
+                matched: m.as_str().to_string(),
+                replacement,
+            });
+        }
+        if matches.is_empty() {
+            return None;
+        }
+        output.push_str(&text[last..]);
+        Some((output, matches))
+    }
+}
+
+impl PronunciationEngine {
+    #[must_use]
+    pub fn new(rules: &[PronunciationRule]) -> Self {
+        let mut rules: Vec<CompiledRule> = rules
+            .iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| {
+                compile_rule(rule).ok().map(|regex| CompiledRule {
+                    rule: rule.clone(),
+                    regex,
+                })
+            })
+            .collect();
+        rules.sort_by(|a, b| {
+            (a.rule.priority, a.rule.is_regex, &a.rule.match_pattern).cmp(&(
+                b.rule.priority,
+                b.rule.is_regex,
+                &b.rule.match_pattern,
+            ))
+        });
+        Self { rules, ssml: false }
+    }
+
+    /// Emits SSML `<phoneme>` elements for rules with a phoneme hint. Only
+    /// enable this for TTS engines that parse SSML.
+    #[must_use]
+    pub fn with_ssml(mut self, ssml: bool) -> Self {
+        self.ssml = ssml;
+        self
+    }
+
+    #[must_use]
+    pub fn apply(&self, text: &str, ctx: &RuleContext) -> String {
+        let mut hints = Vec::new();
+        let mut result = text.to_string();
+        for rule in &self.rules {
+            if rule.applies_to(ctx) {
+                result = rule.apply(&result, self.ssml.then_some(&mut hints));
+            }
+        }
+        render_ssml(&result, &hints)
+    }
+
+    /// Applies the rules like `apply` and returns the rules that fired, in
+    /// evaluation order.
+    #[must_use]
+    pub fn trace(&self, text: &str, ctx: &RuleContext) -> (String, Vec<RuleFiring>) {
+        let mut hints = Vec::new();
+        let mut result = text.to_string();
+        let mut trace = Vec::new();
+        for rule in &self.rules {
+            if !rule.applies_to(ctx) {
+                continue;
+            }
+            if let Some((output, matches)) =
+                rule.apply_traced(&result, self.ssml.then_some(&mut hints))
+            {
+                trace.push(RuleFiring {
+                    match_pattern: rule.rule.match_pattern.clone(),
+                    is_regex: rule.rule.is_regex,
+                    priority: rule.rule.priority,
+                    matches,
+                    output: strip_hints(&output, &hints),
+                });
+                result = output;
+            }
+        }
+        (render_ssml(&result, &hints), trace)
+    }
+}
+
+/// Applies the case of `matched` to `replacement`: UPPER, Title or lower.
+/// Mixed case matches keep the replacement as written.
+#[must_use]
+pub fn match_case(matched: &str, replacement: &str) -> String {
+    let mut letters = matched.chars().filter(|c| c.is_alphabetic());
+    let Some(first) = letters.next() else {
+        return replacement.to_string();
+    };
+    let rest: Vec<char> = letters.collect();
+    let rest_lower = rest.iter().all(|c| !c.is_uppercase());
+    if first.is_uppercase() && !rest.is_empty() && rest.iter().all(|c| c.is_uppercase()) {
+        replacement.to_uppercase()
+    } else if first.is_uppercase() && rest_lower {
+        let mut chars = replacement.chars();
+        chars
+            .next()
+            .map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+    } else if first.is_lowercase() && rest_lower {
+        replacement.to_lowercase()
+    } else {
+        replacement.to_string()
+    }
+}
+
+fn hint_marker(index: usize) -> Option<char> {
+    u32::try_from(index)
+        .ok()
+        .filter(|&i| i < HINT_LIMIT)
+        .and_then(|i| char::from_u32(HINT_BASE + i))
+}
+
+fn hint_index(c: char) -> Option<usize> {
+    (c as u32)
+        .checked_sub(HINT_BASE)
+        .filter(|&i| i < HINT_LIMIT)
+        .and_then(|i| usize::try_from(i).ok())
+}
+
+fn escape_xml(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Replaces hint markers with the spoken text.
+fn strip_hints(text: &str, hints: &[PhonemeHint]) -> String {
+    if hints.is_empty() {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match hint_index(c).and_then(|i| hints.get(i)) {
+            Some(hint) => out.push_str(&hint.text),
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+/// Wraps the text in `<speak>` and turns hint markers into `<phoneme>`
+/// elements. Text without hints is returned unchanged.
+fn render_ssml(text: &str, hints: &[PhonemeHint]) -> String {
+    if hints.is_empty() {
+        return text.to_string();
+    }
+    let mut out = String::from("<speak>");
+    for c in text.chars() {
+        match hint_index(c).and_then(|i| hints.get(i)) {
+            Some(hint) => {
+                out.push_str("<phoneme alphabet=\"ipa\" ph=\"");
+                escape_xml(&hint.phoneme, &mut out);
+                out.push_str("\">");
+                escape_xml(&hint.text, &mut out);
+                out.push_str("</phoneme>");
+            }
+            None => escape_xml(&c.to_string(), &mut out),
+        }
+    }
+    out.push_str("</speak>");
+    out
+}
+
+#[cfg(test)]
+mod test_pronunciation;
@@ -0,0 +1,242 @@
+use super::*;
+
+fn rule(match_pattern: &str, replacement: &str) -> PronunciationRule {
+    PronunciationRule {
+        match_pattern: match_pattern.to_string(),
+        replacement: replacement.to_string(),
+        is_regex: false,
+        priority: 0,
+        enabled: true,
+        language: String::new(),
+        domain: String::new(),
+        scope: String::new(),
+        whole_word: false,
+        case_insensitive: false,
+        preserve_case: false,
+        phoneme: String::new(),
+    }
+}
+
+const PROSE: RuleContext = RuleContext {
+    language: "en",
+    domain: "blog.example.com",
+    kind: BlockKind::Prose,
+};
+
+#[test]
+fn test_default_order_plain_before_regex() {
+    let rules = vec![
+        PronunciationRule {
+            is_regex: true,
+            ..rule(r"b+", "c")
+        },
+        rule("a", "b"),
+    ];
+    let engine = PronunciationEngine::new(&rules);
+    assert_eq!(engine.apply("a", &PROSE), "c");
+}
+
+#[test]
+fn test_priority_overrides_default_order() {
+    let rules = vec![
+        PronunciationRule {
+            is_regex: true,
+            priority: -1,
+            ..rule(r"b+", "c")
+        },
+        rule("a", "b"),
+    ];
+    let engine = PronunciationEngine::new(&rules);
+    assert_eq!(engine.apply("ab", &PROSE), "bc");
+}
+
+#[test]
+fn test_disabled_rule_skipped() {
+    let rules = vec![PronunciationRule {
+        enabled: false,
+        ..rule("a", "b")
+    }];
+    let engine = PronunciationEngine::new(&rules);
+    assert_eq!(engine.apply("a", &PROSE), "a");
+}
+
+#[test]
+fn test_whole_word_and_case_insensitive() {
+    let rules = vec![PronunciationRule {
+        whole_word: true,
+        case_insensitive: true,
+        ..rule("cat", "dog")
+    }];
+    let engine = PronunciationEngine::new(&rules);
+    assert_eq!(
+        engine.apply("Cat concatenate CAT.", &PROSE),
+        "dog concatenate dog."
+    );
+}
+
+#[test]
+fn test_plain_replacement_is_literal() {
+    let rules = vec![rule("$x", "$1 dollars")];
+    let engine = PronunciationEngine::new(&rules);
+    assert_eq!(engine.apply("$x", &PROSE), "$1 dollars");
+}
+
+#[test]
+fn test_regex_capture_groups() {
+    let rules = vec![PronunciationRule {
+        is_regex: true,
+        ..rule(r"(\d+)px", "$1 pixels")
+    }];
+    let engine = PronunciationEngine::new(&rules);
+    assert_eq!(engine.apply("10px", &PROSE), "10 pixels");
+}
+
+#[test]
+fn test_language_scope() {
+    let rules = vec![PronunciationRule {
+        language: "de".to_string(),
+        ..rule("a", "b")
+    }];
+    let engine = PronunciationEngine::new(&rules);
+    assert_eq!(engine.apply("a", &PROSE), "a");
+    let german = RuleContext {
+        language: "de-AT",
+        ..PROSE
+    };
+    assert_eq!(engine.apply("a", &german), "b");
+}
+
+#[test]
+fn test_domain_scope() {
+    let rules = vec![
+        PronunciationRule {
+            domain: "example.com".to_string(),
+            ..rule("a", "b")
+        },
+        PronunciationRule {
+            domain: "other.org".to_string(),
+            ..rule("b", "c")
+        },
+    ];
+    let engine = PronunciationEngine::new(&rules);
+    assert_eq!(engine.apply("a", &PROSE), "b");
+    assert_eq!(domain_of("https://www.Example.com/post"), "example.com");
+}
+
+#[test]
+fn test_kind_scope() {
+    let rules = vec![
+        PronunciationRule {
+            scope: "code".to_string(),
+            ..rule("fn", "function")
+        },
+        PronunciationRule {
+            scope: "prose".to_string(),
+            ..rule("btw", "by the way")
+        },
+    ];
+    let engine = PronunciationEngine::new(&rules);
+    let code = RuleContext {
+        kind: BlockKind::Code,
+        ..PROSE
+    };
+    assert_eq!(engine.apply("fn btw", &PROSE), "fn by the way");
+    assert_eq!(engine.apply("fn btw", &code), "function btw");
+}
+
+#[test]
+fn test_legacy_json_is_accepted() {
+    let rules: Vec<PronunciationRule> =
+        serde_json::from_str(r#"[{"match_pattern":"a","replacement":"b","is_regex":false}]"#)
+            .unwrap();
+    assert_eq!(rules, vec![rule("a", "b")]);
+}
+
+#[test]
+fn test_trace_records_firing_order_and_matches() {
+    let rules = vec![
+        PronunciationRule {
+            is_regex: true,
+            ..rule(r"(\d+)px", "$1 pixels")
+        },
+        rule("CSS", "C S S"),
+        rule("unused", "never"),
+    ];
+    let engine = PronunciationEngine::new(&rules);
+    let (output, trace) = engine.trace("CSS: 10px or 2px", &PROSE);
+    assert_eq!(output, engine.apply("CSS: 10px or 2px", &PROSE));
+    assert_eq!(output, "C S S: 10 pixels or 2 pixels");
+    assert_eq!(trace.len(), 2);
+    assert_eq!(trace[0].match_pattern, "CSS");
+    assert_eq!(trace[0].output, "C S S: 10px or 2px");
+    assert_eq!(
+        trace[1].matches,
+        vec![
+            RuleMatch {
+                start: 7,
+                end: 11,
+                matched: "10px".to_string(),
+                replacement: "10 pixels".to_string(),
+            },
+            RuleMatch {
+                start: 15,
+                end: 18,
+                matched: "2px".to_string(),
+                replacement: "2 pixels".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_preserve_case() {
+    let rules = vec![PronunciationRule {
+        whole_word: true,
+        preserve_case: true,
+        ..rule("nginx", "engine x")
+    }];
+    let engine = PronunciationEngine::new(&rules);
+    assert_eq!(
+        engine.apply("nginx, Nginx and NGINX", &PROSE),
+        "engine x, Engine x and ENGINE X"
+    );
+    assert_eq!(match_case("NgInX", "engine x"), "engine x");
+    assert_eq!(match_case("42", "forty-two"), "forty-two");
+}
+
+#[test]
+fn test_named_capture_template() {
+    let rules = vec![PronunciationRule {
+        is_regex: true,
+        ..rule(
+            r"v(?<major>\d+)\.(?<minor>\d+)",
+            "version ${major} point ${minor}",
+        )
+    }];
+    let engine = PronunciationEngine::new(&rules);
+    assert_eq!(engine.apply("v2.1", &PROSE), "version 2 point 1");
+}
+
+#[test]
+fn test_phoneme_hints() {
+    let rules = vec![
+        PronunciationRule {
+            whole_word: true,
+            phoneme: "ˈɡɪf".to_string(),
+            ..rule("GIF", "gif")
+        },
+        rule("&", "and"),
+    ];
+    let text = "A GIF & <tag>";
+    let plain = PronunciationEngine::new(&rules);
+    assert_eq!(plain.apply(text, &PROSE), "A gif and <tag>");
+    let ssml = PronunciationEngine::new(&rules).with_ssml(true);
+    assert_eq!(
+        ssml.apply(text, &PROSE),
+        r#"<speak>A <phoneme alphabet="ipa" ph="ˈɡɪf">gif</phoneme> and &lt;tag&gt;</speak>"#
+    );
+    let (output, trace) = ssml.trace(text, &PROSE);
+    assert_eq!(output, ssml.apply(text, &PROSE));
+    assert_eq!(trace[1].output, "A gif and <tag>");
+    assert_eq!(ssml.apply("no hints here", &PROSE), "no hints here");
+}
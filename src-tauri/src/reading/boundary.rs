@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+// Word Boundaries:
+//
+// 1. Engines report the word being spoken as an offset into the utterance
+//    text, in UTF-16 code units like the DOM.
+// 2. The utterance text is the unit text after policies, normalization and
+//    pronunciation rules, so offsets are mapped back to the displayed unit
+//    text by word index. When the word counts differ, the index is scaled.
+// 3. Without boundary events nothing is reported and the viewer keeps
+//    highlighting the whole unit. The pinned tts plugin (0.1.11) emits
+//    none, so for now this is all the app shows.
+// 4. An utterance restarted mid-unit starts at the last reported word, so
+//    its offsets are shifted by the start of that word.
+// 5. SSML units (`<speak>...`, with phoneme hints) are mapped and timed by
+//    their plain text, without markup. They always restart from their
+//    start, since cutting them would break the markup.
+
+const SSML_START: &str = "<speak>";
+
+/// The characters an engine reads out of an SSML unit, with their UTF-16
+/// offsets in the markup.
+fn ssml_chars(ssml: &str) -> Vec<(usize, char)> {
+    let mut chars = Vec::new();
+    let mut offset = 0;
+    let mut tag = false;
+    let mut entity: Option<(usize, String)> = None;
+    for ch in ssml.chars() {
+        match (ch, tag, &mut entity) {
+            ('>', true, _) => tag = false,
+            (_, true, _) => {}
+            (';', false, Some((start, name))) => {
+                let decoded = match name.as_str() {
+                    "amp" => '&',
+                    "lt" => '<',
+                    "gt" => '>',
+                    "quot" => '"',
+                    "apos" => '\'',
+                    _ => ' ',
+                };
+                chars.push((*start, decoded));
+                entity = None;
+            }
+            (_, false, Some((_, name))) => name.push(ch),
+            ('<', false, None) => tag = true,
+            ('&', false, None) => entity = Some((offset, String::new())),
+            _ => chars.push((offset, ch)),
+        }
+        offset += ch.len_utf16();
+    }
+    chars
+}
+
+/// Whether the unit is SSML markup rather than plain text.
+fn is_ssml(spoken: &str) -> bool {
+    spoken.starts_with(SSML_START)
+}
+
+/// The text read out of a spoken unit: the unit itself, or the text of an
+/// SSML unit without its markup.
+#[must_use]
+pub fn plain_text(spoken: &str) -> Cow<'_, str> {
+    if is_ssml(spoken) {
+        Cow::Owned(ssml_chars(spoken).into_iter().map(|(_, ch)| ch).collect())
+    } else {
+        Cow::Borrowed(spoken)
+    }
+}
+
+/// Maps a UTF-16 offset into a spoken unit to its plain text.
+fn plain_offset(spoken: &str, char_index: usize) -> usize {
+    if !is_ssml(spoken) {
+        return char_index;
+    }
+    ssml_chars(spoken)
+        .into_iter()
+        .take_while(|&(offset, _)| offset < char_index)
+        .map(|(_, ch)| ch.len_utf16())
+        .sum()
+}
+
+/// A word of the displayed unit text, in UTF-16 offsets into the trimmed
+/// text.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct WordRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Whitespace-separated words with their UTF-16 ranges.
+fn words(text: &str) -> Vec<WordRange> {
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    let mut start = None;
+    for ch in text.chars() {
+        match (ch.is_whitespace(), start) {
+            (true, Some(word_start)) => {
+                ranges.push(WordRange {
+                    start: word_start,
+                    end: offset,
+                });
+                start = None;
+            }
+            (false, None) => start = Some(offset),
+            _ => {}
+        }
+        offset += ch.len_utf16();
+    }
+    if let Some(word_start) = start {
+        ranges.push(WordRange {
+            start: word_start,
+            end: offset,
+        });
+    }
+    ranges
+}
+
+/// Maps a boundary at `char_index` of the spoken text to a word of the
+/// displayed text.
+#[must_use]
+pub fn display_range(display: &str, spoken: &str, char_index: usize) -> Option<WordRange> {
+    let char_index = plain_offset(spoken, char_index);
+    let display = words(display.trim());
+    let spoken = words(plain_text(spoken).trim_end());
+    let last = display.len().checked_sub(1)?;
+    let index = spoken
+        .iter()
+        .position(|word| char_index < word.end)
+        .unwrap_or(spoken.len().saturating_sub(1));
+    let index = if spoken.len() == display.len() {
+        index
+    } else {
+        index * display.len() / spoken.len().max(1)
+    };
+    display.get(index.min(last)).copied()
+}
+
+/// Splits `text` at the start of the word containing UTF-16 `offset`.
+/// Returns the UTF-16 offset of that word and the text from there on. SSML
+/// units are never split.
+#[must_use]
+pub fn resume_at(text: &str, offset: usize) -> (usize, &str) {
+    if is_ssml(text) {
+        return (0, text);
+    }
+    let start = words(text)
+        .iter()
+        .rev()
+        .find(|word| word.start <= offset)
+        .map_or(0, |word| word.start);
+    let mut utf16 = 0;
+    let byte = text
+        .char_indices()
+        .find(|&(_, ch)| {
+            let reached = utf16 >= start;
+            utf16 += ch.len_utf16();
+            reached
+        })
+        .map_or(text.len(), |(byte, _)| byte);
+    (start, &text[byte..])
+}
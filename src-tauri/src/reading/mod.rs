@@ -0,0 +1,289 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::models::{TocEntry, TtsParagraph};
+
+mod boundary;
+mod player;
+mod policy;
+mod queue;
+mod speaker;
+
+pub use boundary::{WordRange, display_range, plain_text, resume_at};
+
+pub use player::{
+    ActiveSleepTimer, Effect, PauseReason, Playback, Player, SleepTimer, SleepTimerInfo,
+    Utterance,
+};
+
+pub use policy::{
+    BlockPolicies, CAPTION_POLICY, CODE_POLICY, CaptionPolicy, CodePolicy, FOOTNOTE_POLICY,
+    IMAGE_POLICY, LINK_POLICY, TABLE_POLICY, TablePolicy, apply_block_policies, is_spoken,
+};
+
+pub use queue::{QueueAdvance, queue_neighbour};
+
+pub use speaker::{
+    Host, Speaker, SpeakerState, SpeechBackend, SpeechEvent, SpeechHandler, SpeechVoice,
+};
+
+// Speak Bar Navigation:
+//
+// 1. Positions are `tts_para` indices. Every unit knows its block (the
+//    nearest block element) and whether it belongs to a heading.
+// 2. Forward skips go to the first unit of the next sentence, block or
+//    heading section. Backward skips go to the start of the current one,
+//    or to the previous one when already at its start.
+// 3. Time skips are estimated from the spoken text length and the rate,
+//    since utterances cannot be seeked; they land on unit boundaries.
+// 4. After a unit finishes, reading pauses before the next one: the
+//    heading pause after a heading, the block pause between blocks and the
+//    sentence pause otherwise.
+// 5. Units emptied by a block policy are never spoken; skips land on the
+//    nearest spoken unit in the skip direction.
+// 6. The media session shows the estimated time of the units before the
+//    position as elapsed time; seeking lands on the unit spoken at the
+//    requested time.
+
+/// Characters spoken per second at rate 1.0, used to estimate durations.
+pub const CHARS_PER_SECOND: f32 = 15.0;
+
+/// Seconds skipped by a time skip.
+pub const TIME_SKIP_SECS: f32 = 15.0;
+
+pub const SETTING_PREFIX: &str = "speech_";
+
+/// Pitch, volume, pauses and resuming, stored as `speech_*` settings. A
+/// voice profile replaces the pitch and volume.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeechOptions {
+    pub pitch: f32,
+    pub volume: f32,
+    pub sentence_pause: Duration,
+    pub heading_pause: Duration,
+    pub block_pause: Duration,
+    /// Resume when the audio focus returns after an interruption.
+    pub auto_resume: bool,
+}
+
+impl Default for SpeechOptions {
+    fn default() -> Self {
+        Self {
+            pitch: 1.0,
+            volume: 1.0,
+            sentence_pause: Duration::ZERO,
+            heading_pause: Duration::ZERO,
+            block_pause: Duration::ZERO,
+            auto_resume: true,
+        }
+    }
+}
+
+impl SpeechOptions {
+    /// Applies one setting. Pauses are in milliseconds. Unknown names and
+    /// invalid values are ignored.
+    pub fn apply_setting(&mut self, name: &str, value: &str) {
+        let name = name.strip_prefix(SETTING_PREFIX).unwrap_or(name);
+        match name {
+            "pitch" => {
+                if let Ok(pitch) = value.parse::<f32>() {
+                    self.pitch = pitch.clamp(0.5, 2.0);
+                }
+            }
+            "volume" => {
+                if let Ok(volume) = value.parse::<f32>() {
+                    self.volume = volume.clamp(0.0, 1.0);
+                }
+            }
+            "auto_resume" => {
+                if let Ok(auto_resume) = value.parse::<bool>() {
+                    self.auto_resume = auto_resume;
+                }
+            }
+            _ => {
+                let Ok(millis) = value.parse::<u64>() else {
+                    return;
+                };
+                let pause = Duration::from_millis(millis.min(MAX_PAUSE_MS));
+                match name {
+                    "pause_sentence" => self.sentence_pause = pause,
+                    "pause_heading" => self.heading_pause = pause,
+                    "pause_block" => self.block_pause = pause,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+const MAX_PAUSE_MS: u64 = 10_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipUnit {
+    Sentence,
+    Block,
+    Heading,
+    Time,
+}
+
+/// Estimated speaking time of `text` in seconds.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn estimate_secs(text: &str, rate: f32) -> f32 {
+    plain_text(text).chars().count() as f32 / (CHARS_PER_SECOND * rate.max(0.1))
+}
+
+fn block_start(paragraphs: &[TtsParagraph], pos: usize) -> usize {
+    let block = paragraphs[pos].block;
+    paragraphs[..pos]
+        .iter()
+        .rposition(|p| p.block != block)
+        .map_or(0, |i| i + 1)
+}
+
+fn is_section_start(paragraphs: &[TtsParagraph], pos: usize) -> bool {
+    paragraphs[pos].heading.is_some()
+        && (pos == 0 || paragraphs[pos - 1].block != paragraphs[pos].block)
+}
+
+fn section_start(paragraphs: &[TtsParagraph], pos: usize) -> usize {
+    (0..=pos)
+        .rev()
+        .find(|&i| is_section_start(paragraphs, i))
+        .unwrap_or(0)
+}
+
+/// Position reached by skipping one `unit` from `pos`. `spoken` is the text
+/// sent to the TTS engine, used for time estimates. Returns `None` when
+/// there is nothing to skip to.
+#[must_use]
+pub fn skip_target(
+    paragraphs: &[TtsParagraph],
+    spoken: &[String],
+    pos: usize,
+    unit: SkipUnit,
+    forward: bool,
+    rate: f32,
+) -> Option<usize> {
+    let len = paragraphs.len();
+    if pos >= len {
+        return None;
+    }
+    let target = match (unit, forward) {
+        (SkipUnit::Sentence, true) => Some(pos + 1).filter(|&next| next < len),
+        (SkipUnit::Sentence, false) => pos.checked_sub(1),
+        (SkipUnit::Block, true) => {
+            let block = paragraphs[pos].block;
+            (pos + 1..len).find(|&i| paragraphs[i].block != block)
+        }
+        (SkipUnit::Block, false) => {
+            let start = block_start(paragraphs, pos);
+            if start < pos {
+                Some(start)
+            } else {
+                start
+                    .checked_sub(1)
+                    .map(|prev| block_start(paragraphs, prev))
+            }
+        }
+        (SkipUnit::Heading, true) => (pos + 1..len).find(|&i| is_section_start(paragraphs, i)),
+        (SkipUnit::Heading, false) => {
+            let start = section_start(paragraphs, pos);
+            if start < pos {
+                Some(start)
+            } else {
+                start
+                    .checked_sub(1)
+                    .map(|prev| section_start(paragraphs, prev))
+            }
+        }
+        (SkipUnit::Time, true) => {
+            let mut elapsed = 0.0;
+            (pos..len).find(|&i| {
+                let reached = elapsed >= TIME_SKIP_SECS;
+                elapsed += spoken.get(i).map_or(0.0, |text| estimate_secs(text, rate));
+                reached
+            })
+        }
+        (SkipUnit::Time, false) => {
+            if pos == 0 {
+                return None;
+            }
+            let mut elapsed = 0.0;
+            (0..pos)
+                .rev()
+                .find(|&i| {
+                    elapsed += spoken.get(i).map_or(0.0, |text| estimate_secs(text, rate));
+                    elapsed >= TIME_SKIP_SECS
+                })
+                .or(Some(0))
+        }
+    }?;
+    if forward {
+        (target..len).find(|&i| is_spoken(spoken, i))
+    } else {
+        (0..=target).rev().find(|&i| is_spoken(spoken, i))
+    }
+}
+
+/// Estimated time to speak the units before `pos`, in seconds. With `pos`
+/// past the end, the estimated duration of the article.
+#[must_use]
+pub fn elapsed_secs(spoken: &[String], pos: usize, rate: f32) -> f32 {
+    spoken
+        .iter()
+        .take(pos)
+        .map(|text| estimate_secs(text, rate))
+        .sum()
+}
+
+/// The spoken unit being read `secs` into the article, or the last one
+/// when `secs` is past the end.
+#[must_use]
+pub fn seek_target(spoken: &[String], secs: f32, rate: f32) -> Option<usize> {
+    let mut elapsed = 0.0;
+    let pos = (0..spoken.len())
+        .find(|&i| {
+            elapsed += estimate_secs(&spoken[i], rate);
+            elapsed > secs
+        })
+        .unwrap_or(spoken.len().saturating_sub(1));
+    (pos..spoken.len())
+        .find(|&i| is_spoken(spoken, i))
+        .or_else(|| (0..pos).rev().find(|&i| is_spoken(spoken, i)))
+}
+
+/// The TOC entry whose section contains `pos`.
+#[must_use]
+pub fn current_section(toc: &[TocEntry], pos: usize) -> Option<&TocEntry> {
+    toc.iter().take_while(|entry| entry.para <= pos).last()
+}
+
+/// Pause after unit `pos` before the next one is spoken.
+#[must_use]
+pub fn pause_after(paragraphs: &[TtsParagraph], pos: usize, options: &SpeechOptions) -> Duration {
+    let (Some(current), Some(next)) = (paragraphs.get(pos), paragraphs.get(pos + 1)) else {
+        return Duration::ZERO;
+    };
+    if current.block == next.block {
+        options.sentence_pause
+    } else if current.heading.is_some() {
+        options.heading_pause
+    } else {
+        options.block_pause
+    }
+}
+
+#[cfg(test)]
+mod test_boundary;
+#[cfg(test)]
+mod test_player;
+#[cfg(test)]
+mod test_policy;
+#[cfg(test)]
+mod test_queue;
+#[cfg(test)]
+mod test_reading;
+#[cfg(test)]
+mod test_speaker;
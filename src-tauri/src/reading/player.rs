@@ -0,0 +1,627 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::{SpeechOptions, WordRange, display_range, is_spoken, pause_after, resume_at};
+use crate::models::{TtsParagraph, VoiceProfile};
+
+// Speak Bar Playback:
+//
+// 1. The player is a state machine over the spoken units of one article:
+//    `Idle` after loading, `Playing` while a unit or the pause after it
+//    runs, `Paused` when stopped mid-article and `Finished` once the last
+//    unit was read.
+// 2. Every utterance gets an id. The engine reports one finish, error or
+//    interruption per utterance, in order, so each report settles the
+//    oldest utterance in flight. Reports for an utterance that was replaced
+//    are stale and change nothing; so are reports when nothing is in
+//    flight. Engines may or may not report the utterances the player
+//    stops, so a stop sets them aside: the next errors and interruptions
+//    are taken as their late reports, and a finish shows that none are
+//    coming.
+// 3. Transitions return `Effect`s for the caller to carry out once the
+//    lock on the player is released.
+// 4. Rate and voice changes restart the current utterance from the last
+//    word the engine reported, or from the start of the unit, so they are
+//    heard at once. The position does not move.
+// 5. Playback that pauses by itself remembers why. After an interruption
+//    (a call, a navigation prompt) or unplugged headphones, playing again
+//    resumes from the last reported word; when the audio focus returns
+//    after an interruption, it resumes by itself if `auto_resume` is on.
+//    Pausing by hand forgets the reason.
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Playback {
+    #[default]
+    Idle,
+    Playing,
+    Paused,
+    Finished,
+}
+
+/// Why playback paused without being asked to.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseReason {
+    /// The engine failed to speak.
+    Error,
+    /// Another app took the audio focus.
+    Interrupted,
+    /// The audio output went away, like headphones being unplugged.
+    Unplugged,
+}
+
+/// When the sleep timer stops reading.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SleepTimer {
+    Minutes { minutes: u32 },
+    EndOfParagraph,
+    EndOfArticle,
+}
+
+/// Volume fades out over the last seconds of a `Minutes` timer. The TTS
+/// plugin takes the volume per utterance, so the fade is applied to each
+/// paragraph that starts inside this window.
+const SLEEP_FADE: Duration = Duration::from_secs(30);
+const SLEEP_MIN_VOLUME: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActiveSleepTimer {
+    pub timer: SleepTimer,
+    pub deadline: Option<Instant>,
+}
+
+impl ActiveSleepTimer {
+    #[must_use]
+    pub fn new(timer: SleepTimer) -> Self {
+        Self {
+            timer,
+            deadline: match timer {
+                SleepTimer::Minutes { minutes } => {
+                    Some(Instant::now() + Duration::from_secs(u64::from(minutes) * 60))
+                }
+                SleepTimer::EndOfParagraph | SleepTimer::EndOfArticle => None,
+            },
+        }
+    }
+
+    fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Volume for an utterance starting now.
+    fn volume(&self) -> f32 {
+        match self.remaining() {
+            Some(remaining) if remaining < SLEEP_FADE => {
+                let fraction = remaining.as_secs_f32() / SLEEP_FADE.as_secs_f32();
+                SLEEP_MIN_VOLUME + (1.0 - SLEEP_MIN_VOLUME) * fraction
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SleepTimerInfo {
+    pub timer: SleepTimer,
+    pub remaining_secs: Option<u64>,
+}
+
+/// One unit sent to the engine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Utterance {
+    pub id: u64,
+    pub position: usize,
+    pub text: String,
+    pub rate: f32,
+    pub voice_id: Option<String>,
+    pub pitch: f32,
+    pub volume: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    /// Speak the utterance, replacing whatever is being spoken.
+    Speak(Utterance),
+    Stop,
+    /// Call `Player::pause_elapsed` with `ticket` after `pause`.
+    Wait {
+        ticket: u64,
+        pause: Duration,
+    },
+    /// The position or the playback changed.
+    StateChanged,
+    /// The engine reached `word` of the unit at `position`.
+    Word {
+        position: usize,
+        word: WordRange,
+    },
+    /// The last unit was read. `continue_queue` is false when the sleep
+    /// timer ends reading with this article.
+    Finished {
+        continue_queue: bool,
+    },
+}
+
+#[derive(Debug)]
+pub struct Player {
+    units: Vec<String>,
+    outline: Vec<TtsParagraph>,
+    position: usize,
+    playback: Playback,
+    pause_reason: Option<PauseReason>,
+    rate: f32,
+    voice_id: Option<String>,
+    profile: Option<VoiceProfile>,
+    speech: SpeechOptions,
+    sleep_timer: Option<ActiveSleepTimer>,
+    word: Option<WordRange>,
+    /// UTF-16 offset into the current unit where the utterance starts.
+    offset: usize,
+    /// UTF-16 offset into the current unit of the last reported word.
+    reached: Option<usize>,
+    next_id: u64,
+    /// Utterance being spoken.
+    current: Option<u64>,
+    /// Utterances sent to the engine that were not reported yet, oldest
+    /// first.
+    in_flight: VecDeque<u64>,
+    /// Utterances in flight when the player stopped the engine, which may
+    /// never be reported.
+    stopped: VecDeque<u64>,
+    /// Ticket of the pause running after a unit.
+    waiting: Option<u64>,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            units: Vec::new(),
+            outline: Vec::new(),
+            position: 0,
+            playback: Playback::Idle,
+            pause_reason: None,
+            rate: 1.0,
+            voice_id: None,
+            profile: None,
+            speech: SpeechOptions::default(),
+            sleep_timer: None,
+            word: None,
+            offset: 0,
+            reached: None,
+            next_id: 0,
+            current: None,
+            in_flight: VecDeque::new(),
+            stopped: VecDeque::new(),
+            waiting: None,
+        }
+    }
+}
+
+impl Player {
+    /// Units as spoken, parallel to `outline`. Empty units are not read.
+    #[must_use]
+    pub fn units(&self) -> &[String] {
+        &self.units
+    }
+
+    #[must_use]
+    pub fn outline(&self) -> &[TtsParagraph] {
+        &self.outline
+    }
+
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    #[must_use]
+    pub fn playback(&self) -> Playback {
+        self.playback
+    }
+
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        self.playback == Playback::Playing
+    }
+
+    #[must_use]
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    #[must_use]
+    pub fn profile(&self) -> Option<&VoiceProfile> {
+        self.profile.as_ref()
+    }
+
+    #[must_use]
+    pub fn pause_reason(&self) -> Option<PauseReason> {
+        self.pause_reason
+    }
+
+    #[must_use]
+    pub fn word(&self) -> Option<WordRange> {
+        self.word
+    }
+
+    pub fn set_rate(&mut self, rate: f32) {
+        self.rate = rate;
+    }
+
+    pub fn set_voice_id(&mut self, voice_id: Option<String>) {
+        self.voice_id = voice_id;
+    }
+
+    pub fn set_profile(&mut self, profile: Option<VoiceProfile>) {
+        self.profile = profile;
+    }
+
+    pub fn set_speech(&mut self, speech: SpeechOptions) {
+        self.speech = speech;
+    }
+
+    /// Changes the rate, restarting the current utterance when it differs.
+    pub fn change_rate(&mut self, rate: f32) -> Vec<Effect> {
+        if (self.rate - rate).abs() < f32::EPSILON {
+            return Vec::new();
+        }
+        self.rate = rate;
+        self.restart()
+    }
+
+    /// Changes the voice, restarting the current utterance when the voice
+    /// it is spoken with changes. A voice profile's voice takes precedence.
+    pub fn change_voice_id(&mut self, voice_id: Option<String>) -> Vec<Effect> {
+        let before = self.voice();
+        self.voice_id = voice_id;
+        if self.voice() == before {
+            return Vec::new();
+        }
+        self.restart()
+    }
+
+    /// Speaks the current unit again with the current voice settings, from
+    /// the last reported word (SSML units from their start). Does nothing between utterances; the next one
+    /// uses the new settings anyway.
+    pub fn restart(&mut self) -> Vec<Effect> {
+        if self.current.is_none() {
+            return Vec::new();
+        }
+        let word = self.word;
+        let mut effects = self.halt();
+        self.word = word;
+        if let Some(reached) = self.reached {
+            self.offset = resume_at(&self.units[self.position], reached).0;
+        }
+        self.speak(&mut effects);
+        effects
+    }
+
+    /// Replaces the article, stopping the current one.
+    pub fn load(&mut self, units: Vec<String>, outline: Vec<TtsParagraph>) -> Vec<Effect> {
+        let effects = self.halt();
+        self.units = units;
+        self.outline = outline;
+        self.position = 0;
+        self.playback = Playback::Idle;
+        self.pause_reason = None;
+        self.reached = None;
+        effects
+    }
+
+    /// Unloads the article and cancels the sleep timer.
+    pub fn reset(&mut self) -> Vec<Effect> {
+        let effects = self.load(Vec::new(), Vec::new());
+        self.profile = None;
+        self.sleep_timer = None;
+        effects
+    }
+
+    /// Reads from `from`, or from the current position. After an
+    /// interruption, reading resumes from the last reported word.
+    pub fn play(&mut self, from: Option<usize>) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        if let Some(from) = from {
+            self.position = from;
+            self.advance(&mut effects);
+        } else {
+            self.resume(&mut effects);
+        }
+        effects
+    }
+
+    pub fn pause(&mut self) -> Vec<Effect> {
+        let mut effects = self.halt();
+        if self.playback == Playback::Playing {
+            self.playback = Playback::Paused;
+        }
+        self.pause_reason = None;
+        effects.push(Effect::StateChanged);
+        effects
+    }
+
+    /// Moves to `pos`, replacing the current utterance when playing.
+    pub fn skip_to(&mut self, pos: usize) -> Vec<Effect> {
+        self.position = pos;
+        self.reached = None;
+        if !self.is_playing() {
+            return Vec::new();
+        }
+        let mut effects = Vec::new();
+        self.advance(&mut effects);
+        effects
+    }
+
+    /// The engine finished an utterance.
+    pub fn utterance_finished(&mut self) -> Vec<Effect> {
+        let Some(id) = self.settle(false) else {
+            return Vec::new();
+        };
+        let pause = pause_after(&self.outline, self.position, &self.speech);
+        self.position += 1;
+        self.reached = None;
+        if self.take_sleep_timer(SleepTimer::EndOfParagraph) {
+            self.playback = Playback::Paused;
+            return vec![Effect::StateChanged];
+        }
+        let mut effects = Vec::new();
+        if pause.is_zero() {
+            self.advance(&mut effects);
+        } else {
+            self.waiting = Some(id);
+            effects.push(Effect::Wait { ticket: id, pause });
+        }
+        effects
+    }
+
+    /// The engine failed to speak an utterance.
+    pub fn utterance_failed(&mut self) -> Vec<Effect> {
+        self.paused_by(PauseReason::Error)
+    }
+
+    /// Something else than the player cut the utterance off.
+    pub fn utterance_interrupted(&mut self) -> Vec<Effect> {
+        self.paused_by(PauseReason::Interrupted)
+    }
+
+    /// The audio focus came back after an interruption.
+    pub fn focus_gained(&mut self) -> Vec<Effect> {
+        if self.playback != Playback::Paused
+            || self.pause_reason != Some(PauseReason::Interrupted)
+            || !self.speech.auto_resume
+        {
+            return Vec::new();
+        }
+        self.play(None)
+    }
+
+    /// Another app took the audio focus while the engine speaks on. Pauses
+    /// as if the utterance had been interrupted.
+    pub fn focus_lost(&mut self) -> Vec<Effect> {
+        self.halted_by(PauseReason::Interrupted)
+    }
+
+    /// The audio output went away. Pauses instead of reading on through the
+    /// speaker.
+    pub fn output_lost(&mut self) -> Vec<Effect> {
+        self.halted_by(PauseReason::Unplugged)
+    }
+
+    /// The engine rejected an utterance before speaking it.
+    pub fn speak_failed(&mut self, id: u64) -> Vec<Effect> {
+        self.in_flight.retain(|&in_flight| in_flight != id);
+        if self.current != Some(id) {
+            return Vec::new();
+        }
+        self.current = None;
+        self.word = None;
+        self.playback = Playback::Paused;
+        self.pause_reason = Some(PauseReason::Error);
+        vec![Effect::StateChanged]
+    }
+
+    /// The pause after a unit is over.
+    pub fn pause_elapsed(&mut self, ticket: u64) -> Vec<Effect> {
+        if self.waiting != Some(ticket) || !self.is_playing() {
+            return Vec::new();
+        }
+        let mut effects = Vec::new();
+        self.advance(&mut effects);
+        effects
+    }
+
+    /// Maps a boundary of the current utterance to the displayed word.
+    pub fn boundary(&mut self, char_index: usize) -> Vec<Effect> {
+        if self.current.is_none() {
+            return Vec::new();
+        }
+        let (Some(display), Some(spoken)) = (
+            self.outline.get(self.position),
+            self.units.get(self.position),
+        ) else {
+            return Vec::new();
+        };
+        let reached = self.offset + char_index;
+        let Some(word) = display_range(&display.text, spoken, reached) else {
+            return Vec::new();
+        };
+        self.reached = Some(reached);
+        self.word = Some(word);
+        vec![Effect::Word {
+            position: self.position,
+            word,
+        }]
+    }
+
+    pub fn set_sleep_timer(&mut self, timer: Option<ActiveSleepTimer>) {
+        self.sleep_timer = timer;
+    }
+
+    #[must_use]
+    pub fn sleep_timer_info(&self) -> Option<SleepTimerInfo> {
+        let timer = self.sleep_timer?;
+        Some(SleepTimerInfo {
+            timer: timer.timer,
+            remaining_secs: timer.remaining().map(|remaining| remaining.as_secs()),
+        })
+    }
+
+    /// Pauses when the `Minutes` timer with `deadline` is still the active
+    /// one.
+    pub fn sleep_timer_expired(&mut self, deadline: Instant) -> Vec<Effect> {
+        if self
+            .sleep_timer
+            .is_none_or(|active| active.deadline != Some(deadline))
+        {
+            return Vec::new();
+        }
+        self.sleep_timer = None;
+        self.pause()
+    }
+
+    /// Clears the sleep timer if it is of the given kind and reports whether
+    /// it was.
+    fn take_sleep_timer(&mut self, kind: SleepTimer) -> bool {
+        if self.sleep_timer.is_some_and(|active| active.timer == kind) {
+            self.sleep_timer = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Settles a report that cut the current utterance off.
+    fn paused_by(&mut self, reason: PauseReason) -> Vec<Effect> {
+        if self.settle(true).is_none() {
+            return Vec::new();
+        }
+        self.playback = Playback::Paused;
+        self.pause_reason = Some(reason);
+        vec![Effect::StateChanged]
+    }
+
+    /// Stops reading for `reason`, when playing.
+    fn halted_by(&mut self, reason: PauseReason) -> Vec<Effect> {
+        if !self.is_playing() {
+            return Vec::new();
+        }
+        let mut effects = self.halt();
+        self.playback = Playback::Paused;
+        self.pause_reason = Some(reason);
+        effects.push(Effect::StateChanged);
+        effects
+    }
+
+    /// Stops the current utterance and any pending pause.
+    fn halt(&mut self) -> Vec<Effect> {
+        self.waiting = None;
+        self.word = None;
+        match self.current.take() {
+            Some(_) => {
+                self.stopped.extend(self.in_flight.drain(..));
+                vec![Effect::Stop]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Settles the oldest utterance in flight and returns its id when it is
+    /// the current one. A report of an utterance `cut_off` goes to the
+    /// stopped utterances first.
+    fn settle(&mut self, cut_off: bool) -> Option<u64> {
+        if cut_off && self.stopped.pop_front().is_some() {
+            return None;
+        }
+        self.stopped.clear();
+        let id = self.in_flight.pop_front()?;
+        if self.current != Some(id) {
+            return None;
+        }
+        self.current = None;
+        self.word = None;
+        Some(id)
+    }
+
+    /// Speaks the first spoken unit at or after the position, or finishes
+    /// the article.
+    fn advance(&mut self, effects: &mut Vec<Effect>) {
+        self.waiting = None;
+        self.word = None;
+        self.pause_reason = None;
+        self.position = (self.position..self.units.len())
+            .find(|&i| is_spoken(&self.units, i))
+            .unwrap_or(self.units.len());
+        if self.position >= self.units.len() {
+            if self.current.take().is_some() {
+                effects.push(Effect::Stop);
+            }
+            self.playback = Playback::Finished;
+            let continue_queue = !self.take_sleep_timer(SleepTimer::EndOfArticle);
+            effects.push(Effect::StateChanged);
+            effects.push(Effect::Finished { continue_queue });
+            return;
+        }
+        self.playback = Playback::Playing;
+        self.offset = 0;
+        self.reached = None;
+        effects.push(Effect::StateChanged);
+        self.speak(effects);
+    }
+
+    /// Continues reading at the position, from the last reported word when
+    /// playback was cut off there.
+    fn resume(&mut self, effects: &mut Vec<Effect>) {
+        let cut_off = self.playback == Playback::Paused
+            && matches!(
+                self.pause_reason,
+                Some(PauseReason::Interrupted | PauseReason::Unplugged)
+            );
+        let Some(reached) = self.reached.filter(|_| cut_off) else {
+            self.advance(effects);
+            return;
+        };
+        self.offset = resume_at(&self.units[self.position], reached).0;
+        self.waiting = None;
+        self.playback = Playback::Playing;
+        self.pause_reason = None;
+        effects.push(Effect::StateChanged);
+        self.speak(effects);
+    }
+
+    /// Sends the current unit from `offset` to the engine.
+    fn speak(&mut self, effects: &mut Vec<Effect>) {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.current = Some(id);
+        self.in_flight.push_back(id);
+        effects.push(Effect::Speak(self.utterance(id)));
+    }
+
+    fn voice(&self) -> Option<String> {
+        self.profile
+            .as_ref()
+            .and_then(|p| p.voice_id.clone())
+            .or_else(|| self.voice_id.clone())
+    }
+
+    fn utterance(&self, id: u64) -> Utterance {
+        let profile = self.profile.as_ref();
+        let volume = profile.map_or(self.speech.volume, |p| p.volume)
+            * self.sleep_timer.map_or(1.0, |timer| timer.volume());
+        Utterance {
+            id,
+            position: self.position,
+            text: resume_at(&self.units[self.position], self.offset)
+                .1
+                .to_string(),
+            rate: self.rate,
+            voice_id: self.voice(),
+            pitch: profile.map_or(self.speech.pitch, |p| p.pitch),
+            volume,
+        }
+    }
+}
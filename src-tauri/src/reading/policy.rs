@@ -0,0 +1,378 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::{BlockKind, InlineKind, TtsParagraph};
+use crate::parse::primary_language;
+
+// Block Reading Policies:
+//
+// Applied to the TTS paragraphs before normalization and pronunciation
+// rules. Units that are not read get an empty text, so positions keep
+// matching the `tts_para_N` spans and the speak bar steps over them.
+//
+// 1. Code blocks (consecutive code units of one block) are read, skipped,
+//    announced with their line count, or reduced to their first line.
+// 2. Tables are read cell by cell, skipped, announced with their row
+//    count, or read row by row as "column: value" using the header row.
+// 3. Image alt texts are read as "Image: …" when enabled and skipped
+//    otherwise. Figure captions are read, skipped, or announced the same
+//    way as images.
+// 4. Links and footnote references are inline: when enabled, "link" is
+//    said before the link text and footnote numbers are read as "footnote
+//    N". They are applied first, so table rows include them.
+// 5. Announcements use the phrases of the reading language, English when
+//    the language has none.
+
+pub const CODE_POLICY: &str = "readingCode";
+pub const TABLE_POLICY: &str = "readingTable";
+pub const IMAGE_POLICY: &str = "readingImages";
+pub const CAPTION_POLICY: &str = "readingCaptions";
+pub const LINK_POLICY: &str = "readingLinks";
+pub const FOOTNOTE_POLICY: &str = "readingFootnotes";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CodePolicy {
+    #[default]
+    Read,
+    Skip,
+    Announce,
+    FirstLine,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TablePolicy {
+    #[default]
+    Read,
+    Skip,
+    Announce,
+    Rows,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionPolicy {
+    #[default]
+    Read,
+    Skip,
+    Announce,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct BlockPolicies {
+    pub code: CodePolicy,
+    pub table: TablePolicy,
+    pub caption: CaptionPolicy,
+    pub images: bool,
+    pub links: bool,
+    pub footnotes: bool,
+}
+
+impl BlockPolicies {
+    /// Applies one setting. Unknown names and values are ignored.
+    pub fn apply_setting(&mut self, name: &str, value: &str) {
+        let value = serde_json::Value::String(value.to_string());
+        match name {
+            CODE_POLICY => {
+                if let Ok(code) = serde_json::from_value(value) {
+                    self.code = code;
+                }
+            }
+            TABLE_POLICY => {
+                if let Ok(table) = serde_json::from_value(value) {
+                    self.table = table;
+                }
+            }
+            CAPTION_POLICY => {
+                if let Ok(caption) = serde_json::from_value(value) {
+                    self.caption = caption;
+                }
+            }
+            IMAGE_POLICY => self.images = value == "true",
+            LINK_POLICY => self.links = value == "true",
+            FOOTNOTE_POLICY => self.footnotes = value == "true",
+            _ => {}
+        }
+    }
+}
+
+/// Spoken phrases, `{n}` is replaced by a count and `{text}` by the text
+/// being announced.
+pub struct Phrases {
+    pub code_skipped: &'static str,
+    pub table_skipped: &'static str,
+    pub image: &'static str,
+    pub link: &'static str,
+    pub footnote: &'static str,
+}
+
+const EN: Phrases = Phrases {
+    code_skipped: "Code block with {n} lines skipped.",
+    table_skipped: "Table with {n} rows skipped.",
+    image: "Image: {text}",
+    link: "link, {text}",
+    footnote: "footnote {text}",
+};
+
+const DE: Phrases = Phrases {
+    code_skipped: "Codeblock mit {n} Zeilen übersprungen.",
+    table_skipped: "Tabelle mit {n} Zeilen übersprungen.",
+    image: "Bild: {text}",
+    link: "Link, {text}",
+    footnote: "Fußnote {text}",
+};
+
+const FR: Phrases = Phrases {
+    code_skipped: "Bloc de code de {n} lignes ignoré.",
+    table_skipped: "Tableau de {n} lignes ignoré.",
+    image: "Image : {text}",
+    link: "lien, {text}",
+    footnote: "note {text}",
+};
+
+const ES: Phrases = Phrases {
+    code_skipped: "Bloque de código de {n} líneas omitido.",
+    table_skipped: "Tabla de {n} filas omitida.",
+    image: "Imagen: {text}",
+    link: "enlace, {text}",
+    footnote: "nota {text}",
+};
+
+const PT: Phrases = Phrases {
+    code_skipped: "Bloco de código com {n} linhas ignorado.",
+    table_skipped: "Tabela com {n} linhas ignorada.",
+    image: "Imagem: {text}",
+    link: "link, {text}",
+    footnote: "nota {text}",
+};
+
+#[must_use]
+pub fn phrases(language: &str) -> &'static Phrases {
+    match primary_language(language).as_str() {
+        "de" => &DE,
+        "fr" => &FR,
+        "es" => &ES,
+        "pt" => &PT,
+        _ => &EN,
+    }
+}
+
+fn phrase(template: &str, n: usize) -> String {
+    template.replace("{n}", &n.to_string())
+}
+
+fn announce(template: &str, text: &str) -> String {
+    template.replace("{text}", text)
+}
+
+/// Index ranges of consecutive units that belong to the same group, as
+/// decided by `key`. Units without a key are not grouped.
+fn groups<K: PartialEq>(
+    paragraphs: &[TtsParagraph],
+    key: impl Fn(&TtsParagraph) -> Option<K>,
+) -> Vec<std::ops::Range<usize>> {
+    let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
+    let mut last: Option<K> = None;
+    for (i, paragraph) in paragraphs.iter().enumerate() {
+        let current = key(paragraph);
+        match (&current, ranges.last_mut()) {
+            (Some(_), Some(range)) if current == last && range.end == i => range.end = i + 1,
+            (Some(_), _) => ranges.push(i..i + 1),
+            (None, _) => {}
+        }
+        last = current;
+    }
+    ranges
+}
+
+fn apply_code_policy(paragraphs: &mut [TtsParagraph], policy: CodePolicy, phrases: &Phrases) {
+    if policy == CodePolicy::Read {
+        return;
+    }
+    let blocks = groups(paragraphs, |p| {
+        (p.kind == BlockKind::Code).then_some(p.block)
+    });
+    for range in blocks {
+        let lines = range.len();
+        let mut units = paragraphs[range].iter_mut();
+        if let Some(first) = units.next() {
+            match policy {
+                CodePolicy::Read | CodePolicy::FirstLine => {}
+                CodePolicy::Skip => first.text.clear(),
+                CodePolicy::Announce => first.text = phrase(phrases.code_skipped, lines),
+            }
+        }
+        for unit in units {
+            unit.text.clear();
+        }
+    }
+}
+
+/// Reads each body row as one unit, "header: value" per cell.
+fn read_rows(table: &mut [TtsParagraph]) {
+    let mut headers: Vec<(usize, String)> = Vec::new();
+    for unit in table.iter().filter(|p| p.cell.is_some_and(|c| c.header)) {
+        let Some(cell) = unit.cell else { continue };
+        match headers
+            .iter_mut()
+            .find(|(column, _)| *column == cell.column)
+        {
+            Some((_, text)) => {
+                text.push(' ');
+                text.push_str(&unit.text);
+            }
+            None => headers.push((cell.column, unit.text.clone())),
+        }
+    }
+    let rows = groups(table, |p| p.cell.filter(|c| !c.header).map(|c| c.row));
+    for range in rows {
+        let mut cells: Vec<(usize, String)> = Vec::new();
+        for unit in &table[range.clone()] {
+            let Some(cell) = unit.cell else { continue };
+            match cells.last_mut() {
+                Some((column, text)) if *column == cell.column => {
+                    text.push(' ');
+                    text.push_str(&unit.text);
+                }
+                _ => cells.push((cell.column, unit.text.clone())),
+            }
+        }
+        let row: Vec<String> = cells
+            .into_iter()
+            .map(|(column, value)| {
+                let value = value.trim_end_matches('.').to_string();
+                match headers.iter().find(|(c, _)| *c == column) {
+                    Some((_, header)) => format!("{}: {value}", header.trim_end_matches('.')),
+                    None => value,
+                }
+            })
+            .collect();
+        table[range.start].text = format!("{}.", row.join(". "));
+        for unit in &mut table[range.start + 1..range.end] {
+            unit.text.clear();
+        }
+    }
+    for unit in table
+        .iter_mut()
+        .filter(|p| p.cell.is_some_and(|c| c.header))
+    {
+        unit.text.clear();
+    }
+}
+
+/// Rewrites the links and footnote references of one unit. References
+/// whose offset no longer matches the text are left alone.
+fn apply_inline_policies(paragraph: &mut TtsParagraph, policies: BlockPolicies, phrases: &Phrases) {
+    if !policies.links && !policies.footnotes || paragraph.refs.is_empty() {
+        return;
+    }
+    let mut refs = paragraph.refs.clone();
+    refs.sort_by_key(|r| r.start);
+    let source = &paragraph.text;
+    let mut text = String::new();
+    let mut cursor = 0;
+    for inline in refs {
+        let end = inline.start + inline.text.len();
+        if inline.start < cursor || source.get(inline.start..end) != Some(inline.text.as_str()) {
+            continue;
+        }
+        let spoken = match inline.kind {
+            InlineKind::Link if policies.links => announce(phrases.link, &inline.text),
+            InlineKind::Footnote if policies.footnotes => {
+                announce(phrases.footnote, inline.text.trim_matches(['[', ']']))
+            }
+            _ => continue,
+        };
+        text.push_str(&source[cursor..inline.start]);
+        if inline.kind == InlineKind::Footnote
+            && !text.is_empty()
+            && !text.ends_with(char::is_whitespace)
+        {
+            text.push(' ');
+        }
+        text.push_str(&spoken);
+        cursor = end;
+    }
+    text.push_str(&source[cursor..]);
+    paragraph.text = text;
+}
+
+fn apply_image_policies(
+    paragraphs: &mut [TtsParagraph],
+    policies: BlockPolicies,
+    phrases: &Phrases,
+) {
+    for image in paragraphs.iter_mut().filter(|p| p.kind == BlockKind::Image) {
+        image.text = if policies.images {
+            announce(phrases.image, &image.text)
+        } else {
+            String::new()
+        };
+    }
+    if policies.caption == CaptionPolicy::Read {
+        return;
+    }
+    let captions = groups(paragraphs, |p| {
+        (p.kind == BlockKind::Caption).then_some(p.block)
+    });
+    for range in captions {
+        let caption = &mut paragraphs[range];
+        match policies.caption {
+            CaptionPolicy::Read => {}
+            CaptionPolicy::Skip => clear(caption),
+            CaptionPolicy::Announce => caption[0].text = announce(phrases.image, &caption[0].text),
+        }
+    }
+}
+
+fn clear(units: &mut [TtsParagraph]) {
+    for unit in units {
+        unit.text.clear();
+    }
+}
+
+fn apply_table_policy(paragraphs: &mut [TtsParagraph], policy: TablePolicy, phrases: &Phrases) {
+    if policy == TablePolicy::Read {
+        return;
+    }
+    let tables = groups(paragraphs, |p| p.cell.map(|c| c.table));
+    for range in tables {
+        let table = &mut paragraphs[range];
+        match policy {
+            TablePolicy::Read => {}
+            TablePolicy::Skip => clear(table),
+            TablePolicy::Announce => {
+                let rows = groups(table, |p| p.cell.filter(|c| !c.header).map(|c| c.row)).len();
+                clear(table);
+                table[0].text = phrase(phrases.table_skipped, rows);
+            }
+            TablePolicy::Rows => read_rows(table),
+        }
+    }
+}
+
+/// Applies the block policies. Returns paragraphs in the same order; units
+/// that are not read have an empty text.
+#[must_use]
+pub fn apply_block_policies(
+    paragraphs: &[TtsParagraph],
+    policies: &BlockPolicies,
+    language: &str,
+) -> Vec<TtsParagraph> {
+    let phrases = phrases(language);
+    let mut planned = paragraphs.to_vec();
+    for paragraph in &mut planned {
+        apply_inline_policies(paragraph, *policies, phrases);
+    }
+    apply_image_policies(&mut planned, *policies, phrases);
+    apply_code_policy(&mut planned, policies.code, phrases);
+    apply_table_policy(&mut planned, policies.table, phrases);
+    planned
+}
+
+/// Whether the unit at `pos` is spoken.
+#[must_use]
+pub fn is_spoken(spoken: &[String], pos: usize) -> bool {
+    spoken.get(pos).is_some_and(|text| !text.trim().is_empty())
+}
@@ -0,0 +1,39 @@
+/// The queued article `offset` places from `current`. When `current` is not
+/// queued, moving forward starts at the head of the queue.
+#[must_use]
+pub fn queue_neighbour(queue: &[i32], current: Option<i32>, offset: isize) -> Option<i32> {
+    match current.and_then(|current| queue.iter().position(|&id| id == current)) {
+        Some(index) => index
+            .checked_add_signed(offset)
+            .and_then(|index| queue.get(index))
+            .copied(),
+        None if offset > 0 => queue.first().copied(),
+        None => None,
+    }
+}
+
+/// What follows an article read to the end. It leaves the queue either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueAdvance {
+    /// The article to read next.
+    pub next: Option<i32>,
+    /// Whether the finished article is deleted (auto delete).
+    pub delete: bool,
+}
+
+impl QueueAdvance {
+    #[must_use]
+    pub fn after(queue: &[i32], current: i32, auto_delete: bool) -> Self {
+        Self {
+            next: queue_neighbour(queue, Some(current), 1),
+            delete: auto_delete,
+        }
+    }
+
+    /// Whether the speak bar lets go of the finished article: it was
+    /// `deleted` and there is nothing to move on to.
+    #[must_use]
+    pub fn unloads(&self, deleted: bool) -> bool {
+        deleted && self.next.is_none()
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use super::{Effect, Player, Utterance};
+
+/// What the engine reports about the utterance it is speaking and the
+/// audio output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeechEvent {
+    Finished,
+    Error,
+    /// Another app took the audio focus and cut the utterance off.
+    Interrupted,
+    /// Another app took the audio focus; the utterance goes on unless
+    /// stopped.
+    FocusLost,
+    /// The audio focus came back.
+    FocusGained,
+    /// The audio output went away, like headphones being unplugged.
+    OutputLost,
+    /// The engine reached the word starting at `char_index` of the spoken
+    /// text.
+    Boundary {
+        char_index: usize,
+    },
+}
+
+/// A voice offered by the engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpeechVoice {
+    pub id: String,
+    pub name: String,
+    pub language: String,
+}
+
+pub type SpeechHandler = Arc<dyn Fn(SpeechEvent) + Send + Sync>;
+
+/// A text-to-speech engine speaking one utterance at a time. A new
+/// utterance replaces the one being spoken.
+pub trait SpeechBackend: Send + Sync {
+    fn speak(&self, utterance: &Utterance) -> Result<(), String>;
+
+    fn stop(&self) -> Result<(), String>;
+
+    fn voices(&self) -> Result<Vec<SpeechVoice>, String>;
+
+    /// Registers `handler` for every engine event. Returns the listener ids
+    /// to pass to `unlisten`.
+    fn listen(&self, handler: SpeechHandler) -> Vec<u32>;
+
+    fn unlisten(&self, id: u32);
+}
+
+/// The player and the article it reads, behind the single lock of a
+/// `Speaker`.
+#[derive(Default)]
+pub struct SpeakerState<A> {
+    pub player: Player,
+    pub article: A,
+    listener_ids: Vec<u32>,
+}
+
+/// Carries out the effects the speaker leaves to the app: pauses, state
+/// changes, words and finished articles.
+pub type Host<B, A> = Box<dyn Fn(&Speaker<B, A>, Effect) -> Result<(), String> + Send + Sync>;
+
+/// Drives a `Player` with a speech backend. Utterances and stops go to the
+/// backend, everything else to the host.
+pub struct Speaker<B, A> {
+    backend: B,
+    host: Host<B, A>,
+    state: Mutex<SpeakerState<A>>,
+}
+
+impl<B: SpeechBackend, A: Default> Speaker<B, A> {
+    pub fn new(backend: B, host: Host<B, A>) -> Self {
+        Self {
+            backend,
+            host,
+            state: Mutex::new(SpeakerState::default()),
+        }
+    }
+
+    #[must_use]
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Locks the player. Player transitions do not panic halfway, so a
+    /// poisoned lock still holds a usable state.
+    pub fn lock(&self) -> MutexGuard<'_, SpeakerState<A>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Carries out the effects of a player transition. Must be called
+    /// without holding the lock. Every effect is carried out; the last
+    /// error is returned.
+    pub fn run(&self, effects: Vec<Effect>) -> Result<(), String> {
+        let mut result = Ok(());
+        for effect in effects {
+            let outcome = match effect {
+                Effect::Speak(utterance) => match self.backend.speak(&utterance) {
+                    Ok(()) => Ok(()),
+                    Err(e) => {
+                        let effects = self.lock().player.speak_failed(utterance.id);
+                        self.run(effects).and(Err(e))
+                    }
+                },
+                Effect::Stop => {
+                    // Fails harmlessly when nothing is speaking.
+                    let _ = self.backend.stop();
+                    Ok(())
+                }
+                effect => (self.host)(self, effect),
+            };
+            if let Err(e) = outcome {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    /// Feeds an engine event to the player.
+    pub fn handle(&self, event: SpeechEvent) -> Result<(), String> {
+        let effects = {
+            let player = &mut self.lock().player;
+            match event {
+                SpeechEvent::Finished => player.utterance_finished(),
+                SpeechEvent::Error => player.utterance_failed(),
+                SpeechEvent::Interrupted => player.utterance_interrupted(),
+                SpeechEvent::FocusLost => player.focus_lost(),
+                SpeechEvent::FocusGained => player.focus_gained(),
+                SpeechEvent::OutputLost => player.output_lost(),
+                SpeechEvent::Boundary { char_index } => player.boundary(char_index),
+            }
+        };
+        self.run(effects)
+    }
+
+    /// Sends engine events to `handler`, replacing the listeners of an
+    /// earlier call.
+    pub fn attach(&self, handler: SpeechHandler) {
+        let ids = self.backend.listen(handler);
+        let previous = std::mem::replace(&mut self.lock().listener_ids, ids);
+        for id in previous {
+            self.backend.unlisten(id);
+        }
+    }
+
+    /// Removes the listeners of `attach`.
+    pub fn detach(&self) {
+        let ids = std::mem::take(&mut self.lock().listener_ids);
+        for id in ids {
+            self.backend.unlisten(id);
+        }
+    }
+}
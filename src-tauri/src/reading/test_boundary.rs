@@ -0,0 +1,94 @@
+use super::boundary::*;
+
+#[test]
+fn test_same_text() {
+    let text = "Hello brave new world.";
+    assert_eq!(
+        display_range(text, text, 0),
+        Some(WordRange { start: 0, end: 5 })
+    );
+    assert_eq!(
+        display_range(text, text, 6),
+        Some(WordRange { start: 6, end: 11 })
+    );
+    assert_eq!(
+        display_range(text, text, 16),
+        Some(WordRange { start: 16, end: 22 })
+    );
+}
+
+#[test]
+fn test_rewritten_text_maps_by_word() {
+    let display = "It costs $5 today.";
+    let spoken = "It costs five dollars today.";
+    assert_eq!(
+        display_range(display, spoken, 22),
+        Some(WordRange { start: 12, end: 18 })
+    );
+    assert_eq!(
+        display_range(display, spoken, 0),
+        Some(WordRange { start: 0, end: 2 })
+    );
+}
+
+#[test]
+fn test_utf16_offsets() {
+    let text = "Grüße 😀 aus Köln";
+    assert_eq!(
+        display_range(text, text, 9),
+        Some(WordRange { start: 9, end: 12 })
+    );
+}
+
+#[test]
+fn test_leading_whitespace_and_empty() {
+    assert_eq!(
+        display_range("  Hi there", "Hi there", 3),
+        Some(WordRange { start: 3, end: 8 })
+    );
+    assert_eq!(display_range("", "Hi", 0), None);
+    assert_eq!(
+        display_range("Hi", "", 0),
+        Some(WordRange { start: 0, end: 2 })
+    );
+}
+
+#[test]
+fn test_resume_at_word_start() {
+    let text = "Hello brave new world.";
+    assert_eq!(resume_at(text, 0), (0, text));
+    assert_eq!(resume_at(text, 6), (6, "brave new world."));
+    assert_eq!(resume_at(text, 8), (6, "brave new world."));
+    assert_eq!(resume_at("Ünïcode 😀 words", 9), (8, "😀 words"));
+    assert_eq!(resume_at("Ünïcode 😀 words", 12), (11, "words"));
+}
+
+/// UTF-16 offset of `needle` in `text`.
+fn utf16_offset(text: &str, needle: &str) -> usize {
+    text[..text.find(needle).unwrap()].encode_utf16().count()
+}
+
+#[test]
+fn test_ssml_units() {
+    let ssml = "<speak>Read <phoneme alphabet=\"ipa\" ph=\"ɡɪf\">GIF</phoneme> &amp; more.</speak>";
+    let display = "Read GIF & more.";
+    assert_eq!(plain_text(ssml), display);
+    assert_eq!(plain_text("1 <2> 3"), "1 <2> 3");
+
+    // Offsets into the markup land on the words of the plain text.
+    assert_eq!(
+        display_range(display, ssml, utf16_offset(ssml, "GIF<")),
+        Some(WordRange { start: 5, end: 8 })
+    );
+    assert_eq!(
+        display_range(display, ssml, utf16_offset(ssml, "&amp;")),
+        Some(WordRange { start: 9, end: 10 })
+    );
+    assert_eq!(
+        display_range(display, ssml, utf16_offset(ssml, "more")),
+        Some(WordRange { start: 11, end: 16 })
+    );
+
+    // Never cut mid-markup.
+    assert_eq!(resume_at(ssml, utf16_offset(ssml, "more")), (0, ssml));
+}
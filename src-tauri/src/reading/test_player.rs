@@ -0,0 +1,475 @@
+use super::*;
+use crate::models::{BlockKind, VoiceProfile};
+use std::time::Instant;
+
+fn para(block: usize) -> TtsParagraph {
+    TtsParagraph {
+        text: "Some words here.".to_string(),
+        kind: BlockKind::Prose,
+        block,
+        heading: None,
+        cell: None,
+        refs: vec![],
+    }
+}
+
+/// Engine that speaks one utterance at a time, like the plugin with
+/// `QueueMode::Flush`, and reports back in order.
+#[derive(Default)]
+struct MockTts {
+    player: Player,
+    /// Utterances spoken, in order.
+    spoken: Vec<Utterance>,
+    /// Utterances the engine has not reported yet.
+    queue: Vec<u64>,
+    waits: Vec<(u64, Duration)>,
+    finished: Vec<bool>,
+}
+
+impl MockTts {
+    fn new(units: &[&str], blocks: &[usize]) -> Self {
+        let mut tts = Self::default();
+        let effects = tts.player.load(
+            units.iter().map(ToString::to_string).collect(),
+            blocks.iter().map(|&block| para(block)).collect(),
+        );
+        tts.run(effects);
+        tts
+    }
+
+    fn run(&mut self, effects: Vec<Effect>) {
+        for effect in effects {
+            match effect {
+                Effect::Speak(utterance) => {
+                    self.queue.push(utterance.id);
+                    self.spoken.push(utterance);
+                }
+                Effect::Stop | Effect::StateChanged | Effect::Word { .. } => {}
+                Effect::Wait { ticket, pause } => self.waits.push((ticket, pause)),
+                Effect::Finished { continue_queue } => self.finished.push(continue_queue),
+            }
+        }
+    }
+
+    fn play(&mut self, from: Option<usize>) {
+        let effects = self.player.play(from);
+        self.run(effects);
+    }
+
+    fn pause(&mut self) {
+        let effects = self.player.pause();
+        self.run(effects);
+    }
+
+    /// The oldest utterance ends naturally.
+    fn finish(&mut self) {
+        self.queue.remove(0);
+        let effects = self.player.utterance_finished();
+        self.run(effects);
+    }
+
+    /// The oldest utterance is cut off.
+    fn interrupt(&mut self) {
+        self.queue.remove(0);
+        let effects = self.player.utterance_failed();
+        self.run(effects);
+    }
+
+    fn elapse_wait(&mut self) {
+        let (ticket, _) = self.waits.remove(0);
+        let effects = self.player.pause_elapsed(ticket);
+        self.run(effects);
+    }
+
+    fn spoken_positions(&self) -> Vec<usize> {
+        self.spoken.iter().map(|u| u.position).collect()
+    }
+}
+
+#[test]
+fn test_reads_to_the_end() {
+    let mut tts = MockTts::new(&["One.", "", "Three."], &[0, 0, 1]);
+    tts.play(None);
+    assert!(tts.player.is_playing());
+    tts.finish();
+    tts.finish();
+    assert_eq!(tts.spoken_positions(), vec![0, 2]);
+    assert_eq!(tts.player.playback(), Playback::Finished);
+    assert_eq!(tts.finished, vec![true]);
+}
+
+#[test]
+fn test_pause_between_blocks() {
+    let mut tts = MockTts::new(&["One.", "Two."], &[0, 1]);
+    tts.player.set_speech(SpeechOptions {
+        block_pause: Duration::from_millis(500),
+        ..SpeechOptions::default()
+    });
+    tts.play(None);
+    tts.finish();
+    assert_eq!(tts.waits, vec![(1, Duration::from_millis(500))]);
+    assert_eq!(tts.spoken_positions(), vec![0]);
+    tts.elapse_wait();
+    assert_eq!(tts.spoken_positions(), vec![0, 1]);
+}
+
+#[test]
+fn test_pause_during_wait_cancels_it() {
+    let mut tts = MockTts::new(&["One.", "Two."], &[0, 1]);
+    tts.player.set_speech(SpeechOptions {
+        block_pause: Duration::from_millis(500),
+        ..SpeechOptions::default()
+    });
+    tts.play(None);
+    tts.finish();
+    tts.pause();
+    tts.elapse_wait();
+    assert_eq!(tts.spoken_positions(), vec![0]);
+    assert_eq!(tts.player.playback(), Playback::Paused);
+    assert_eq!(tts.player.position(), 1);
+}
+
+#[test]
+fn test_stale_report_after_pause_and_play() {
+    let mut tts = MockTts::new(&["One.", "Two.", "Three."], &[0, 0, 0]);
+    tts.play(None);
+    tts.pause();
+    tts.play(None);
+    // The engine reports the stopped utterance after playback resumed.
+    tts.interrupt();
+    assert!(tts.player.is_playing());
+    assert_eq!(tts.player.position(), 0);
+    tts.finish();
+    assert_eq!(tts.spoken_positions(), vec![0, 0, 1]);
+}
+
+#[test]
+fn test_stop_without_report() {
+    let mut tts = MockTts::new(&["One.", "Two.", "Three.", "Four."], &[0, 0, 0, 0]);
+    tts.play(None);
+    tts.pause();
+    // The engine drops the stopped utterance without a word.
+    tts.queue.remove(0);
+    tts.play(None);
+    tts.finish();
+    assert_eq!(tts.player.position(), 1);
+    assert_eq!(tts.spoken_positions(), vec![0, 0, 1]);
+
+    // Once a finish came in, errors are the current utterance's again.
+    tts.pause();
+    tts.queue.remove(0);
+    tts.play(None);
+    tts.finish();
+    tts.interrupt();
+    assert_eq!(tts.player.playback(), Playback::Paused);
+    assert_eq!(tts.player.pause_reason(), Some(PauseReason::Error));
+    assert_eq!(tts.player.position(), 2);
+}
+
+#[test]
+fn test_stale_finish_after_skip() {
+    let mut tts = MockTts::new(&["One.", "Two.", "Three.", "Four."], &[0, 0, 0, 0]);
+    tts.play(None);
+    let effects = tts.player.skip_to(2);
+    tts.run(effects);
+    // The replaced utterance finished just before it was flushed.
+    tts.finish();
+    assert_eq!(tts.player.position(), 2);
+    tts.finish();
+    assert_eq!(tts.spoken_positions(), vec![0, 2, 3]);
+}
+
+#[test]
+fn test_duplicate_finish_is_ignored() {
+    let mut tts = MockTts::new(&["One.", "Two."], &[0, 0]);
+    tts.play(None);
+    tts.pause();
+    tts.interrupt();
+    let effects = tts.player.utterance_finished();
+    assert!(effects.is_empty());
+    assert_eq!(tts.player.position(), 0);
+}
+
+#[test]
+fn test_error_pauses() {
+    let mut tts = MockTts::new(&["One.", "Two."], &[0, 0]);
+    tts.play(None);
+    tts.interrupt();
+    assert_eq!(tts.player.playback(), Playback::Paused);
+    assert_eq!(tts.player.position(), 0);
+    tts.play(None);
+    assert_eq!(tts.spoken_positions(), vec![0, 0]);
+}
+
+#[test]
+fn test_speak_failure() {
+    let mut tts = MockTts::new(&["One."], &[0]);
+    tts.play(None);
+    let id = tts.spoken[0].id;
+    assert_eq!(tts.player.speak_failed(id), vec![Effect::StateChanged]);
+    assert_eq!(tts.player.playback(), Playback::Paused);
+    assert!(tts.player.utterance_finished().is_empty());
+}
+
+#[test]
+fn test_sleep_timers() {
+    let mut tts = MockTts::new(&["One.", "Two."], &[0, 0]);
+    tts.player
+        .set_sleep_timer(Some(ActiveSleepTimer::new(SleepTimer::EndOfParagraph)));
+    tts.play(None);
+    tts.finish();
+    assert_eq!(tts.player.playback(), Playback::Paused);
+    assert_eq!(tts.player.position(), 1);
+    assert!(tts.player.sleep_timer_info().is_none());
+
+    tts.player
+        .set_sleep_timer(Some(ActiveSleepTimer::new(SleepTimer::EndOfArticle)));
+    tts.play(None);
+    tts.finish();
+    assert_eq!(tts.finished, vec![false]);
+
+    let timer = ActiveSleepTimer::new(SleepTimer::Minutes { minutes: 1 });
+    tts.player.set_sleep_timer(Some(timer));
+    tts.play(Some(0));
+    let replaced = timer.deadline.unwrap() + Duration::from_secs(1);
+    assert!(tts.player.sleep_timer_expired(replaced).is_empty());
+    let effects = tts.player.sleep_timer_expired(timer.deadline.unwrap());
+    assert_eq!(effects, vec![Effect::Stop, Effect::StateChanged]);
+    assert_eq!(tts.player.playback(), Playback::Paused);
+}
+
+#[test]
+fn test_utterance_voice() {
+    let mut tts = MockTts::new(&["One."], &[0]);
+    tts.player.set_rate(1.5);
+    tts.player.set_voice_id(Some("default".to_string()));
+    tts.player.set_profile(Some(VoiceProfile {
+        id: 1,
+        name: "Profile".to_string(),
+        voice_id: None,
+        rate: 1.5,
+        pitch: 0.8,
+        volume: 0.5,
+        language: String::new(),
+    }));
+    tts.play(None);
+    assert_eq!(
+        tts.spoken,
+        vec![Utterance {
+            id: 1,
+            position: 0,
+            text: "One.".to_string(),
+            rate: 1.5,
+            voice_id: Some("default".to_string()),
+            pitch: 0.8,
+            volume: 0.5,
+        }]
+    );
+}
+
+#[test]
+fn test_words_only_while_speaking() {
+    let mut tts = MockTts::new(&["Some words here."], &[0]);
+    assert!(tts.player.boundary(5).is_empty());
+    tts.play(None);
+    let word = WordRange { start: 5, end: 10 };
+    assert_eq!(
+        tts.player.boundary(5),
+        vec![Effect::Word { position: 0, word }]
+    );
+    assert_eq!(tts.player.word(), Some(word));
+    tts.pause();
+    assert_eq!(tts.player.word(), None);
+}
+
+#[test]
+fn test_restart_from_last_word() {
+    let mut tts = MockTts::new(&["Some words here.", "Two."], &[0, 0]);
+    tts.play(None);
+    tts.player.boundary(5);
+    let effects = tts.player.change_rate(1.5);
+    assert_eq!(effects[0], Effect::Stop);
+    tts.run(effects);
+    assert_eq!(tts.spoken[1].text, "words here.");
+    assert_eq!(tts.player.word(), Some(WordRange { start: 5, end: 10 }));
+
+    // Offsets of the restarted utterance are shifted into the unit.
+    let word = WordRange { start: 11, end: 16 };
+    assert_eq!(
+        tts.player.boundary(6),
+        vec![Effect::Word { position: 0, word }]
+    );
+    tts.interrupt();
+    assert!(tts.player.is_playing());
+    tts.finish();
+    assert_eq!(tts.spoken_positions(), vec![0, 0, 1]);
+    assert_eq!(tts.spoken[2].text, "Two.");
+}
+
+#[test]
+fn test_restart_without_boundaries() {
+    let mut tts = MockTts::new(&["One.", "Two."], &[0, 0]);
+    assert!(tts.player.change_rate(2.0).is_empty());
+    tts.play(None);
+    assert!(tts.player.change_rate(2.0).is_empty());
+    let effects = tts.player.change_voice_id(Some("other".to_string()));
+    tts.run(effects);
+    assert_eq!(tts.spoken_positions(), vec![0, 0]);
+    assert_eq!(tts.spoken[1].text, "One.");
+    assert_eq!(tts.spoken[1].voice_id, Some("other".to_string()));
+    assert!(
+        tts.player
+            .change_voice_id(Some("other".to_string()))
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_interruption_resumes_from_last_word() {
+    let mut tts = MockTts::new(&["Some words here.", "Two."], &[0, 0]);
+    tts.play(None);
+    tts.player.boundary(5);
+    tts.queue.remove(0);
+    let effects = tts.player.utterance_interrupted();
+    tts.run(effects);
+    assert_eq!(tts.player.playback(), Playback::Paused);
+    assert_eq!(tts.player.pause_reason(), Some(PauseReason::Interrupted));
+
+    let effects = tts.player.focus_gained();
+    tts.run(effects);
+    assert!(tts.player.is_playing());
+    assert_eq!(tts.player.pause_reason(), None);
+    assert_eq!(tts.spoken[1].text, "words here.");
+    assert!(tts.player.focus_gained().is_empty());
+}
+
+#[test]
+fn test_auto_resume_off() {
+    let mut tts = MockTts::new(&["Some words here."], &[0]);
+    tts.player.set_speech(SpeechOptions {
+        auto_resume: false,
+        ..SpeechOptions::default()
+    });
+    tts.play(None);
+    tts.player.boundary(5);
+    tts.queue.remove(0);
+    let effects = tts.player.utterance_interrupted();
+    tts.run(effects);
+    assert!(tts.player.focus_gained().is_empty());
+    // Playing by hand still resumes where it was cut off.
+    tts.play(None);
+    assert_eq!(tts.spoken[1].text, "words here.");
+}
+
+#[test]
+fn test_errors_restart_the_sentence() {
+    let mut tts = MockTts::new(&["Some words here."], &[0]);
+    tts.play(None);
+    tts.player.boundary(5);
+    tts.interrupt();
+    assert_eq!(tts.player.pause_reason(), Some(PauseReason::Error));
+    assert!(tts.player.focus_gained().is_empty());
+    tts.play(None);
+    assert_eq!(tts.spoken[1].text, "Some words here.");
+}
+
+#[test]
+fn test_output_lost_pauses() {
+    let mut tts = MockTts::new(&["Some words here.", "Two."], &[0, 0]);
+    assert!(tts.player.output_lost().is_empty());
+    tts.play(None);
+    tts.player.boundary(5);
+    let effects = tts.player.output_lost();
+    assert_eq!(effects, vec![Effect::Stop, Effect::StateChanged]);
+    tts.run(effects);
+    assert_eq!(tts.player.pause_reason(), Some(PauseReason::Unplugged));
+    assert!(tts.player.focus_gained().is_empty());
+
+    // The stopped utterance reports back; it is stale.
+    tts.interrupt();
+    assert_eq!(tts.player.pause_reason(), Some(PauseReason::Unplugged));
+    tts.play(None);
+    assert_eq!(tts.spoken[1].text, "words here.");
+
+    tts.pause();
+    assert_eq!(tts.player.pause_reason(), None);
+}
+
+#[test]
+fn test_focus_lost_pauses_until_regained() {
+    let mut tts = MockTts::new(&["Some words here.", "Two."], &[0, 0]);
+    assert!(tts.player.focus_lost().is_empty());
+    tts.play(None);
+    tts.player.boundary(5);
+    let effects = tts.player.focus_lost();
+    assert_eq!(effects, vec![Effect::Stop, Effect::StateChanged]);
+    tts.run(effects);
+    assert_eq!(tts.player.pause_reason(), Some(PauseReason::Interrupted));
+
+    // The engine drops the stopped utterance without a report.
+    tts.queue.remove(0);
+    let effects = tts.player.focus_gained();
+    tts.run(effects);
+    assert!(tts.player.is_playing());
+    assert_eq!(tts.spoken[1].text, "words here.");
+    tts.finish();
+    assert_eq!(tts.spoken_positions(), vec![0, 0, 1]);
+}
+
+#[test]
+fn test_sleep_timer_fade() {
+    let mut tts = MockTts::new(&["One.", "Two.", "Three."], &[0, 0, 0]);
+    tts.player.set_sleep_timer(Some(ActiveSleepTimer {
+        timer: SleepTimer::Minutes { minutes: 5 },
+        deadline: Some(Instant::now() + Duration::from_secs(100)),
+    }));
+    tts.play(Some(0));
+    assert!((tts.spoken[0].volume - 1.0).abs() < f32::EPSILON);
+    let info = tts.player.sleep_timer_info().unwrap();
+    assert!(info.remaining_secs.is_some_and(|secs| secs <= 100));
+
+    // Half way through the fade window.
+    tts.player.set_sleep_timer(Some(ActiveSleepTimer {
+        timer: SleepTimer::Minutes { minutes: 5 },
+        deadline: Some(Instant::now() + Duration::from_secs(15)),
+    }));
+    tts.finish();
+    let volume = tts.spoken[1].volume;
+    assert!(volume < 0.65 && volume > 0.55, "{volume}");
+
+    // Past the deadline, before the timer fires.
+    tts.player.set_sleep_timer(Some(ActiveSleepTimer {
+        timer: SleepTimer::Minutes { minutes: 5 },
+        deadline: Some(Instant::now()),
+    }));
+    tts.finish();
+    assert!((tts.spoken[2].volume - 0.2).abs() < 0.01);
+
+    let effects = tts.player.reset();
+    tts.run(effects);
+    assert!(tts.player.sleep_timer_info().is_none());
+}
+
+#[test]
+fn test_restart_ssml_from_start() {
+    let ssml = "<speak>Some <phoneme alphabet=\"ipa\" ph=\"wɜːdz\">words</phoneme> here.</speak>";
+    let mut tts = MockTts::new(&[ssml], &[0]);
+    tts.play(None);
+    let index = ssml[..ssml.find("words<").unwrap()].encode_utf16().count();
+    let word = WordRange { start: 5, end: 10 };
+    assert_eq!(
+        tts.player.boundary(index),
+        vec![Effect::Word { position: 0, word }]
+    );
+    tts.queue.remove(0);
+    let effects = tts.player.utterance_interrupted();
+    tts.run(effects);
+    let effects = tts.player.focus_gained();
+    tts.run(effects);
+    assert_eq!(tts.spoken[1].text, ssml);
+
+    tts.player.boundary(index);
+    let effects = tts.player.change_rate(1.5);
+    tts.run(effects);
+    assert_eq!(tts.spoken[2].text, ssml);
+}
@@ -0,0 +1,201 @@
+use super::policy::*;
+use crate::models::{BlockKind, InlineKind, InlineRef, TableCell, TtsParagraph};
+
+fn unit(text: &str, kind: BlockKind, block: usize, cell: Option<TableCell>) -> TtsParagraph {
+    TtsParagraph {
+        text: text.to_string(),
+        kind,
+        block,
+        heading: None,
+        cell,
+        refs: vec![],
+    }
+}
+
+fn cell(row: usize, column: usize, header: bool) -> TableCell {
+    TableCell {
+        table: 0,
+        row,
+        column,
+        header,
+    }
+}
+
+fn article() -> Vec<TtsParagraph> {
+    vec![
+        unit("Intro.", BlockKind::Prose, 0, None),
+        unit("fn main() {", BlockKind::Code, 1, None),
+        unit("println!();", BlockKind::Code, 1, None),
+        unit("}", BlockKind::Code, 1, None),
+        unit("Name", BlockKind::Table, 2, Some(cell(0, 0, true))),
+        unit("Age", BlockKind::Table, 3, Some(cell(0, 1, true))),
+        unit("Ann", BlockKind::Table, 4, Some(cell(1, 0, false))),
+        unit("31", BlockKind::Table, 5, Some(cell(1, 1, false))),
+        unit("Bob", BlockKind::Table, 6, Some(cell(2, 0, false))),
+        unit("45", BlockKind::Table, 7, Some(cell(2, 1, false))),
+        unit("Outro.", BlockKind::Prose, 8, None),
+    ]
+}
+
+fn texts(policies: BlockPolicies) -> Vec<String> {
+    apply_block_policies(&article(), &policies, "en")
+        .into_iter()
+        .map(|p| p.text)
+        .collect()
+}
+
+#[test]
+fn test_read_policy_keeps_text() {
+    let expected: Vec<String> = article().into_iter().map(|p| p.text).collect();
+    assert_eq!(texts(BlockPolicies::default()), expected);
+}
+
+#[test]
+fn test_code_policies() {
+    let code = |code| {
+        texts(BlockPolicies {
+            code,
+            ..BlockPolicies::default()
+        })[1..4]
+            .to_vec()
+    };
+    assert_eq!(code(CodePolicy::Skip), vec!["", "", ""]);
+    assert_eq!(
+        code(CodePolicy::Announce),
+        vec!["Code block with 3 lines skipped.", "", ""]
+    );
+    assert_eq!(code(CodePolicy::FirstLine), vec!["fn main() {", "", ""]);
+}
+
+#[test]
+fn test_table_policies() {
+    let table = |table| {
+        texts(BlockPolicies {
+            table,
+            ..BlockPolicies::default()
+        })[4..10]
+            .to_vec()
+    };
+    assert_eq!(table(TablePolicy::Skip), vec![""; 6]);
+    assert_eq!(
+        table(TablePolicy::Announce),
+        vec!["Table with 2 rows skipped.", "", "", "", "", ""]
+    );
+    assert_eq!(
+        table(TablePolicy::Rows),
+        vec!["", "", "Name: Ann. Age: 31.", "", "Name: Bob. Age: 45.", ""]
+    );
+}
+
+#[test]
+fn test_policy_settings_and_phrases() {
+    let mut policies = BlockPolicies::default();
+    policies.apply_setting(CODE_POLICY, "first_line");
+    policies.apply_setting(TABLE_POLICY, "bogus");
+    assert_eq!(policies.code, CodePolicy::FirstLine);
+    assert_eq!(policies.table, TablePolicy::Read);
+
+    let german = apply_block_policies(
+        &article(),
+        &BlockPolicies {
+            code: CodePolicy::Announce,
+            ..BlockPolicies::default()
+        },
+        "de-AT",
+    );
+    assert_eq!(german[1].text, "Codeblock mit 3 Zeilen übersprungen.");
+}
+
+fn figure() -> Vec<TtsParagraph> {
+    let mut text = unit("See the docs for details1.", BlockKind::Prose, 2, None);
+    text.refs = vec![
+        InlineRef {
+            kind: InlineKind::Link,
+            text: "the docs".to_string(),
+            start: 4,
+        },
+        InlineRef {
+            kind: InlineKind::Footnote,
+            text: "1".to_string(),
+            start: 24,
+        },
+    ];
+    vec![
+        unit("A cat", BlockKind::Image, 0, None),
+        unit("A sleeping", BlockKind::Caption, 1, None),
+        unit("cat.", BlockKind::Caption, 1, None),
+        text,
+    ]
+}
+
+fn figure_texts(policies: BlockPolicies) -> Vec<String> {
+    apply_block_policies(&figure(), &policies, "en")
+        .into_iter()
+        .map(|p| p.text)
+        .collect()
+}
+
+#[test]
+fn test_image_and_caption_policies() {
+    assert_eq!(
+        figure_texts(BlockPolicies::default())[..3],
+        ["", "A sleeping", "cat."]
+    );
+    assert_eq!(
+        figure_texts(BlockPolicies {
+            images: true,
+            caption: CaptionPolicy::Announce,
+            ..BlockPolicies::default()
+        })[..3],
+        ["Image: A cat", "Image: A sleeping", "cat."]
+    );
+    assert_eq!(
+        figure_texts(BlockPolicies {
+            caption: CaptionPolicy::Skip,
+            ..BlockPolicies::default()
+        })[..3],
+        ["", "", ""]
+    );
+}
+
+#[test]
+fn test_inline_policies() {
+    let text = |links, footnotes| {
+        figure_texts(BlockPolicies {
+            links,
+            footnotes,
+            ..BlockPolicies::default()
+        })[3]
+            .clone()
+    };
+    assert_eq!(text(false, false), "See the docs for details1.");
+    assert_eq!(text(true, false), "See link, the docs for details1.");
+    assert_eq!(text(false, true), "See the docs for details footnote 1.");
+    assert_eq!(
+        text(true, true),
+        "See link, the docs for details footnote 1."
+    );
+
+    let mut policies = BlockPolicies::default();
+    policies.apply_setting(LINK_POLICY, "true");
+    policies.apply_setting(IMAGE_POLICY, "false");
+    policies.apply_setting(CAPTION_POLICY, "announce");
+    assert!(policies.links && !policies.images);
+    assert_eq!(policies.caption, CaptionPolicy::Announce);
+}
+
+#[test]
+fn test_stale_inline_ref_is_ignored() {
+    let mut paragraphs = figure();
+    paragraphs[3].text = "Changed.".to_string();
+    let planned = apply_block_policies(
+        &paragraphs,
+        &BlockPolicies {
+            links: true,
+            footnotes: true,
+            ..BlockPolicies::default()
+        },
+        "en",
+    );
+    assert_eq!(planned[3].text, "Changed.");
+}
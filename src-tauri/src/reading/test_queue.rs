@@ -0,0 +1,50 @@
+use super::*;
+
+#[test]
+fn test_queue_neighbour() {
+    let queue = [3, 1, 2];
+    assert_eq!(queue_neighbour(&queue, Some(1), 1), Some(2));
+    assert_eq!(queue_neighbour(&queue, Some(1), -1), Some(3));
+    assert_eq!(queue_neighbour(&queue, Some(3), 2), Some(2));
+    // Both ends.
+    assert_eq!(queue_neighbour(&queue, Some(2), 1), None);
+    assert_eq!(queue_neighbour(&queue, Some(3), -1), None);
+    assert_eq!(queue_neighbour(&queue, Some(1), -2), None);
+    assert_eq!(queue_neighbour(&[], Some(1), 1), None);
+}
+
+#[test]
+fn test_queue_neighbour_not_queued() {
+    let queue = [3, 1, 2];
+    assert_eq!(queue_neighbour(&queue, Some(9), 1), Some(3));
+    assert_eq!(queue_neighbour(&queue, None, 2), Some(3));
+    assert_eq!(queue_neighbour(&queue, Some(9), -1), None);
+    assert_eq!(queue_neighbour(&queue, None, 0), None);
+}
+
+#[test]
+fn test_queue_advance() {
+    let queue = [3, 1, 2];
+    let advance = QueueAdvance::after(&queue, 1, false);
+    assert_eq!(
+        advance,
+        QueueAdvance {
+            next: Some(2),
+            delete: false
+        }
+    );
+    assert!(!advance.unloads(false));
+
+    // The last article is deleted and nothing follows.
+    let advance = QueueAdvance::after(&queue, 2, true);
+    assert_eq!(advance.next, None);
+    assert!(advance.delete);
+    assert!(advance.unloads(true));
+    // Already deleted elsewhere: the speak bar keeps it.
+    assert!(!advance.unloads(false));
+
+    // An article read outside the queue starts it.
+    let advance = QueueAdvance::after(&queue, 9, true);
+    assert_eq!(advance.next, Some(3));
+    assert!(!advance.unloads(true));
+}
@@ -0,0 +1,162 @@
+use super::*;
+use crate::models::BlockKind;
+
+fn para(block: usize, heading: Option<u8>) -> TtsParagraph {
+    TtsParagraph {
+        text: "Ten chars.".to_string(),
+        kind: BlockKind::Prose,
+        block,
+        heading,
+        cell: None,
+        refs: vec![],
+    }
+}
+
+/// Heading (0), paragraph of two sentences (1), heading (2), paragraph (3).
+fn outline() -> Vec<TtsParagraph> {
+    vec![
+        para(0, Some(1)),
+        para(1, None),
+        para(1, None),
+        para(2, Some(2)),
+        para(3, None),
+    ]
+}
+
+fn spoken(n: usize) -> Vec<String> {
+    vec!["x".repeat(75); n]
+}
+
+fn skip(pos: usize, unit: SkipUnit, forward: bool) -> Option<usize> {
+    let paragraphs = outline();
+    skip_target(&paragraphs, &spoken(5), pos, unit, forward, 1.0)
+}
+
+#[test]
+fn test_sentence_skip() {
+    assert_eq!(skip(1, SkipUnit::Sentence, true), Some(2));
+    assert_eq!(skip(4, SkipUnit::Sentence, true), None);
+    assert_eq!(skip(0, SkipUnit::Sentence, false), None);
+}
+
+#[test]
+fn test_block_skip() {
+    assert_eq!(skip(1, SkipUnit::Block, true), Some(3));
+    assert_eq!(skip(2, SkipUnit::Block, false), Some(1));
+    assert_eq!(skip(1, SkipUnit::Block, false), Some(0));
+    assert_eq!(skip(4, SkipUnit::Block, true), None);
+}
+
+#[test]
+fn test_heading_skip() {
+    assert_eq!(skip(0, SkipUnit::Heading, true), Some(3));
+    assert_eq!(skip(4, SkipUnit::Heading, true), None);
+    assert_eq!(skip(4, SkipUnit::Heading, false), Some(3));
+    assert_eq!(skip(3, SkipUnit::Heading, false), Some(0));
+    assert_eq!(skip(2, SkipUnit::Heading, false), Some(0));
+}
+
+#[test]
+fn test_time_skip() {
+    // Each unit is 75 characters, five seconds at rate 1.0.
+    assert_eq!(skip(0, SkipUnit::Time, true), Some(3));
+    assert_eq!(skip(3, SkipUnit::Time, true), None);
+    assert_eq!(skip(4, SkipUnit::Time, false), Some(1));
+    assert_eq!(skip(1, SkipUnit::Time, false), Some(0));
+    let paragraphs = outline();
+    assert_eq!(
+        skip_target(&paragraphs, &spoken(5), 0, SkipUnit::Time, true, 2.0),
+        None
+    );
+}
+
+#[test]
+fn test_elapsed_and_seek() {
+    let mut units = spoken(4);
+    units[2] = String::new();
+    assert!((elapsed_secs(&units, 2, 1.0) - 10.0).abs() < 1e-3);
+    assert!((elapsed_secs(&units, 10, 1.0) - 15.0).abs() < 1e-3);
+    assert!((elapsed_secs(&units, 10, 2.0) - 7.5).abs() < 1e-3);
+
+    assert_eq!(seek_target(&units, 0.0, 1.0), Some(0));
+    assert_eq!(seek_target(&units, 7.0, 1.0), Some(1));
+    // The third unit is not read; time past the second lands on the fourth.
+    assert_eq!(seek_target(&units, 10.0, 1.0), Some(3));
+    assert_eq!(seek_target(&units, 60.0, 1.0), Some(3));
+    assert_eq!(seek_target(&units, 3.0, 2.0), Some(1));
+    assert_eq!(seek_target(&[], 3.0, 1.0), None);
+
+    // SSML markup takes no time.
+    let ssml = "<speak><phoneme alphabet=\"ipa\" ph=\"tɛn\">Ten</phoneme> chars.</speak>";
+    assert!((estimate_secs(ssml, 1.0) - estimate_secs("Ten chars.", 1.0)).abs() < 1e-6);
+}
+
+#[test]
+fn test_current_section() {
+    let entry = |para: usize| TocEntry {
+        level: 2,
+        text: format!("Section {para}"),
+        para,
+        anchor: String::new(),
+    };
+    let toc = vec![entry(1), entry(4)];
+    assert_eq!(current_section(&toc, 0), None);
+    assert_eq!(current_section(&toc, 1).map(|e| e.para), Some(1));
+    assert_eq!(current_section(&toc, 3).map(|e| e.para), Some(1));
+    assert_eq!(current_section(&toc, 9).map(|e| e.para), Some(4));
+}
+
+#[test]
+fn test_speech_options_settings() {
+    let mut options = SpeechOptions::default();
+    options.apply_setting("speech_pitch", "1.5");
+    options.apply_setting("speech_volume", "3");
+    options.apply_setting("speech_pause_heading", "800");
+    options.apply_setting("speech_pause_block", "oops");
+    options.apply_setting("speech_unknown", "1");
+    options.apply_setting("speech_auto_resume", "false");
+    assert_eq!(
+        options,
+        SpeechOptions {
+            pitch: 1.5,
+            volume: 1.0,
+            heading_pause: Duration::from_millis(800),
+            auto_resume: false,
+            ..SpeechOptions::default()
+        }
+    );
+}
+
+#[test]
+fn test_pause_after() {
+    let paragraphs = outline();
+    let options = SpeechOptions {
+        sentence_pause: Duration::from_millis(100),
+        heading_pause: Duration::from_millis(700),
+        block_pause: Duration::from_millis(400),
+        ..SpeechOptions::default()
+    };
+    assert_eq!(pause_after(&paragraphs, 0, &options), options.heading_pause);
+    assert_eq!(
+        pause_after(&paragraphs, 1, &options),
+        options.sentence_pause
+    );
+    assert_eq!(pause_after(&paragraphs, 2, &options), options.block_pause);
+    assert_eq!(pause_after(&paragraphs, 4, &options), Duration::ZERO);
+}
+
+#[test]
+fn test_skip_over_unspoken() {
+    let paragraphs = outline();
+    let mut spoken = spoken(5);
+    spoken[1].clear();
+    spoken[2].clear();
+    assert_eq!(
+        skip_target(&paragraphs, &spoken, 0, SkipUnit::Sentence, true, 1.0),
+        Some(3)
+    );
+    assert_eq!(
+        skip_target(&paragraphs, &spoken, 3, SkipUnit::Sentence, false, 1.0),
+        Some(0)
+    );
+}
@@ -0,0 +1,269 @@
+use super::*;
+use crate::models::{BlockKind, TtsParagraph};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[derive(Default)]
+struct Engine {
+    spoken: Vec<Utterance>,
+    stops: usize,
+    unavailable: bool,
+    listeners: Vec<(u32, SpeechHandler)>,
+    next_listener: u32,
+}
+
+/// In-memory engine that records what it was asked to speak. Events are
+/// sent with `emit`, as the device would.
+#[derive(Default)]
+struct FakeTts(Mutex<Engine>);
+
+impl FakeTts {
+    fn engine(&self) -> MutexGuard<'_, Engine> {
+        self.0.lock().unwrap()
+    }
+
+    fn emit(&self, event: SpeechEvent) {
+        let handlers: Vec<SpeechHandler> = self
+            .engine()
+            .listeners
+            .iter()
+            .map(|(_, handler)| Arc::clone(handler))
+            .collect();
+        for handler in handlers {
+            handler(event);
+        }
+    }
+
+    fn spoken_positions(&self) -> Vec<usize> {
+        self.engine().spoken.iter().map(|u| u.position).collect()
+    }
+}
+
+impl SpeechBackend for FakeTts {
+    fn speak(&self, utterance: &Utterance) -> Result<(), String> {
+        let mut engine = self.engine();
+        if engine.unavailable {
+            return Err("engine unavailable".to_string());
+        }
+        engine.spoken.push(utterance.clone());
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        self.engine().stops += 1;
+        Ok(())
+    }
+
+    fn voices(&self) -> Result<Vec<SpeechVoice>, String> {
+        Ok(vec![SpeechVoice {
+            id: "en-1".to_string(),
+            name: "English".to_string(),
+            language: "en-US".to_string(),
+        }])
+    }
+
+    fn listen(&self, handler: SpeechHandler) -> Vec<u32> {
+        let mut engine = self.engine();
+        engine.next_listener += 1;
+        let id = engine.next_listener;
+        engine.listeners.push((id, handler));
+        vec![id]
+    }
+
+    fn unlisten(&self, id: u32) {
+        self.engine()
+            .listeners
+            .retain(|(listener, _)| *listener != id);
+    }
+}
+
+struct Harness {
+    speaker: Arc<Speaker<FakeTts, ()>>,
+    /// Effects left to the host, in order.
+    hosted: Arc<Mutex<Vec<Effect>>>,
+}
+
+impl Harness {
+    fn new(units: &[&str]) -> Self {
+        let hosted = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&hosted);
+        let speaker = Arc::new(Speaker::new(
+            FakeTts::default(),
+            Box::new(move |_: &Speaker<FakeTts, ()>, effect| {
+                log.lock().unwrap().push(effect);
+                Ok(())
+            }),
+        ));
+        Self::attach(&speaker);
+        let outline = units
+            .iter()
+            .map(|text| TtsParagraph {
+                text: (*text).to_string(),
+                kind: BlockKind::Prose,
+                block: 0,
+                heading: None,
+                cell: None,
+                refs: vec![],
+            })
+            .collect();
+        let effects = speaker
+            .lock()
+            .player
+            .load(units.iter().map(ToString::to_string).collect(), outline);
+        speaker.run(effects).unwrap();
+        Self { speaker, hosted }
+    }
+
+    fn attach(speaker: &Arc<Speaker<FakeTts, ()>>) {
+        let weak = Arc::downgrade(speaker);
+        speaker.attach(Arc::new(move |event| {
+            if let Some(speaker) = weak.upgrade() {
+                let _ = speaker.handle(event);
+            }
+        }));
+    }
+
+    fn tts(&self) -> &FakeTts {
+        self.speaker.backend()
+    }
+
+    fn play(&self) -> Result<(), String> {
+        let effects = self.speaker.lock().player.play(None);
+        self.speaker.run(effects)
+    }
+
+    fn playback(&self) -> Playback {
+        self.speaker.lock().player.playback()
+    }
+
+    fn hosted(&self) -> Vec<Effect> {
+        self.hosted.lock().unwrap().clone()
+    }
+}
+
+#[test]
+fn test_auto_advance() {
+    let harness = Harness::new(&["One.", "Two.", "Three."]);
+    harness.play().unwrap();
+    for _ in 0..3 {
+        harness.tts().emit(SpeechEvent::Finished);
+    }
+    assert_eq!(harness.tts().spoken_positions(), vec![0, 1, 2]);
+    assert_eq!(harness.playback(), Playback::Finished);
+    assert_eq!(
+        harness.hosted().last(),
+        Some(&Effect::Finished {
+            continue_queue: true
+        })
+    );
+}
+
+#[test]
+fn test_rate_change_mid_article() {
+    let harness = Harness::new(&["Some words here.", "Two."]);
+    harness.play().unwrap();
+    harness.tts().emit(SpeechEvent::Boundary { char_index: 5 });
+    let effects = harness.speaker.lock().player.change_rate(2.0);
+    harness.speaker.run(effects).unwrap();
+    assert_eq!(harness.tts().engine().stops, 1);
+
+    // The flushed utterance reports its interruption after the restart.
+    harness.tts().emit(SpeechEvent::Interrupted);
+    assert_eq!(harness.playback(), Playback::Playing);
+    harness.tts().emit(SpeechEvent::Finished);
+    let spoken: Vec<(String, f32)> = harness
+        .tts()
+        .engine()
+        .spoken
+        .iter()
+        .map(|u| (u.text.clone(), u.rate))
+        .collect();
+    assert_eq!(
+        spoken,
+        vec![
+            ("Some words here.".to_string(), 1.0),
+            ("words here.".to_string(), 2.0),
+            ("Two.".to_string(), 2.0),
+        ]
+    );
+    assert_eq!(harness.speaker.lock().player.position(), 1);
+}
+
+#[test]
+fn test_error_and_interrupted_events() {
+    let harness = Harness::new(&["One.", "Two."]);
+    harness.play().unwrap();
+    harness.tts().emit(SpeechEvent::Error);
+    assert_eq!(harness.playback(), Playback::Paused);
+
+    harness.play().unwrap();
+    harness.tts().emit(SpeechEvent::Interrupted);
+    assert_eq!(harness.playback(), Playback::Paused);
+
+    // Nothing is in flight, so a late report changes nothing.
+    harness.tts().emit(SpeechEvent::Finished);
+    assert_eq!(harness.tts().spoken_positions(), vec![0, 0]);
+    assert_eq!(harness.speaker.lock().player.position(), 0);
+}
+
+#[test]
+fn test_focus_and_output_events() {
+    let harness = Harness::new(&["One.", "Two."]);
+    harness.play().unwrap();
+    harness.tts().emit(SpeechEvent::Interrupted);
+    assert_eq!(
+        harness.speaker.lock().player.pause_reason(),
+        Some(PauseReason::Interrupted)
+    );
+    harness.tts().emit(SpeechEvent::FocusGained);
+    assert_eq!(harness.playback(), Playback::Playing);
+
+    harness.tts().emit(SpeechEvent::OutputLost);
+    assert_eq!(harness.playback(), Playback::Paused);
+    harness.tts().emit(SpeechEvent::FocusGained);
+    assert_eq!(harness.playback(), Playback::Paused);
+    assert_eq!(harness.tts().spoken_positions(), vec![0, 0]);
+}
+
+#[test]
+fn test_speak_failure() {
+    let harness = Harness::new(&["One."]);
+    harness.tts().engine().unavailable = true;
+    assert_eq!(harness.play(), Err("engine unavailable".to_string()));
+    assert_eq!(harness.playback(), Playback::Paused);
+    // Playing, then paused again.
+    assert_eq!(
+        harness.hosted(),
+        vec![Effect::StateChanged, Effect::StateChanged]
+    );
+}
+
+#[test]
+fn test_boundaries_reach_the_host() {
+    let harness = Harness::new(&["Some words here."]);
+    harness.play().unwrap();
+    harness.tts().emit(SpeechEvent::Boundary { char_index: 5 });
+    assert_eq!(
+        harness.hosted().last(),
+        Some(&Effect::Word {
+            position: 0,
+            word: WordRange { start: 5, end: 10 },
+        })
+    );
+}
+
+#[test]
+fn test_listener_cleanup() {
+    let harness = Harness::new(&["One.", "Two."]);
+    Harness::attach(&harness.speaker);
+    assert_eq!(harness.tts().engine().listeners.len(), 1);
+
+    harness.play().unwrap();
+    harness.speaker.detach();
+    assert!(harness.tts().engine().listeners.is_empty());
+    harness.tts().emit(SpeechEvent::Finished);
+    assert_eq!(harness.tts().spoken_positions(), vec![0]);
+
+    let effects = harness.speaker.lock().player.reset();
+    harness.speaker.run(effects).unwrap();
+    assert_eq!(harness.tts().engine().stops, 1);
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::SiteStat;
+use crate::pronunciation::domain_of;
+
+// Reading Statistics:
+//
+// 1. A session is recorded when the speak bar stops or finishes an article
+//    (`listen`) and when the reader view is left (`read`). Sessions shorter
+//    than `MIN_SESSION_SECS` are dropped as accidental.
+// 2. A session finishes its article when the speak bar reaches the end or
+//    the reader view was scrolled to the last paragraph.
+// 3. Sessions keep the article url and creation time so statistics survive
+//    the article being deleted and purged.
+// 4. The backlog size is snapshotted once per day, whenever a session is
+//    recorded or the statistics are opened.
+
+pub const MIN_SESSION_SECS: f64 = 5.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionKind {
+    Listen,
+    Read,
+}
+
+impl SessionKind {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SessionKind::Listen => "listen",
+            SessionKind::Read => "read",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    #[default]
+    Day,
+    Week,
+}
+
+impl Period {
+    /// `strftime` format grouping timestamps by period.
+    #[must_use]
+    pub fn sql_format(self) -> &'static str {
+        match self {
+            Period::Day => "%Y-%m-%d",
+            Period::Week => "%Y-W%W",
+        }
+    }
+}
+
+/// Date modifier for the last `days` days.
+#[must_use]
+pub fn since(days: u32) -> String {
+    format!("-{days} days")
+}
+
+/// One recorded session, as stored in `reading_sessions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub article_id: i32,
+    pub kind: SessionKind,
+    pub start_para: usize,
+    pub end_para: usize,
+    pub duration_secs: f64,
+    pub rate: Option<f32>,
+    pub finished: bool,
+}
+
+impl Session {
+    #[must_use]
+    pub fn is_recorded(&self) -> bool {
+        self.duration_secs >= MIN_SESSION_SECS || self.finished
+    }
+}
+
+/// Groups `(url, duration_secs, finished)` sessions by site and returns the
+/// `limit` sites with the most time spent.
+#[must_use]
+pub fn top_sites(sessions: &[(String, f64, bool)], limit: usize) -> Vec<SiteStat> {
+    let mut sites: HashMap<String, SiteStat> = HashMap::new();
+    for (url, secs, finished) in sessions {
+        let site = domain_of(url);
+        if site.is_empty() {
+            continue;
+        }
+        let stat = sites.entry(site.clone()).or_insert_with(|| SiteStat {
+            site,
+            secs: 0.0,
+            sessions: 0,
+            finished: 0,
+        });
+        stat.secs += secs;
+        stat.sessions += 1;
+        stat.finished += usize::from(*finished);
+    }
+    let mut sites: Vec<SiteStat> = sites.into_values().collect();
+    sites.sort_by(|a, b| b.secs.total_cmp(&a.secs).then_with(|| a.site.cmp(&b.site)));
+    sites.truncate(limit);
+    sites
+}
+
+#[cfg(test)]
+mod test_stats;
@@ -0,0 +1,64 @@
+use super::*;
+
+fn session(url: &str, secs: f64, finished: bool) -> (String, f64, bool) {
+    (url.to_string(), secs, finished)
+}
+
+#[test]
+fn test_top_sites() {
+    let sessions = vec![
+        session("https://www.example.com/a", 60.0, true),
+        session("https://blog.rust-lang.org/post", 100.0, false),
+        session("https://example.com/b", 50.0, false),
+        session("not a url", 500.0, true),
+        session("https://lwn.net/Articles/1", 10.0, true),
+    ];
+    let sites = top_sites(&sessions, 2);
+    assert_eq!(
+        sites,
+        vec![
+            SiteStat {
+                site: "example.com".to_string(),
+                secs: 110.0,
+                sessions: 2,
+                finished: 1,
+            },
+            SiteStat {
+                site: "blog.rust-lang.org".to_string(),
+                secs: 100.0,
+                sessions: 1,
+                finished: 0,
+            },
+        ]
+    );
+    assert!(top_sites(&[], 5).is_empty());
+}
+
+#[test]
+fn test_short_sessions_are_dropped() {
+    let mut session = Session {
+        article_id: 1,
+        kind: SessionKind::Read,
+        start_para: 0,
+        end_para: 2,
+        duration_secs: 3.0,
+        rate: None,
+        finished: false,
+    };
+    assert!(!session.is_recorded());
+    session.finished = true;
+    assert!(session.is_recorded());
+    session.finished = false;
+    session.duration_secs = MIN_SESSION_SECS;
+    assert!(session.is_recorded());
+}
+
+#[test]
+fn test_period_format() {
+    assert_eq!(Period::Week.sql_format(), "%Y-W%W");
+    assert_eq!(
+        serde_json::from_str::<Period>("\"day\"").unwrap(),
+        Period::Day
+    );
+    assert_eq!(since(7), "-7 days");
+}
@@ -0,0 +1,142 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+
+use super::KEY_FILE_NAME;
+
+const KEY_FILE_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+/// Encrypted into the key file, so a wrong passphrase is caught before any
+/// article is read or written.
+const KEY_CHECK: &[u8] = b"read-it-later sync key";
+/// Limits on the Argon2 parameters read from the key file, so a crafted
+/// one cannot make every device hash for minutes or run out of memory.
+const MAX_MEMORY_KIB: u32 = 256 * 1024;
+const MAX_ITERATIONS: u32 = 16;
+const MAX_PARALLELISM: u32 = 8;
+
+/// Stored in the clear at the root of an encrypted sync folder: what every
+/// device needs to derive the key from the passphrase, and a check value.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct KeyFile {
+    pub version: u32,
+    pub salt: Vec<u8>,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    pub check: Vec<u8>,
+}
+
+/// The key of an encrypted sync folder, derived from the passphrase with
+/// Argon2id. One half encrypts files, the other names them.
+pub struct SyncKey {
+    cipher: XChaCha20Poly1305,
+    name_key: [u8; 32],
+}
+
+/// The Argon2 parameters of `file`, within the limits.
+pub(super) fn key_params(file: &KeyFile) -> Result<Params, String> {
+    Params::new(
+        file.memory_kib.min(MAX_MEMORY_KIB),
+        file.iterations.min(MAX_ITERATIONS),
+        file.parallelism.min(MAX_PARALLELISM),
+        Some(64),
+    )
+    .map_err(|e| e.to_string())
+}
+
+impl SyncKey {
+    fn derive(passphrase: &str, file: &KeyFile) -> Result<Self, String> {
+        let params = key_params(file)?;
+        let mut out = [0u8; 64];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &file.salt, &mut out)
+            .map_err(|e| e.to_string())?;
+        let (cipher_key, name_key) = out.split_at(32);
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new(cipher_key.into()),
+            name_key: name_key.try_into().map_err(|_| "Invalid key length")?,
+        })
+    }
+
+    /// A new key with a random salt, and the key file to upload with it.
+    pub fn create(passphrase: &str) -> Result<(Self, KeyFile), String> {
+        if passphrase.is_empty() {
+            return Err("The sync passphrase is empty".to_string());
+        }
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut file = KeyFile {
+            version: KEY_FILE_VERSION,
+            salt,
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+            check: Vec::new(),
+        };
+        let key = Self::derive(passphrase, &file)?;
+        file.check = key.seal(KEY_FILE_NAME, KEY_CHECK)?;
+        Ok((key, file))
+    }
+
+    /// The key of an existing folder. Fails on a wrong passphrase.
+    pub fn unlock(passphrase: &str, file: &KeyFile) -> Result<Self, String> {
+        if file.version != KEY_FILE_VERSION {
+            return Err(format!(
+                "Unsupported sync key version {}, update the app",
+                file.version
+            ));
+        }
+        let key = Self::derive(passphrase, file)?;
+        match key.open(KEY_FILE_NAME, &file.check) {
+            Ok(check) if check == KEY_CHECK => Ok(key),
+            _ => Err("Wrong sync passphrase".to_string()),
+        }
+    }
+
+    /// The keyed hash naming the files of `url`, so the server cannot tell
+    /// which urls are synced.
+    #[must_use]
+    pub fn file_stem(&self, url: &str) -> String {
+        blake3::keyed_hash(&self.name_key, url.as_bytes())
+            .to_hex()
+            .to_string()
+    }
+
+    /// Encrypts the file `name` holding `plain` with a random nonce, stored
+    /// in front of the ciphertext. The name is authenticated, so the server
+    /// cannot swap the contents of two files.
+    pub fn seal(&self, name: &str, plain: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: plain,
+            aad: name.as_bytes(),
+        };
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, payload)
+            .map_err(|e| e.to_string())?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(sealed)
+    }
+
+    /// Decrypts the output of `seal` for the file `name`. Fails when it was
+    /// tampered with, sealed with another key or for another file.
+    pub fn open(&self, name: &str, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        if sealed.len() < NONCE_LEN {
+            return Err("Encrypted file is truncated".to_string());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: name.as_bytes(),
+        };
+        self.cipher
+            .decrypt(XNonce::from_slice(nonce), payload)
+            .map_err(|_| "Cannot decrypt sync file".to_string())
+    }
+}
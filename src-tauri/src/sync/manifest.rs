@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::{Merged, RecordSet, SyncedRecord, gunzip_json, gzip_json, merge};
+use crate::models::ArticleSync;
+
+/// Name of the manifest among the record sets of the folder.
+pub const MANIFEST_SET: &str = "articles";
+const MANIFEST_VERSION: u32 = 1;
+
+/// What the manifest holds about an article besides its timestamp and
+/// deleted flag.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ArticleEntry {
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+}
+
+/// The record of every synced article, by url.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Manifest {
+    pub version: u32,
+    pub articles: RecordSet<ArticleEntry>,
+    /// Versions (`ETag`s) of the per-article record files written before the
+    /// manifest, by file name, as of their last merge.
+    #[serde(default)]
+    pub legacy: BTreeMap<String, String>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            articles: RecordSet::new(),
+            legacy: BTreeMap::new(),
+        }
+    }
+}
+
+/// The manifest record of an article, keyed by its url.
+#[must_use]
+pub fn article_record(article: &ArticleSync) -> (String, SyncedRecord<ArticleEntry>) {
+    let record = SyncedRecord {
+        updated_at: article.updated_at.clone(),
+        is_deleted: article.is_deleted == 1,
+        value: ArticleEntry {
+            created_at: article.created_at.clone(),
+            content_hash: article.content_hash.clone(),
+        },
+    };
+    (article.url.clone(), record)
+}
+
+/// Merges the local articles with the manifest like any record set, except
+/// that a local copy with content replaces a remote copy of the same edit
+/// without any, as uploaded by a device that does not sync content.
+#[must_use]
+pub fn merge_articles(
+    local: &RecordSet<ArticleEntry>,
+    remote: &RecordSet<ArticleEntry>,
+) -> Merged<ArticleEntry> {
+    let mut merged = merge(local, remote);
+    for (url, record) in local {
+        let Some(theirs) = merged.pull.get(url) else {
+            continue;
+        };
+        if theirs.updated_at == record.updated_at
+            && theirs.is_deleted == record.is_deleted
+            && theirs.value.content_hash.is_none()
+            && record.value.content_hash.is_some()
+        {
+            merged.pull.remove(url);
+            merged.records.insert(url.clone(), record.clone());
+            merged.push.push(url.clone());
+        }
+    }
+    merged.push.sort();
+    merged
+}
+
+/// Fingerprint of the listed versions of the manifest and of the legacy
+/// record files. While it and the local articles stay the same, a sync has
+/// nothing to read or write.
+#[must_use]
+pub fn folder_state(manifest: Option<&str>, legacy: &BTreeMap<String, String>) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(manifest.unwrap_or_default().as_bytes());
+    for (name, version) in legacy {
+        hasher.update(b"\n");
+        hasher.update(name.as_bytes());
+        hasher.update(b" ");
+        hasher.update(version.as_bytes());
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+impl Manifest {
+    /// Compressed JSON, as stored in the manifest file.
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        gzip_json(self)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let manifest: Self =
+            gunzip_json(bytes).map_err(|e| format!("Invalid sync manifest: {e}"))?;
+        if manifest.version > MANIFEST_VERSION {
+            return Err(format!(
+                "Unsupported sync manifest version {}, update the app",
+                manifest.version
+            ));
+        }
+        Ok(manifest)
+    }
+
+    /// Names of the legacy record files, given with their current version,
+    /// that changed since their last merge. Files that are gone are
+    /// forgotten.
+    pub fn stale_legacy(&mut self, files: &BTreeMap<String, String>) -> Vec<String> {
+        self.legacy.retain(|name, _| files.contains_key(name));
+        files
+            .iter()
+            .filter(|(name, version)| self.legacy.get(*name) != Some(*version))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Merges a legacy record file at `version`, keeping the newer copy of
+    /// the article.
+    pub fn merge_legacy(&mut self, name: &str, version: &str, article: &ArticleSync) {
+        let (url, record) = article_record(article);
+        let newer = self
+            .articles
+            .get(&url)
+            .is_none_or(|known| known.updated_at < record.updated_at);
+        if newer {
+            self.articles.insert(url, record);
+        }
+        self.legacy.insert(name.to_string(), version.to_string());
+    }
+
+    /// Merges the opened legacy record file `plain`. A file that is gone,
+    /// cannot be opened or does not hold a record is skipped until it
+    /// changes, like a merged one.
+    pub fn merge_legacy_file(&mut self, name: &str, version: &str, plain: Option<&[u8]>) {
+        match plain.and_then(|plain| serde_json::from_slice::<ArticleSync>(plain).ok()) {
+            Some(article) => self.merge_legacy(name, version, &article),
+            None => {
+                self.legacy.insert(name.to_string(), version.to_string());
+            }
+        }
+    }
+}
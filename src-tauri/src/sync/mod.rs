@@ -0,0 +1,235 @@
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::{Read, Write};
+
+mod crypto;
+mod manifest;
+mod records;
+
+pub use crypto::{KeyFile, SyncKey};
+pub use manifest::{
+    ArticleEntry, MANIFEST_SET, Manifest, article_record, folder_state, merge_articles,
+};
+pub use records::{
+    Merged, PRONUNCIATION_SET, RecordSet, SETTINGS_SET, SYNCED_SETTINGS, SyncedRecord, keyed_rules,
+    merge, rule_key, synced_settings,
+};
+
+// WebDAV Sync:
+//
+// 1. The manifest holds the record of every article: timestamps, deleted
+//    flag and content hash, keyed by url. A sync reads and writes this one
+//    file, keeping the newer copy of each record, and skips it altogether
+//    when neither its ETag nor the local articles changed. The manifest is
+//    only written over the version that was read; when another device
+//    wrote it first, the sync merges that copy instead. Versions before
+//    the manifest wrote one record file per article, named after the blake3
+//    hash of its url; those are still merged in when their ETag changes.
+// 2. With content sync on, the readability output of an article is stored
+//    in a content file as gzip-compressed JSON, and the record carries the
+//    hash of that content. A device downloads the content only when the
+//    hash differs from its own copy, so an article fetched on one device
+//    (for example behind a login) is readable offline on the others.
+// 3. Devices without content sync ignore content files and leave out the
+//    hash; their records still reconcile the flags.
+// 4. With a passphrase, every file is encrypted and named by a hash keyed
+//    with the passphrase, so the server sees neither urls nor content. The
+//    key file at the root holds the salt and tells devices the folder is
+//    encrypted. A device given a passphrase for a folder in the clear pulls
+//    the pending changes, removes the files in the clear and uploads
+//    everything again encrypted. Only a missing key file, not a failed
+//    request, means the folder is in the clear, and a folder holding
+//    encrypted files is never emptied.
+// 5. Synced settings and pronunciation rules live in one file per set,
+//    holding every record with its own timestamp. Each sync merges the
+//    remote set record by record and uploads it again when it changed.
+
+pub const RECORD_EXTENSION: &str = ".json";
+pub const CONTENT_EXTENSION: &str = ".content";
+pub const SET_EXTENSION: &str = ".set";
+/// Marks an encrypted folder, see `KeyFile`.
+pub const KEY_FILE_NAME: &str = "sync.key";
+
+/// How the files of a sync folder are named and stored: in the clear, or
+/// encrypted with the key of the folder.
+#[derive(Default)]
+pub struct Folder {
+    key: Option<SyncKey>,
+}
+
+impl Folder {
+    #[must_use]
+    pub fn encrypted(key: SyncKey) -> Self {
+        Self { key: Some(key) }
+    }
+
+    #[must_use]
+    pub fn is_encrypted(&self) -> bool {
+        self.key.is_some()
+    }
+
+    fn file_stem(&self, url: &str) -> String {
+        match &self.key {
+            Some(key) => key.file_stem(url),
+            None => blake3::hash(url.as_bytes()).to_hex().to_string(),
+        }
+    }
+
+    /// The name of the record file of `url`, as written before the
+    /// manifest.
+    #[must_use]
+    pub fn record_name(&self, url: &str) -> String {
+        format!("{}{RECORD_EXTENSION}", self.file_stem(url))
+    }
+
+    /// The name of the content file of `url`.
+    #[must_use]
+    pub fn content_name(&self, url: &str) -> String {
+        format!("{}{CONTENT_EXTENSION}", self.file_stem(url))
+    }
+
+    /// The name of the file holding the record set `set`.
+    #[must_use]
+    pub fn set_name(&self, set: &str) -> String {
+        format!("{}{SET_EXTENSION}", self.file_stem(set))
+    }
+
+    /// The bytes to upload for the file `name` holding `plain`.
+    pub fn seal(&self, name: &str, plain: Vec<u8>) -> Result<Vec<u8>, String> {
+        match &self.key {
+            Some(key) => key.seal(name, &plain),
+            None => Ok(plain),
+        }
+    }
+
+    /// The file `name` held by downloaded `bytes`.
+    pub fn open(&self, name: &str, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        match &self.key {
+            Some(key) => key.open(name, &bytes),
+            None => Ok(bytes),
+        }
+    }
+}
+
+/// Whether a file of the sync folder is an article record written before
+/// the manifest.
+#[must_use]
+pub fn is_record_name(name: &str) -> bool {
+    name.ends_with(RECORD_EXTENSION)
+}
+
+/// The files among `names` that a folder in the clear holds, given its
+/// `manifest`: record files, record sets and the content of the articles
+/// it names. Any other record set or content file was written encrypted,
+/// so the folder is refused rather than emptied for want of its key file.
+pub fn plain_files<'a>(
+    names: impl IntoIterator<Item = &'a String>,
+    manifest: Option<&Manifest>,
+) -> Result<Vec<String>, String> {
+    let folder = Folder::default();
+    let sets: BTreeSet<String> = [MANIFEST_SET, SETTINGS_SET, PRONUNCIATION_SET]
+        .into_iter()
+        .map(|set| folder.set_name(set))
+        .collect();
+    let contents: BTreeSet<String> = manifest
+        .into_iter()
+        .flat_map(|manifest| manifest.articles.keys())
+        .map(|url| folder.content_name(url))
+        .collect();
+    let mut plain = Vec::new();
+    for name in names {
+        if is_record_name(name) || sets.contains(name) || contents.contains(name) {
+            plain.push(name.clone());
+        } else if name.ends_with(SET_EXTENSION) || name.ends_with(CONTENT_EXTENSION) {
+            return Err(format!(
+                "The sync folder holds encrypted files but no {KEY_FILE_NAME}, restore it"
+            ));
+        }
+    }
+    Ok(plain)
+}
+
+/// An article as extracted by readability, synced as is so every device
+/// shows the same text.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, sqlx::FromRow)]
+pub struct ArticleContent {
+    pub title: String,
+    pub body: String,
+    pub text_content: String,
+    #[serde(default)]
+    pub site_name: String,
+    #[serde(default)]
+    pub image_url: String,
+}
+
+impl ArticleContent {
+    /// Articles saved without content, or deleted, have none to sync.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.title.is_empty() && self.body.is_empty()
+    }
+
+    /// Hash of the content, or `None` when there is none.
+    #[must_use]
+    pub fn hash(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut hasher = blake3::Hasher::new();
+        for field in [
+            &self.title,
+            &self.body,
+            &self.text_content,
+            &self.site_name,
+            &self.image_url,
+        ] {
+            hasher.update(&(field.len() as u64).to_le_bytes());
+            hasher.update(field.as_bytes());
+        }
+        Some(hasher.finalize().to_hex().to_string())
+    }
+
+    /// Compressed JSON, as stored in the content file.
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        gzip_json(self)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        gunzip_json(bytes).map_err(|e| format!("Invalid content file: {e}"))
+    }
+}
+
+fn gzip_json<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    let json = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&json).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+fn gunzip_json<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    let mut json = Vec::new();
+    GzDecoder::new(bytes)
+        .read_to_end(&mut json)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_slice(&json).map_err(|e| e.to_string())
+}
+
+/// Whether the content named by `remote` must be downloaded to replace the
+/// local content hashed `local`.
+#[must_use]
+pub fn needs_content(local: Option<&str>, remote: Option<&str>) -> bool {
+    remote.is_some() && remote != local
+}
+
+#[cfg(test)]
+mod test_crypto;
+#[cfg(test)]
+mod test_manifest;
+#[cfg(test)]
+mod test_records;
+#[cfg(test)]
+mod test_sync;
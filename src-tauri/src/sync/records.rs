@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::models::PronunciationRule;
+
+/// Settings that follow the user across devices. Everything else, like the
+/// server credentials, stays on the device, and so does the voice: voice
+/// ids are engine specific and name no voice on another device.
+pub const SYNCED_SETTINGS: &[&str] = &["theme", "fetcher_mode", "rate"];
+
+/// Names of the record sets in the sync folder.
+pub const SETTINGS_SET: &str = "settings";
+pub const PRONUNCIATION_SET: &str = "pronunciation_rules";
+
+/// A setting or a pronunciation rule with its own timestamp, so changes
+/// made to different records on different devices all survive.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SyncedRecord<T> {
+    /// `YYYY-MM-DD HH:MM:SS` as written by `SQLite`, which sorts in time
+    /// order.
+    pub updated_at: String,
+    #[serde(default)]
+    pub is_deleted: bool,
+    pub value: T,
+}
+
+/// Records by name: setting name or `rule_key`.
+pub type RecordSet<T> = BTreeMap<String, SyncedRecord<T>>;
+
+/// The outcome of merging the local and remote copies of a record set.
+#[derive(Debug, PartialEq)]
+pub struct Merged<T> {
+    /// The set to keep on the server.
+    pub records: RecordSet<T>,
+    /// Remote records newer than the local ones, to store locally.
+    pub pull: RecordSet<T>,
+    /// Local records that replace remote ones. The set must be uploaded
+    /// when there are any.
+    pub push: Vec<String>,
+}
+
+/// Keeps the newer copy of every record. On equal timestamps the remote
+/// copy wins, so every device settles on the same value.
+#[must_use]
+pub fn merge<T: Clone + PartialEq>(local: &RecordSet<T>, remote: &RecordSet<T>) -> Merged<T> {
+    let mut records = remote.clone();
+    let mut pull = RecordSet::new();
+    let mut push = Vec::new();
+    for (name, record) in local {
+        match remote.get(name) {
+            Some(theirs) if theirs.updated_at >= record.updated_at => {
+                if theirs != record {
+                    pull.insert(name.clone(), theirs.clone());
+                }
+            }
+            _ => {
+                records.insert(name.clone(), record.clone());
+                push.push(name.clone());
+            }
+        }
+    }
+    for (name, record) in remote {
+        if !local.contains_key(name) && !record.is_deleted {
+            pull.insert(name.clone(), record.clone());
+        }
+    }
+    Merged {
+        records,
+        pull,
+        push,
+    }
+}
+
+/// Drops the settings of `set` that are not synced, so a crafted or
+/// outdated folder cannot change device settings.
+#[must_use]
+pub fn synced_settings(mut set: RecordSet<String>) -> RecordSet<String> {
+    set.retain(|name, _| SYNCED_SETTINGS.contains(&name.as_str()));
+    set
+}
+
+/// Name of a pronunciation rule in its record set. Rules are told apart by
+/// pattern, language, domain and scope, as in the database.
+#[must_use]
+pub fn rule_key(rule: &PronunciationRule) -> String {
+    serde_json::json!([rule.match_pattern, rule.language, rule.domain, rule.scope]).to_string()
+}
+
+/// Names every rule of `set` by its `rule_key`. Folders written before
+/// rules were keyed by scope name them by pattern only; on a clash the
+/// newer record wins.
+#[must_use]
+pub fn keyed_rules(set: RecordSet<PronunciationRule>) -> RecordSet<PronunciationRule> {
+    let mut keyed = RecordSet::new();
+    for record in set.into_values() {
+        let key = rule_key(&record.value);
+        if keyed
+            .get(&key)
+            .is_none_or(|known: &SyncedRecord<_>| known.updated_at < record.updated_at)
+        {
+            keyed.insert(key, record);
+        }
+    }
+    keyed
+}
@@ -0,0 +1,68 @@
+use super::*;
+
+#[test]
+fn test_unlock() {
+    let (key, file) = SyncKey::create("correct horse").unwrap();
+    assert_eq!(file.salt.len(), 16);
+    let unlocked = SyncKey::unlock("correct horse", &file).unwrap();
+    assert_eq!(
+        unlocked.file_stem("https://example.com"),
+        key.file_stem("https://example.com")
+    );
+    assert_eq!(
+        SyncKey::unlock("battery staple", &file).err(),
+        Some("Wrong sync passphrase".to_string())
+    );
+    assert!(SyncKey::create("").is_err());
+
+    let future = KeyFile { version: 2, ..file };
+    assert!(SyncKey::unlock("correct horse", &future).is_err());
+}
+
+#[test]
+fn test_encrypted_folder() {
+    let (key, _) = SyncKey::create("correct horse").unwrap();
+    let (other, _) = SyncKey::create("correct horse").unwrap();
+    let url = "https://example.com/article";
+
+    let folder = Folder::encrypted(key);
+    assert!(folder.is_encrypted());
+    // Names are keyed: they match neither the plain names nor those of a
+    // folder with another salt.
+    let record = folder.record_name(url);
+    assert!(is_record_name(&record));
+    assert_ne!(record, Folder::default().record_name(url));
+    assert_ne!(record, Folder::encrypted(other).record_name(url));
+
+    let name = folder.content_name(url);
+    let sealed = folder.seal(&name, url.as_bytes().to_vec()).unwrap();
+    assert!(!sealed.windows(url.len()).any(|w| w == url.as_bytes()));
+    assert_ne!(sealed, folder.seal(&name, url.as_bytes().to_vec()).unwrap());
+    assert_eq!(folder.open(&name, sealed.clone()).unwrap(), url.as_bytes());
+    // Bound to its name, so it cannot pass for another file.
+    assert!(folder.open(&record, sealed.clone()).is_err());
+
+    let mut tampered = sealed;
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(folder.open(&name, tampered).is_err());
+    assert!(folder.open(&name, vec![0; 10]).is_err());
+}
+
+#[test]
+fn test_key_file_limits() {
+    let (_, file) = SyncKey::create("correct horse").unwrap();
+    let params = crypto::key_params(&file).unwrap();
+    assert_eq!(params.m_cost(), file.memory_kib);
+    assert_eq!(params.t_cost(), file.iterations);
+
+    let costly = KeyFile {
+        memory_kib: u32::MAX,
+        iterations: u32::MAX,
+        parallelism: u32::MAX,
+        ..file
+    };
+    let params = crypto::key_params(&costly).unwrap();
+    assert_eq!(params.m_cost(), 256 * 1024);
+    assert_eq!(params.t_cost(), 16);
+    assert_eq!(params.p_cost(), 8);
+}
@@ -0,0 +1,134 @@
+use super::*;
+use crate::models::ArticleSync;
+use std::collections::BTreeMap;
+
+fn article(url: &str, updated_at: &str) -> ArticleSync {
+    ArticleSync {
+        url: url.to_string(),
+        created_at: "2026-10-01 08:00:00".to_string(),
+        updated_at: updated_at.to_string(),
+        is_deleted: 0,
+        content_hash: None,
+    }
+}
+
+fn versions(files: &[(&str, &str)]) -> BTreeMap<String, String> {
+    files
+        .iter()
+        .map(|(name, version)| ((*name).to_string(), (*version).to_string()))
+        .collect()
+}
+
+#[test]
+fn test_manifest_round_trip() {
+    let mut manifest = Manifest::default();
+    for i in 0..100 {
+        let (url, record) = article_record(&article(
+            &format!("https://example.com/{i}"),
+            "2026-10-19 10:00:00",
+        ));
+        manifest.articles.insert(url, record);
+    }
+    let encoded = manifest.encode().unwrap();
+    assert!(encoded.len() < serde_json::to_vec(&manifest).unwrap().len());
+    assert_eq!(Manifest::decode(&encoded).unwrap(), manifest);
+
+    let future = Manifest {
+        version: 2,
+        ..Manifest::default()
+    };
+    assert!(Manifest::decode(&future.encode().unwrap()).is_err());
+}
+
+#[test]
+fn test_article_record() {
+    let mut deleted = article("https://example.com/a", "2026-10-19 10:00:00");
+    deleted.is_deleted = 1;
+    deleted.content_hash = Some("abc".to_string());
+    let (url, record) = article_record(&deleted);
+    assert_eq!(url, "https://example.com/a");
+    assert!(record.is_deleted);
+    assert_eq!(record.updated_at, "2026-10-19 10:00:00");
+    assert_eq!(record.value.content_hash.as_deref(), Some("abc"));
+}
+
+#[test]
+fn test_legacy_records() {
+    let mut manifest = Manifest::default();
+    let files = versions(&[("a.json", "1"), ("b.json", "1")]);
+    assert_eq!(manifest.stale_legacy(&files), vec!["a.json", "b.json"]);
+
+    let newer = article("https://example.com/a", "2026-10-19 10:00:00");
+    let older = article("https://example.com/a", "2026-10-19 09:00:00");
+    manifest.merge_legacy("a.json", "1", &newer);
+    manifest.merge_legacy("b.json", "1", &older);
+    assert_eq!(
+        manifest.articles["https://example.com/a"].updated_at,
+        "2026-10-19 10:00:00"
+    );
+
+    // Unchanged files are skipped, removed ones forgotten.
+    let files = versions(&[("a.json", "2"), ("c.json", "1")]);
+    assert_eq!(manifest.stale_legacy(&files), vec!["a.json", "c.json"]);
+    assert_eq!(manifest.legacy, versions(&[("a.json", "1")]));
+}
+
+#[test]
+fn test_unreadable_legacy_records() {
+    let mut manifest = Manifest::default();
+    let record =
+        serde_json::to_vec(&article("https://example.com/a", "2026-10-19 10:00:00")).unwrap();
+    manifest.merge_legacy_file("a.json", "1", Some(&record));
+    // Gone, not opened, not a record.
+    manifest.merge_legacy_file("b.json", "1", None);
+    manifest.merge_legacy_file("c.json", "1", Some(b"{\"url\": 1}"));
+    assert_eq!(manifest.articles.len(), 1);
+    assert!(manifest.articles.contains_key("https://example.com/a"));
+
+    let files = versions(&[("a.json", "1"), ("b.json", "1"), ("c.json", "2")]);
+    assert_eq!(manifest.stale_legacy(&files), vec!["c.json"]);
+}
+
+#[test]
+fn test_folder_state() {
+    let legacy = versions(&[("a.json", "1")]);
+    let state = folder_state(Some("m1"), &legacy);
+    assert_eq!(state, folder_state(Some("m1"), &legacy));
+    assert_ne!(state, folder_state(Some("m2"), &legacy));
+    assert_ne!(state, folder_state(None, &legacy));
+    assert_ne!(
+        state,
+        folder_state(Some("m1"), &versions(&[("a.json", "2")]))
+    );
+    assert_ne!(state, folder_state(Some("m1"), &BTreeMap::new()));
+}
+
+#[test]
+fn test_merge_articles_content() {
+    let record = |hash: Option<&str>| {
+        let mut article = article("https://example.com/a", "2026-10-19 10:00:00");
+        article.content_hash = hash.map(str::to_string);
+        article_record(&article).1
+    };
+    let set = |record: SyncedRecord<ArticleEntry>| -> RecordSet<ArticleEntry> {
+        [("https://example.com/a".to_string(), record)]
+            .into_iter()
+            .collect()
+    };
+
+    // The same edit, uploaded by a device without content: push ours.
+    let merged = merge_articles(&set(record(Some("abc"))), &set(record(None)));
+    assert!(merged.pull.is_empty());
+    assert_eq!(merged.push, vec!["https://example.com/a"]);
+    assert_eq!(merged.records, set(record(Some("abc"))));
+
+    // Different content of the same edit: the remote one wins.
+    let merged = merge_articles(&set(record(Some("abc"))), &set(record(Some("def"))));
+    assert_eq!(merged.pull, set(record(Some("def"))));
+    assert!(merged.push.is_empty());
+
+    // No content here: take the remote one.
+    let merged = merge_articles(&set(record(None)), &set(record(Some("def"))));
+    assert_eq!(merged.pull, set(record(Some("def"))));
+    assert!(merged.push.is_empty());
+}
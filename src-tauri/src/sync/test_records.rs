@@ -0,0 +1,134 @@
+use super::*;
+use crate::models::PronunciationRule;
+
+fn record(updated_at: &str, value: &str) -> SyncedRecord<String> {
+    SyncedRecord {
+        updated_at: updated_at.to_string(),
+        is_deleted: false,
+        value: value.to_string(),
+    }
+}
+
+fn set(records: &[(&str, SyncedRecord<String>)]) -> RecordSet<String> {
+    records
+        .iter()
+        .map(|(name, record)| ((*name).to_string(), record.clone()))
+        .collect()
+}
+
+#[test]
+fn test_merge_keeps_newer_records() {
+    let local = set(&[
+        ("theme", record("2026-10-19 10:00:00", "dark")),
+        ("rate", record("2026-10-19 08:00:00", "1.0")),
+        ("fetcher_mode", record("2026-10-19 08:00:00", "browser")),
+    ]);
+    let remote = set(&[
+        ("theme", record("2026-10-19 09:00:00", "light")),
+        ("rate", record("2026-10-19 11:00:00", "1.5")),
+        ("voice_id", record("2026-10-19 07:00:00", "en-1")),
+    ]);
+    let merged = merge(&local, &remote);
+    assert_eq!(
+        merged.records,
+        set(&[
+            ("theme", record("2026-10-19 10:00:00", "dark")),
+            ("rate", record("2026-10-19 11:00:00", "1.5")),
+            ("fetcher_mode", record("2026-10-19 08:00:00", "browser")),
+            ("voice_id", record("2026-10-19 07:00:00", "en-1")),
+        ])
+    );
+    assert_eq!(
+        merged.pull,
+        set(&[
+            ("rate", record("2026-10-19 11:00:00", "1.5")),
+            ("voice_id", record("2026-10-19 07:00:00", "en-1")),
+        ])
+    );
+    assert_eq!(merged.push, vec!["fetcher_mode", "theme"]);
+}
+
+#[test]
+fn test_merge_in_sync() {
+    let records = set(&[("theme", record("2026-10-19 10:00:00", "dark"))]);
+    let merged = merge(&records, &records);
+    assert!(merged.pull.is_empty());
+    assert!(merged.push.is_empty());
+
+    // Same time, different values: both devices take the remote one.
+    let local = set(&[("theme", record("2026-10-19 10:00:00", "light"))]);
+    let merged = merge(&local, &records);
+    assert_eq!(merged.pull, records);
+    assert!(merged.push.is_empty());
+}
+
+#[test]
+fn test_merge_deletions() {
+    let mut deleted = record("2026-10-19 12:00:00", "old");
+    deleted.is_deleted = true;
+    let local = set(&[("colour", record("2026-10-19 10:00:00", "color"))]);
+    let remote = set(&[("colour", deleted.clone()), ("gone", deleted.clone())]);
+    let merged = merge(&local, &remote);
+    // Unknown tombstones are kept remotely but need nothing locally.
+    assert_eq!(merged.pull, set(&[("colour", deleted)]));
+    assert!(merged.push.is_empty());
+}
+
+#[test]
+fn test_synced_settings() {
+    let remote = set(&[
+        ("theme", record("2026-10-19 10:00:00", "dark")),
+        ("webdavPassword", record("2026-10-19 10:00:00", "secret")),
+        ("voice_id", record("2026-10-19 10:00:00", "en-1")),
+    ]);
+    assert_eq!(
+        synced_settings(remote).into_keys().collect::<Vec<_>>(),
+        vec!["theme"]
+    );
+}
+
+fn rule(pattern: &str, language: &str, replacement: &str) -> PronunciationRule {
+    PronunciationRule {
+        match_pattern: pattern.to_string(),
+        replacement: replacement.to_string(),
+        is_regex: false,
+        priority: 0,
+        enabled: true,
+        language: language.to_string(),
+        domain: String::new(),
+        scope: String::new(),
+        whole_word: false,
+        case_insensitive: false,
+        preserve_case: false,
+        phoneme: String::new(),
+    }
+}
+
+#[test]
+fn test_rules_keyed_by_scope() {
+    let english = rule("GIF", "en", "jif");
+    let german = rule("GIF", "de", "gif");
+    assert_ne!(rule_key(&english), rule_key(&german));
+    assert_eq!(rule_key(&english), rule_key(&rule("GIF", "en", "gif")));
+
+    // Pattern-only names from older folders, the newer copy wins.
+    let record = |updated_at: &str, rule: &PronunciationRule| SyncedRecord {
+        updated_at: updated_at.to_string(),
+        is_deleted: false,
+        value: rule.clone(),
+    };
+    let legacy: RecordSet<PronunciationRule> = [
+        ("GIF".to_string(), record("2026-10-19 09:00:00", &english)),
+        (
+            rule_key(&english),
+            record("2026-10-19 08:00:00", &rule("GIF", "en", "old")),
+        ),
+        ("gif-de".to_string(), record("2026-10-19 08:00:00", &german)),
+    ]
+    .into_iter()
+    .collect();
+    let keyed = keyed_rules(legacy);
+    assert_eq!(keyed.len(), 2);
+    assert_eq!(keyed[&rule_key(&english)].value.replacement, "jif");
+    assert_eq!(keyed[&rule_key(&german)].value.replacement, "gif");
+}
@@ -0,0 +1,100 @@
+use super::*;
+use crate::models::ArticleSync;
+
+fn content() -> ArticleContent {
+    ArticleContent {
+        title: "A title".to_string(),
+        body: "<p>Some text.</p>".repeat(50),
+        text_content: "Some text.".repeat(50),
+        site_name: "Example".to_string(),
+        image_url: "https://example.com/a.png".to_string(),
+    }
+}
+
+#[test]
+fn test_names() {
+    let url = "https://example.com/article";
+    let folder = Folder::default();
+    let record = folder.record_name(url);
+    assert!(is_record_name(&record));
+    assert_eq!(record.len(), 64 + RECORD_EXTENSION.len());
+    assert_eq!(
+        folder.content_name(url).strip_suffix(CONTENT_EXTENSION),
+        record.strip_suffix(RECORD_EXTENSION)
+    );
+    assert!(!is_record_name(&folder.content_name(url)));
+    assert!(!is_record_name(KEY_FILE_NAME));
+    assert!(!is_record_name(&folder.set_name(SETTINGS_SET)));
+}
+
+#[test]
+fn test_plain_folder() {
+    let folder = Folder::default();
+    assert!(!folder.is_encrypted());
+    let sealed = folder.seal("a.json", b"{}".to_vec()).unwrap();
+    assert_eq!(sealed, b"{}");
+    assert_eq!(folder.open("a.json", sealed).unwrap(), b"{}");
+}
+
+#[test]
+fn test_content_round_trip() {
+    let content = content();
+    let encoded = content.encode().unwrap();
+    assert!(encoded.len() < content.body.len());
+    assert_eq!(ArticleContent::decode(&encoded).unwrap(), content);
+    assert!(ArticleContent::decode(b"not gzip").is_err());
+}
+
+#[test]
+fn test_content_hash() {
+    let content = content();
+    assert_eq!(content.hash(), content.clone().hash());
+    let mut moved = content.clone();
+    moved.title = "A titl".to_string();
+    moved.body.insert(0, 'e');
+    assert_ne!(moved.hash(), content.hash());
+    assert_eq!(ArticleContent::default().hash(), None);
+}
+
+#[test]
+fn test_needs_content() {
+    assert!(needs_content(None, Some("a")));
+    assert!(needs_content(Some("b"), Some("a")));
+    assert!(!needs_content(Some("a"), Some("a")));
+    assert!(!needs_content(Some("a"), None));
+}
+
+#[test]
+fn test_plain_files() {
+    let folder = Folder::default();
+    let url = "https://example.com/article";
+    let mut manifest = Manifest::default();
+    let (_, record) = article_record(&ArticleSync {
+        url: url.to_string(),
+        created_at: "2026-10-01 08:00:00".to_string(),
+        updated_at: "2026-10-19 10:00:00".to_string(),
+        is_deleted: 0,
+        content_hash: Some("abc".to_string()),
+    });
+    manifest.articles.insert(url.to_string(), record);
+
+    let names = vec![
+        folder.record_name(url),
+        folder.set_name(MANIFEST_SET),
+        folder.set_name(SETTINGS_SET),
+        folder.content_name(url),
+        "notes.txt".to_string(),
+    ];
+    assert_eq!(
+        plain_files(&names, Some(&manifest)).unwrap(),
+        names[..4].to_vec()
+    );
+
+    // Files keyed with a passphrase: the key file went missing.
+    let orphan = folder.content_name("https://example.com/other");
+    assert!(plain_files(&[orphan], Some(&manifest)).is_err());
+    assert!(plain_files(&[folder.content_name(url)], None).is_err());
+    let key = SyncKey::create("passphrase").unwrap().0;
+    let encrypted = Folder::encrypted(key).set_name(MANIFEST_SET);
+    assert!(plain_files(&[encrypted], None).is_err());
+}
@@ -13,3 +13,4 @@ export { default as ConfirmModal } from './ConfirmModal.vue'
 export { default as DataTransferButton } from './DataTransferButton.vue'
 export { default as TutorialSpeakBar } from './TutorialSpeakBar.vue'
 export { default as WebdavSettings } from './WebdavSettings.vue'
+export { default as SleepTimer } from './SleepTimer.vue'
@@ -4,3 +4,4 @@ export * from './useClipboard'
 export * from './useShare'
 export * from './useTTS'
 export * from './useMediaSession'
+export * from './useAudioFocus'
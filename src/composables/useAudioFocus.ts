@@ -0,0 +1,35 @@
+/*
+ * Audio focus and output tracking of the Android app, see `AudioTracker`.
+ * Other platforms have neither, so holding the focus does nothing there.
+ */
+
+export type AudioChange = 'focus-lost' | 'focus-gained' | 'output-lost'
+
+const AUDIO_EVENT = 'read-later:audio'
+
+interface AndroidAudio {
+  acquire(): void
+  release(): void
+}
+
+declare global {
+  interface Window {
+    AndroidAudio?: AndroidAudio
+  }
+}
+
+/** Holds the audio focus while reading, or while waiting to resume. */
+export function holdAudioFocus(hold: boolean) {
+  if (hold) {
+    window.AndroidAudio?.acquire()
+  }
+  else {
+    window.AndroidAudio?.release()
+  }
+}
+
+export function onAudioChange(handler: (change: AudioChange) => void): () => void {
+  const listener = (event: Event) => handler((event as CustomEvent<AudioChange>).detail)
+  window.addEventListener(AUDIO_EVENT, listener)
+  return () => window.removeEventListener(AUDIO_EVENT, listener)
+}
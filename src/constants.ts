@@ -21,6 +21,27 @@ export const RATE = 'rate'
 
 export const FONT_SCALE = 'fontScale'
 
+export const NORMALIZE_PREFIX = 'normalize_'
+
+export const SSML_PHONEMES = 'ssmlPhonemes'
+
+export const QUEUE_AUTO_DELETE = 'queueAutoDelete'
+export const MEDIA_SKIP_UNIT = 'mediaSkipUnit'
+
+export const SPEECH_PITCH = 'speech_pitch'
+export const SPEECH_VOLUME = 'speech_volume'
+export const SPEECH_PAUSE_SENTENCE = 'speech_pause_sentence'
+export const SPEECH_PAUSE_HEADING = 'speech_pause_heading'
+export const SPEECH_PAUSE_BLOCK = 'speech_pause_block'
+export const SPEECH_AUTO_RESUME = 'speech_auto_resume'
+
+export const READING_CODE = 'readingCode'
+export const READING_TABLE = 'readingTable'
+export const READING_CAPTIONS = 'readingCaptions'
+export const READING_IMAGES = 'readingImages'
+export const READING_LINKS = 'readingLinks'
+export const READING_FOOTNOTES = 'readingFootnotes'
+
 // Webdav settings
 export const WEBDAV_ENABLED = 'webdavEnabled'
 export const WEBDAV_URL = 'webdavUrl'
@@ -29,3 +50,5 @@ export const WEBDAV_PASSWORD = 'webdavPassword'
 export const WEBDAV_PATH = 'webdavPath'
 export const LAST_SYNCED_AT = 'lastSyncedAt'
 export const WEBDAV_AUTH_TYPE = 'webdavAuthType'
+export const WEBDAV_SYNC_CONTENT = 'webdavSyncContent'
+export const WEBDAV_PASSPHRASE = 'webdavPassphrase'
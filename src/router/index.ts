@@ -5,6 +5,9 @@ const ArticleDetail = () => import('../pages/ArticleDetail.vue')
 const Settings = () => import('../pages/Settings.vue')
 const AddArticle = () => import('../pages/AddArticle.vue')
 const PronunciationRules = () => import('../pages/PronunciationRules.vue')
+const Queue = () => import('../pages/Queue.vue')
+const VoiceProfiles = () => import('../pages/VoiceProfiles.vue')
+const Stats = () => import('../pages/Stats.vue')
 
 const routes = [
   {
@@ -40,6 +43,21 @@ const routes = [
     name: 'pronunciationRules',
     component: PronunciationRules,
   },
+  {
+    path: '/queue',
+    name: 'queue',
+    component: Queue,
+  },
+  {
+    path: '/voice-profiles',
+    name: 'voiceProfiles',
+    component: VoiceProfiles,
+  },
+  {
+    path: '/stats',
+    name: 'stats',
+    component: Stats,
+  },
 ]
 
 const router = createRouter({
@@ -3,11 +3,21 @@ export interface ArticleId {
   id: number
 }
 
+export interface TocEntry {
+  level: number
+  text: string
+  para: number
+  anchor: string
+}
+
 export interface Article {
   id: number
   title: string
   body: string
   url: string
+  site_name?: string
+  image_url?: string
+  toc?: TocEntry[]
 }
 
 export interface Snippet {
@@ -50,3 +60,102 @@ export interface LocaleContext {
   locales: Ref<string[]>
   updateLocale: (locale: string) => Promise<void>
 }
+
+export type SleepTimer
+  = | { kind: 'minutes', minutes: number }
+    | { kind: 'end_of_paragraph' }
+    | { kind: 'end_of_article' }
+
+export interface SleepTimerInfo {
+  timer: SleepTimer
+  remaining_secs: number | null
+}
+
+export interface VoiceProfile {
+  id: number
+  name: string
+  voice_id: string | null
+  rate: number
+  pitch: number
+  volume: number
+  language: string
+}
+
+export type StatsPeriod = 'day' | 'week'
+
+export interface TimeStat {
+  period: string
+  listened_secs: number
+  read_secs: number
+}
+
+export interface CountStat {
+  period: string
+  count: number
+}
+
+export interface BacklogPoint {
+  day: string
+  size: number
+}
+
+export interface SiteStat {
+  site: string
+  secs: number
+  sessions: number
+  finished: number
+}
+
+export type Playback = 'idle' | 'playing' | 'paused' | 'finished'
+
+/** Why playback paused without being asked to. */
+export type PauseReason = 'error' | 'interrupted' | 'unplugged'
+
+export interface ReadState {
+  mode: 'view' | 'reader'
+  playback: Playback
+  pause_reason: PauseReason | null
+  position: number
+  sleep_timer: SleepTimerInfo | null
+  voice_profile: VoiceProfile | null
+  word: WordRange | null
+}
+
+/** UTF-16 offsets into the trimmed text of a `tts_para` unit. */
+export interface WordRange {
+  start: number
+  end: number
+}
+
+export interface WordChanged {
+  position: number
+  word: WordRange
+}
+
+export interface StateChanged {
+  position: number | null
+  mode: 'view' | 'reader'
+  sleep_timer: SleepTimerInfo | null
+  pause_reason: PauseReason | null
+}
+
+export interface QueueEntry {
+  article_id: number
+  title: string
+  url: string
+}
+
+export interface ArticleChanged {
+  article_id: number | null
+}
+
+export type SkipUnit = 'sentence' | 'block' | 'heading' | 'time'
+
+export type MediaSkipUnit = 'article' | SkipUnit
+
+export type AudioFormat = 'wav' | 'ogg' | 'mp3'
+
+export interface ExportProgress {
+  count_processed: number
+  total_count: number
+}